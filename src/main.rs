@@ -5,23 +5,48 @@
 
 */
 
+// The code generated by slint 1.4 defines impl blocks inside of functions which newer compilers warn about
+#![allow(non_local_definitions)]
+// The item tree of the generated window is a const array that grows with every view
+#![allow(clippy::large_const_arrays)]
+
 mod models;
 mod utils;
 
-use std::sync::{Arc, Mutex};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::Arc;
+
+use chrono::{Local, NaiveDate};
+use tokio::sync::Mutex;
 
 use models::client::Client as r_Client;
+use models::conflict::Conflict;
+use models::date;
+use models::money::{self, Money, VatRate};
 use models::error::Error;
 use models::material::Material as r_Material;
 use models::material_type::MaterialType as r_MaterialType;
-use models::project::Project as r_Project;
+use models::project::{Project as r_Project, ProjectStatus};
 use models::project_material::ProjectMaterial as r_ProjectMaterial;
+use models::purchase_order::{OrderLine, OrderStatus, PurchaseOrder};
+use models::goods_receipt::{GoodsReceipt, ReceiptLine};
+use models::stock_movement::{self, MovementReason, StockMovement};
+use models::supplier::{Supplier, SupplierMaterial};
+use models::user_session::{Permission, UserSession};
 
 use slint::SharedString;
-use slint::{ModelRc, VecModel};
+use slint::{Model, ModelRc, VecModel};
 
+use crate::utils::config::Config;
+use crate::utils::filter::{Filter, FilteredList};
 use crate::utils::keycloak_service::Keycloak;
-use crate::utils::workshop_service::WorkshopService;
+use crate::utils::reorder::{self, ReorderData, ReorderGroup};
+use crate::utils::runtime;
+use crate::utils::validation::{self, FieldErrors};
+use crate::utils::schedule;
+use crate::utils::view_models::{self, DomainGraph};
+use crate::utils::workshop_service::{not_restored, Collections, Revisions, SyncState, WorkshopService};
 
 // Import the slint modules
 slint::include_modules!();
@@ -33,26 +58,124 @@ fn main() -> Result<(), slint::PlatformError> {
 
     let ui = WorkshopClient::new()?;
 
+    // Load the configuration, if it is broken we fall back to the built in profiles and show the error
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            ui.global::<Backend>().set_login_error(e.to_string().into());
+            Config::default()
+        }
+    };
+    let profile = config
+        .profile(config.default_profile())
+        .cloned()
+        .unwrap_or_default();
+
     // Need to use the services as mutex arcs so that we can move and still edit the memory
-    let arc_workshop_service: Arc<Mutex<WorkshopService>> =
-        Arc::new(Mutex::new(WorkshopService::new()));
-    let arc_keycloak: Arc<Mutex<Keycloak>> = Arc::new(Mutex::new(Keycloak::new()));
+    let workshop_service = WorkshopService::new(&profile);
+    let mut sync_state = workshop_service.sync_state();
+    let arc_workshop_service: Arc<Mutex<WorkshopService>> = Arc::new(Mutex::new(workshop_service));
+    let arc_keycloak: Arc<Mutex<Keycloak>> = Arc::new(Mutex::new(Keycloak::new(&profile)));
+
+    // Lists with a search, the UI only gets the part of them that matches the search
+    let project_list: FilteredList<ProjectModel> = FilteredList::new(project_matches);
+    let material_list: FilteredList<Material> = FilteredList::new(material_matches);
+    let picker_list: FilteredList<Material> = FilteredList::new(material_matches);
+    ui.global::<Backend>().set_allProjects(project_list.model());
+    // The project management starts with the projects that are not closed
+    let status_filters: Vec<SharedString> = ["Open", "All"]
+        .into_iter()
+        .chain(ProjectStatus::ALL.iter().map(|s| s.label()))
+        .map(SharedString::from)
+        .collect();
+    ui.global::<Backend>().set_projectStatusFilters(ModelRc::new(VecModel::from(status_filters)));
+    ui.global::<Backend>().set_projectStatusFilterIndex(0);
+    project_list.set_scope(project_scope(0));
+    ui.global::<Backend>().set_materials(material_list.model());
+    ui.global::<Backend>().set_pickerMaterials(picker_list.model());
+    let client_list: FilteredList<ClientModel> = FilteredList::new(client_matches);
+    let type_list: FilteredList<MaterialTypeUsage> = FilteredList::new(material_type_matches);
+    ui.global::<Backend>().set_allClients(client_list.model());
+    ui.global::<Backend>().set_allMaterialTypes(type_list.model());
+    // The revisions the lists were built from, a list is only built again when its collections changed
+    let shown: Rc<Cell<Shown>> = Rc::new(Cell::new(Shown::default()));
+    let supplier_list: FilteredList<SupplierModel> = FilteredList::new(supplier_matches);
+    ui.global::<Backend>().set_allSuppliers(supplier_list.model());
+    // The offers of all suppliers, the materials of the chosen supplier are taken from it
+    let supplier_offers: Rc<RefCell<Vec<SupplierMaterial>>> = Rc::new(RefCell::new(Vec::new()));
+    let order_list: FilteredList<PurchaseOrderModel> = FilteredList::new(purchase_order_matches);
+    ui.global::<Backend>().set_allPurchaseOrders(order_list.model());
+    // The orders as the API sent them, a delivery against an order starts with its open lines
+    let purchase_orders: Rc<RefCell<Vec<PurchaseOrder>>> = Rc::new(RefCell::new(Vec::new()));
+    // The suggestions of the reorder view, an order is written from the group of a supplier
+    let reorder_groups: Rc<RefCell<Vec<ReorderGroup>>> = Rc::new(RefCell::new(Vec::new()));
+    // Everything the client management has loaded, the projects of the chosen client are taken from it
+    let client_graph: Rc<RefCell<DomainGraph>> = Rc::new(RefCell::new(DomainGraph::default()));
+    // All projects while the project form is open, the schedule of the form is checked against them
+    let schedule_projects: Rc<RefCell<Vec<r_Project>>> = Rc::new(RefCell::new(Vec::new()));
+
+    // The change of the outbox that waits for the decision of the user
+    let current_conflict: Rc<RefCell<Option<Conflict>>> = Rc::new(RefCell::new(None));
+
+    // Show in the appbar when the data comes from the local cache because the server can not be reached
+    let offline_indicator = slint::spawn_local({
+        let ui_handle = ui.as_weak();
+        async move {
+            let mut last = SyncState::default();
+            while sync_state.changed().await.is_ok() {
+                let state = *sync_state.borrow_and_update();
+                let ui = ui_handle.unwrap();
+                ui.global::<Backend>().set_syncMessage(sync_message(state).into());
+                // The server can be reached again, send what was changed in the meantime
+                if last.offline_since.is_some() && state.offline_since.is_none() && state.pending_changes > 0 {
+                    ui.global::<Backend>().invoke_replay_outbox();
+                }
+                last = state;
+            }
+        }
+    });
+    if let Err(e) = offline_indicator {
+        eprintln!("Failed to start the offline indicator: {}", e);
+    }
+
+    // Offer all profiles on the login view
+    let profile_names: Vec<SharedString> = config
+        .profile_names()
+        .into_iter()
+        .map(SharedString::from)
+        .collect();
+    ui.global::<Backend>()
+        .set_profiles(ModelRc::new(VecModel::from(profile_names)));
+    ui.global::<Backend>()
+        .set_profile(config.default_profile().into());
+
+    // The lines of the delivery that is entered on the goods receipt view
+    let receipt_lines: Rc<RefCell<Vec<ReceiptLine>>> = Rc::new(RefCell::new(Vec::new()));
+    let valuation = config.valuation();
+    ui.global::<Backend>().set_valuationName(valuation.label().into());
+    let weekly_capacity = config.weekly_capacity();
+    let vat_rate = config.vat_rate();
+    money::set_currency(config.currency());
+
+    // Reasons of the stock movements that can be booked by hand
+    let reason_names: Vec<SharedString> = MovementReason::MANUAL
+        .iter()
+        .map(|reason| SharedString::from(reason.label()))
+        .collect();
+    ui.global::<Backend>()
+        .set_movementReasons(ModelRc::new(VecModel::from(reason_names)));
+    ui.global::<Backend>().set_movementReasonName(MovementReason::MANUAL[0].label().into());
 
     // Set the inital state of the window
-    ui.global::<Backend>().set_loginView(true);
-    ui.global::<Backend>().set_projectView(false);
-    ui.global::<Backend>().set_projectManagementView(false);
-    ui.global::<Backend>().set_projectDetailView(false);
-    ui.global::<Backend>().set_lagerOverviewView(false);
-    ui.global::<Backend>().set_showClientPopUp(false);
-    ui.global::<Backend>().set_showMaterialPopUp(false);
-    ui.global::<Backend>().set_showMaterialTypePopUp(false);
+    show_view(&ui, View::Login);
 
     // Login action
     ui.global::<Backend>().on_request_login({
         // Get the handlers that we need to manipulate the UI and Keycloak
         let ui_handle = ui.as_weak();
         let keycloak_handle = arc_keycloak.clone();
+        let workshop_handle = arc_workshop_service.clone();
+        let config = config.clone();
         move || {
             let ui = ui_handle.unwrap();
             let user = ui.global::<Backend>().get_username();
@@ -65,28 +188,108 @@ fn main() -> Result<(), slint::PlatformError> {
                 return;
             }
 
-            // Attempt to login the user and get a token in return
-            let token = keycloak_handle.lock().unwrap().login_user(&user, &password);
-
-            // Check if the token was successfully retrieved otherwise handle the error
-            match token {
-                Err(e) => {
-                    ui.global::<Backend>().set_login_error(e.to_string().into());
+            let profile_name = ui.global::<Backend>().get_profile();
+            let profile = match config.profile(&profile_name) {
+                Some(profile) => profile.clone(),
+                None => {
+                    ui.global::<Backend>()
+                        .set_login_error(format!("Unknown profile '{}'", profile_name).into());
+                    return;
                 }
-                Ok(token) => {
-                    keycloak_handle.lock().unwrap().set_token(token);
-                    keycloak_handle
-                        .lock()
-                        .unwrap()
-                        .set_username(user.to_string());
-                    keycloak_handle
-                        .lock()
-                        .unwrap()
-                        .set_password(password.to_string());
-                    ui.global::<Backend>().invoke_route_to_project_view();
-                    ui.global::<Backend>().set_login_error("".into());
+            };
+
+            let ui_handle = ui_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let user = user.to_string();
+            let password = password.to_string();
+            runtime::spawn(
+                async move {
+                    // Point both services to the selected profile
+                    workshop_handle.lock().await.set_profile(&profile);
+                    let mut keycloak = keycloak_handle.lock().await;
+                    keycloak.set_profile(&profile);
+                    // Attempt to login the user, keycloak keeps the tokens but not the password
+                    keycloak.login_user(&user, &password).await?;
+                    Ok::<_, Error>(keycloak.session().clone())
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the token was successfully retrieved otherwise handle the error
+                    match result {
+                        Err(e) => {
+                            ui.global::<Backend>().set_login_error(e.to_string().into());
+                        }
+                        Ok(session) => {
+                            show_permissions(&ui, &session);
+                            ui.global::<Backend>().invoke_route_to_project_view();
+                            // Changes from an earlier offline session may still wait for the server
+                            ui.global::<Backend>().invoke_replay_outbox();
+                            ui.global::<Backend>().set_login_error("".into());
+                            ui.global::<Backend>().set_password("".into());
+                        }
+                    }
+                },
+            );
+        }
+    });
+
+    // Login in the system browser
+    ui.global::<Backend>().on_request_browser_login({
+        let ui_handle = ui.as_weak();
+        let keycloak_handle = arc_keycloak.clone();
+        let workshop_handle = arc_workshop_service.clone();
+        let config = config.clone();
+        move || {
+            let ui = ui_handle.unwrap();
+            let profile_name = ui.global::<Backend>().get_profile();
+            let profile = match config.profile(&profile_name) {
+                Some(profile) => profile.clone(),
+                None => {
+                    ui.global::<Backend>()
+                        .set_login_error(format!("Unknown profile '{}'", profile_name).into());
+                    return;
                 }
-            }
+            };
+            ui.global::<Backend>()
+                .set_login_error("Please continue the login in your browser.".into());
+
+            let ui_handle = ui_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            runtime::spawn(
+                async move {
+                    // Point both services to the selected profile
+                    workshop_handle.lock().await.set_profile(&profile);
+                    let login = {
+                        let mut keycloak = keycloak_handle.lock().await;
+                        keycloak.set_profile(&profile);
+                        keycloak.browser_login().await?
+                    };
+                    login.open_browser();
+                    // Wait until keycloak sends the browser back to us, the lock is only taken again for the tokens
+                    let tokens = login.finish().await?;
+                    let mut keycloak = keycloak_handle.lock().await;
+                    keycloak.complete_login(tokens)?;
+                    Ok::<_, Error>(keycloak.session().clone())
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the token was successfully retrieved otherwise handle the error
+                    match result {
+                        Err(e) => {
+                            ui.global::<Backend>().set_login_error(e.to_string().into());
+                        }
+                        Ok(session) => {
+                            show_permissions(&ui, &session);
+                            ui.global::<Backend>().invoke_route_to_project_view();
+                            // Changes from an earlier offline session may still wait for the server
+                            ui.global::<Backend>().invoke_replay_outbox();
+                            ui.global::<Backend>().set_login_error("".into());
+                        }
+                    }
+                },
+            );
         }
     });
 
@@ -98,11 +301,17 @@ fn main() -> Result<(), slint::PlatformError> {
         move || {
             // Basically just route to the login view and clear the token
             let ui = ui_handle.unwrap();
-            ui.global::<Backend>().set_loginView(true);
-            ui.global::<Backend>().set_projectView(false);
+            show_view(&ui, View::Login);
+            show_permissions(&ui, &UserSession::default());
             ui.global::<Backend>().set_username("".into());
             ui.global::<Backend>().set_password("".into());
-            keycloak_handle.lock().unwrap().clear();
+            let keycloak_handle = keycloak_handle.clone();
+            runtime::spawn(
+                async move {
+                    keycloak_handle.lock().await.clear();
+                },
+                |_| {},
+            );
         }
     });
 
@@ -111,163 +320,47 @@ fn main() -> Result<(), slint::PlatformError> {
         let ui_handle = ui.as_weak();
         let keycloak_handle = arc_keycloak.clone();
         let workshop_handle = arc_workshop_service.clone();
+        let project_list = project_list.clone();
+        let shown = shown.clone();
         move || {
             let ui = ui_handle.unwrap();
-            ui.global::<Backend>().set_projectView(false);
-            ui.global::<Backend>().set_projectManagementView(true);
-            ui.global::<Backend>().set_projectDetailView(false);
-            ui.global::<Backend>().set_lagerOverviewView(false);
-            ui.global::<Backend>().set_showClientPopUp(false);
-            ui.global::<Backend>().set_showMaterialPopUp(false);
-            ui.global::<Backend>().set_showMaterialTypePopUp(false);
-            // Refresh the token
-            let token = keycloak_handle.lock().unwrap().refresh_token();
-            // Check if the token was successfully retrieved otherwise handle the error
-            let token = match token {
-                Err(e) => {
-                    ui.global::<Backend>().set_projectManagementErrorMessage(e.to_string().into());
-                    return;
-                }
-                Ok(token) => token,
-            };
-            let projects = workshop_handle.lock().unwrap().get_projects(&token);
-            // Check if the projects were successfully retrieved otherwise handle the error
-            let projects = match projects {
-                Err(e) => {
-                    ui.global::<Backend>().set_projectManagementErrorMessage(e.to_string().into());
-                    return;
-                }
-                Ok(projects) => projects,
-            };
-            // Get the clients and materials for each project
-            let project_materials = match workshop_handle
-                .lock()
-                .unwrap()
-                .get_project_materials(&token)
-            {
-                Ok(project_materials) => project_materials,
-                Err(e) => {
-                    ui.global::<Backend>().set_projectManagementErrorMessage(e.to_string().into());
-                    return;
-                }
-            };
-            let clients = match workshop_handle.lock().unwrap().get_clients(&token) {
-                Ok(clients) => clients,
-                Err(e) => {
-                    ui.global::<Backend>().set_projectManagementErrorMessage(e.to_string().into());
-                    return;
-                }
-            };
-            let materials = match workshop_handle.lock().unwrap().get_materials(&token) {
-                Ok(materials) => materials,
-                Err(e) => {
-                    ui.global::<Backend>().set_projectManagementErrorMessage(e.to_string().into());
-                    return;
-                }
-            };
-            let material_types = match workshop_handle.lock().unwrap().get_material_types(&token) {
-                Ok(material_types) => material_types,
-                Err(e) => {
-                    ui.global::<Backend>().set_projectManagementErrorMessage(e.to_string().into());
-                    return;
-                }
-            };
-
-            let mut slint_projects: Vec<ProjectModel> = projects
-                .iter()
-                .map(|p| {
-                    // Get the client for the project
-                    let client: Result<r_Client, Error> =
-                        match clients.iter().find(|c| c.id == p.client_id) {
-                            Some(client) => Ok(client.clone()),
-                            None => Err(Error::new("Client not found".to_owned(), 404)),
-                        };
-
-                    let client: ClientModel = match client {
-                        Ok(client) => ClientModel {
-                            id: client.id,
-                            firstName: client.firstname.clone().into(),
-                            lastName: client.lastname.clone().into(),
-                            phone: client.phone.clone().into(),
-                        },
+            show_view(&ui, View::ProjectManagement);
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            let project_list = project_list.clone();
+            let shown = shown.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token
+                    let token = keycloak_handle.lock().await.refresh_token().await?;
+                    // Get the projects with their clients and materials
+                    let mut workshop = workshop_handle.lock().await;
+                    let collections = workshop.load_all(&token).await?;
+                    Ok::<_, Error>((collections, workshop.revisions()))
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the data was successfully retrieved otherwise handle the error
+                    let (collections, revisions) = match result {
                         Err(e) => {
-                            todo!();
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_projectManagementErrorMessage(e.to_string().into());
+                            return;
                         }
+                        Ok(data) => data,
                     };
-
-                    let project_materials = project_materials
-                        .iter()
-                        .filter(|m| m.project_id == p.id)
-                        .collect::<Vec<&r_ProjectMaterial>>();
-
-                    let materials: Vec<Material> = project_materials
-                        .iter()
-                        .map(|m| {
-                            let material: Result<r_Material, Error> =
-                                match materials.iter().find(|mat| mat.id == m.material_id) {
-                                    Some(material) => Ok(material.clone()),
-                                    None => Err(Error::new("Material not found".to_owned(), 404)),
-                                };
-
-                            let material = match material {
-                                Ok(material) => {
-                                    let m_type: Result<r_MaterialType, Error> = match material_types
-                                        .iter()
-                                        .find(|t| t.id == material.type_id)
-                                    {
-                                        Some(m_type) => Ok(m_type.clone()),
-                                        None => Err(Error::new(
-                                            "Material type not found".to_owned(),
-                                            404,
-                                        )),
-                                    };
-
-                                    let m_type = match m_type {
-                                        Ok(m_type) => MaterialType {
-                                            id: m_type.id,
-                                            name: m_type.name.clone().into(),
-                                            description: m_type.description.clone().into(),
-                                        },
-                                        Err(e) => {
-                                            todo!()
-                                        }
-                                    };
-
-                                    Material {
-                                        id: material.id,
-                                        name: material.name.clone().into(),
-                                        description: material.description.clone().into(),
-                                        m_type,
-                                        quantity: m.amount,
-                                        price: material.costs,
-                                        threshold_value: material.threshold_value,
-                                    }
-                                }
-                                Err(e) => {
-                                    todo!()
-                                }
-                            };
-                            material
-                        })
-                        .collect();
-
-                    ProjectModel {
-                        id: p.id,
-                        name: p.name.clone().into(),
-                        client,
-                        mats: ModelRc::new(VecModel::from(materials)),
-                        calculated_costs: p.estimated_costs,
-                        current_costs: p.costs,
-                        project_deadline: p.endpoint.clone().into(),
-                        project_start: p.startpoint.clone().into(),
+                    ui.global::<Backend>().set_projectManagementErrorMessage("".into());
+                    if !revisions.changes_since(&shown.get().projects).projects_view() {
+                        return;
                     }
-                })
-                .collect();
-            // Sort the projects by the deadline
-            slint_projects.sort_by(|a, b| a.project_deadline.cmp(&b.project_deadline));
 
-            ui.global::<Backend>()
-                .set_allProjects(ModelRc::new(VecModel::from(slint_projects)));
+                    project_list.set_items(DomainGraph::new(collections).projects());
+                    shown.set(Shown { projects: revisions, ..shown.get() });
+                },
+            );
         }
     });
 
@@ -278,306 +371,211 @@ fn main() -> Result<(), slint::PlatformError> {
         let keycloak_handle = arc_keycloak.clone();
         move || {
             let ui = ui_handle.unwrap();
-            ui.global::<Backend>().set_loginView(false);
-            ui.global::<Backend>().set_projectView(true);
-            ui.global::<Backend>().set_projectManagementView(false);
-            ui.global::<Backend>().set_projectDetailView(false);
-            ui.global::<Backend>().set_lagerOverviewView(false);
-            ui.global::<Backend>().set_showClientPopUp(false);
-            ui.global::<Backend>().set_showMaterialPopUp(false);
-            ui.global::<Backend>().set_showMaterialTypePopUp(false);
-            // Refresh the token
-            let token = keycloak_handle.lock().unwrap().refresh_token();
-            // Check if the token was successfully retrieved otherwise handle the error
-            let token = match token {
-                Err(e) => {
-                    ui.global::<Backend>().set_projectViewErrorMessage(e.to_string().into());
-                    return;
-                }
-                Ok(token) => token,
-            };
-            let projects = workshop_handle.lock().unwrap().get_projects(&token);
-            // Check if the projects were successfully retrieved otherwise handle the error
-            let projects = match projects {
-                Err(e) => {
-                    ui.global::<Backend>().set_projectViewErrorMessage(e.to_string().into());
-                    return;
-                }
-                Ok(projects) => projects,
-            };
-            // Get the clients and materials for each project
-            let project_materials = match workshop_handle
-                .lock()
-                .unwrap()
-                .get_project_materials(&token)
-            {
-                Ok(project_materials) => project_materials,
-                Err(e) => {
-                    ui.global::<Backend>().set_projectViewErrorMessage(e.to_string().into());
-                    return;
-                }
-            };
-            let clients = match workshop_handle.lock().unwrap().get_clients(&token) {
-                Ok(clients) => clients,
-                Err(e) => {
-                    ui.global::<Backend>().set_projectViewErrorMessage(e.to_string().into());
-                    return;
-                }
-            };
-            let materials = match workshop_handle.lock().unwrap().get_materials(&token) {
-                Ok(materials) => materials,
-                Err(e) => {
-                    ui.global::<Backend>().set_projectViewErrorMessage(e.to_string().into());
-                    return;
-                }
-            };
-            let material_types = match workshop_handle.lock().unwrap().get_material_types(&token) {
-                Ok(material_types) => material_types,
-                Err(e) => {
-                    ui.global::<Backend>().set_projectViewErrorMessage(e.to_string().into());
-                    return;
-                }
-            };
-
-            let mut slint_projects: Vec<ProjectModel> = projects
-                .iter()
-                .map(|p| {
-                    // Get the client for the project
-                    let client: Result<r_Client, Error> =
-                        match clients.iter().find(|c| c.id == p.client_id) {
-                            Some(client) => Ok(client.clone()),
-                            None => Err(Error::new("Client not found".to_owned(), 404)),
-                        };
-
-                    let client: ClientModel = match client {
-                        Ok(client) => ClientModel {
-                            id: client.id,
-                            firstName: client.firstname.clone().into(),
-                            lastName: client.lastname.clone().into(),
-                            phone: client.phone.clone().into(),
-                        },
+            show_view(&ui, View::Dashboard);
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token
+                    let token = keycloak_handle.lock().await.refresh_token().await?;
+                    let mut workshop = workshop_handle.lock().await;
+                    // Get the projects with their clients and materials and the orders that could be late
+                    let collections = workshop.load_all(&token).await?;
+                    let orders = workshop.get_all::<PurchaseOrder>(&token).await?;
+                    let suppliers = workshop.get_all::<Supplier>(&token).await?;
+                    Ok::<_, Error>((collections, orders, suppliers))
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the data was successfully retrieved otherwise handle the error
+                    let (collections, orders, suppliers) = match result {
                         Err(e) => {
-                            todo!()
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_projectViewErrorMessage(e.to_string().into());
+                            return;
                         }
+                        Ok(data) => data,
                     };
-
-                    let project_materials = project_materials
-                        .iter()
-                        .filter(|m| m.project_id == p.id)
-                        .collect::<Vec<&r_ProjectMaterial>>();
-
-                    let materials: Vec<Material> = project_materials
-                        .iter()
-                        .map(|m| {
-                            let material: Result<r_Material, Error> =
-                                match materials.iter().find(|mat| mat.id == m.material_id) {
-                                    Some(material) => Ok(material.clone()),
-                                    None => Err(Error::new("Material not found".to_owned(), 404)),
-                                };
-
-                            let material = match material {
-                                Ok(material) => {
-                                    let m_type: Result<r_MaterialType, Error> = match material_types
-                                        .iter()
-                                        .find(|t| t.id == material.type_id)
-                                    {
-                                        Some(m_type) => Ok(m_type.clone()),
-                                        None => Err(Error::new(
-                                            "Material type not found".to_owned(),
-                                            404,
-                                        )),
-                                    };
-
-                                    let m_type = match m_type {
-                                        Ok(m_type) => MaterialType {
-                                            id: m_type.id,
-                                            name: m_type.name.clone().into(),
-                                            description: m_type.description.clone().into(),
-                                        },
-                                        Err(e) => {
-                                            ui.global::<Backend>().set_projectViewErrorMessage(e.to_string().into());
-                                            return Material {
-                                                id: material.id,
-                                                name: material.name.clone().into(),
-                                                description: material.description.clone().into(),
-                                                m_type: MaterialType {
-                                                    id: 0,
-                                                    name: "".into(),
-                                                    description: "".into(),
-                                                },
-                                                quantity: m.amount,
-                                                price: material.costs,
-                                                threshold_value: material.threshold_value,
-                                            };
-                                        }
-                                    };
-
-                                    Material {
-                                        id: material.id,
-                                        name: material.name.clone().into(),
-                                        description: material.description.clone().into(),
-                                        m_type,
-                                        quantity: m.amount,
-                                        price: material.costs,
-                                        threshold_value: material.threshold_value,
-                                    }
-                                }
-                                Err(e) => {
-                                    ui.global::<Backend>().set_projectViewErrorMessage(e.to_string().into());
-                                    return Material {
-                                        id: 0,
-                                        name: "".into(),
-                                        description: "".into(),
-                                        m_type: MaterialType {
-                                            id: 0,
-                                            name: "".into(),
-                                            description: "".into(),
-                                        },
-                                        quantity: 0,
-                                        price: 0.0,
-                                        threshold_value: 0,
-                                    };
-                                }
-                            };
-                            material
-                        })
+                    let materials = collections.materials.clone();
+                    let graph = DomainGraph::new(collections);
+
+                    ui.global::<Backend>()
+                        .set_recentProjects(ModelRc::new(VecModel::from(graph.open_projects())));
+                    ui.global::<Backend>()
+                        .set_materialAlerts(ModelRc::new(VecModel::from(graph.material_alerts())));
+                    let today = date::today();
+                    let mut overdue: Vec<&PurchaseOrder> = orders.iter().filter(|o| o.is_overdue(today)).collect();
+                    overdue.sort_by_key(|o| (o.expected_date, o.id));
+                    let overdue: Vec<PurchaseOrderModel> = overdue
+                        .into_iter()
+                        .map(|o| purchase_order_model(o, &suppliers, &materials, today, vat_rate))
                         .collect();
+                    ui.global::<Backend>().set_overdueOrders(ModelRc::new(VecModel::from(overdue)));
+                },
+            );
+        }
+    });
 
-                    ProjectModel {
-                        id: p.id,
-                        name: p.name.clone().into(),
-                        client,
-                        mats: ModelRc::new(VecModel::from(materials)),
-                        calculated_costs: p.estimated_costs,
-                        current_costs: p.costs,
-                        project_deadline: p.endpoint.clone().into(),
-                        project_start: p.startpoint.clone().into(),
+    // Route to lager overview
+    ui.global::<Backend>().on_route_to_warehouse_management({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        let material_list = material_list.clone();
+        let shown = shown.clone();
+        move || {
+            let ui = ui_handle.unwrap();
+            show_view(&ui, View::Warehouse);
+            ui.global::<Backend>().set_selectedMaterialId(0);
+            ui.global::<Backend>().set_stockMovements(ModelRc::default());
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            let material_list = material_list.clone();
+            let shown = shown.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token
+                    let token = keycloak_handle.lock().await.refresh_token().await?;
+                    // Get the materials and their types, unchanged collections are not sent again
+                    let mut workshop = workshop_handle.lock().await;
+                    let collections = workshop.load_all(&token).await?;
+                    Ok::<_, Error>((collections.materials, collections.material_types, workshop.revisions()))
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    let (materials, material_types, revisions): (Vec<r_Material>, Vec<r_MaterialType>, Revisions) =
+                        match result {
+                            Ok(data) => data,
+                            Err(e) => {
+                                if session_expired(&ui, &e) {
+                                    return;
+                                }
+                                ui.global::<Backend>().set_lagerOverviewErrorMessage(e.to_string().into());
+                                return;
+                            }
+                        };
+                    ui.global::<Backend>().set_lagerOverviewErrorMessage("".into());
+                    if !revisions.changes_since(&shown.get().materials).materials_view() {
+                        return;
                     }
-                })
-                .collect();
-            // Sort the projects by the deadline
-            slint_projects.sort_by(|a, b| a.project_deadline.cmp(&b.project_deadline));
+                    let graph = DomainGraph::new(Collections {
+                        materials,
+                        material_types,
+                        ..Default::default()
+                    });
+                    material_list.set_items(graph.materials());
+                    shown.set(Shown { materials: revisions, ..shown.get() });
+                },
+            );
+        }
+    });
 
-            // Finnaly set the projects
-            ui.global::<Backend>()
-                .set_recentProjects(ModelRc::new(VecModel::from(slint_projects)));
-
-            // Set the material alerts
-            let mut material_alerts: Vec<Material> = vec![];
-
-            for material in materials.iter() {
-                // Check if the material is below the threshold
-                if material.amount < material.threshold_value {
-                    // Get the material type
-                    let m_type: Result<r_MaterialType, Error> =
-                        match material_types.iter().find(|t| t.id == material.type_id) {
-                            Some(m_type) => Ok(m_type.clone()),
-                            None => Err(Error::new("Material type not found".to_owned(), 404)),
-                        };
-                    let m_type = match m_type {
-                        Ok(m_type) => MaterialType {
-                            id: m_type.id,
-                            name: m_type.name.clone().into(),
-                            description: m_type.description.clone().into(),
-                        },
+    // Route to client management
+    ui.global::<Backend>().on_route_to_client_management({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        let client_list = client_list.clone();
+        let client_graph = client_graph.clone();
+        let shown = shown.clone();
+        move || {
+            let ui = ui_handle.unwrap();
+            show_view(&ui, View::ClientManagement);
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            let client_list = client_list.clone();
+            let client_graph = client_graph.clone();
+            let shown = shown.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token
+                    let token = keycloak_handle.lock().await.refresh_token().await?;
+                    // Get the clients with their projects
+                    let mut workshop = workshop_handle.lock().await;
+                    let collections = workshop.load_all(&token).await?;
+                    Ok::<_, Error>((collections, workshop.revisions()))
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the data was successfully retrieved otherwise handle the error
+                    let (collections, revisions) = match result {
                         Err(e) => {
-                            ui.global::<Backend>().set_projectViewErrorMessage(e.to_string().into());
-                            MaterialType {
-                                id: 0,
-                                name: "".into(),
-                                description: "".into(),
+                            if session_expired(&ui, &e) {
+                                return;
                             }
+                            ui.global::<Backend>().set_clientManagementErrorMessage(e.to_string().into());
+                            return;
                         }
+                        Ok(data) => data,
                     };
+                    ui.global::<Backend>().set_clientManagementErrorMessage("".into());
+                    if !revisions.changes_since(&shown.get().clients).clients_view() {
+                        return;
+                    }
 
-                    // Add the material to the alerts
-                    material_alerts.push(Material {
-                        id: material.id,
-                        name: material.name.clone().into(),
-                        description: material.description.clone().into(),
-                        m_type,
-                        quantity: material.amount,
-                        price: material.costs,
-                        threshold_value: material.threshold_value,
-                    });
-                }
-            }
-
-            // Set the material alerts
-            ui.global::<Backend>()
-                .set_materialAlerts(ModelRc::new(VecModel::from(material_alerts)));
+                    let graph = DomainGraph::new(collections);
+                    client_list.set_items(graph.clients());
+                    // The chosen client stays chosen as long as they exist
+                    show_client_projects(&ui, &graph, ui.global::<Backend>().get_selectedClientId());
+                    *client_graph.borrow_mut() = graph;
+                    shown.set(Shown { clients: revisions, ..shown.get() });
+                },
+            );
         }
     });
 
-    // Route to lager overview
-    ui.global::<Backend>().on_route_to_warehouse_management({
+    // Route to material type management
+    ui.global::<Backend>().on_route_to_material_type_management({
         let ui_handle = ui.as_weak();
         let workshop_handle = arc_workshop_service.clone();
         let keycloak_handle = arc_keycloak.clone();
+        let material_list = material_list.clone();
+        let type_list = type_list.clone();
+        let shown = shown.clone();
         move || {
             let ui = ui_handle.unwrap();
-            ui.global::<Backend>().set_projectView(false);
-            ui.global::<Backend>().set_projectManagementView(false);
-            ui.global::<Backend>().set_projectDetailView(false);
-            ui.global::<Backend>().set_lagerOverviewView(true);
-            ui.global::<Backend>().set_showClientPopUp(false);
-            ui.global::<Backend>().set_showMaterialPopUp(false);
-            ui.global::<Backend>().set_showMaterialTypePopUp(false);
-            // Refresh the token
-            let token = keycloak_handle.lock().unwrap().refresh_token();
-            let token = match token {
-                Ok(token) => token,
-                Err(e) => {
-                    ui.global::<Backend>().set_lagerOverviewErrorMessage(e.to_string().into());
-                    return;
-                }
-            };
-            // Get the materials
-            let materials: Result<Vec<r_Material>, Error> =
-                workshop_handle.lock().unwrap().get_materials(&token);
-            let materials: Vec<r_Material> = match materials {
-                Ok(materials) => materials,
-                Err(e) => {
-                    ui.global::<Backend>().set_lagerOverviewErrorMessage(e.to_string().into());
-                    return;
-                }
-            };
-            let slint_materials: Vec<Material> = materials
-                .iter()
-                .map(|m| {
-                    let m_type: Result<r_MaterialType, Error> = workshop_handle
-                        .lock()
-                        .unwrap()
-                        .get_material_type_by_id(m.type_id, &token);
-                    let m_type: MaterialType = match m_type {
-                        Ok(m_type) => MaterialType {
-                            id: m_type.id,
-                            name: m_type.name.clone().into(),
-                            description: m_type.description.clone().into(),
-                        },
+            show_view(&ui, View::MaterialTypeManagement);
+            ui.global::<Backend>().set_deletedMaterialTypeId(0);
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            let material_list = material_list.clone();
+            let type_list = type_list.clone();
+            let shown = shown.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token
+                    let token = keycloak_handle.lock().await.refresh_token().await?;
+                    // Get the types and the materials that use them
+                    let mut workshop = workshop_handle.lock().await;
+                    let collections = workshop.load_all(&token).await?;
+                    Ok::<_, Error>((collections.materials, collections.material_types, workshop.revisions()))
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    match result {
                         Err(e) => {
-                            ui.global::<Backend>().set_lagerOverviewErrorMessage(e.to_string().into());
-                            MaterialType {
-                                id: 0,
-                                name: "".into(),
-                                description: "".into(),
+                            if session_expired(&ui, &e) {
+                                return;
                             }
+                            ui.global::<Backend>().set_materialTypeManagementErrorMessage(e.to_string().into());
+                        }
+                        Ok((materials, material_types, revisions)) => {
+                            ui.global::<Backend>().set_materialTypeManagementErrorMessage("".into());
+                            if !revisions.changes_since(&shown.get().types).materials_view() {
+                                return;
+                            }
+                            show_material_types(&ui, &material_list, &type_list, materials, material_types);
+                            shown.set(Shown { materials: revisions, types: revisions, ..shown.get() });
                         }
-                    };
-
-                    Material {
-                        id: m.id,
-                        name: m.name.clone().into(),
-                        description: m.description.clone().into(),
-                        m_type,
-                        quantity: m.amount,
-                        price: m.costs,
-                        threshold_value: m.threshold_value,
                     }
-                })
-                .collect();
-            ui.global::<Backend>()
-                .set_materials(ModelRc::new(VecModel::from(slint_materials)));
+                },
+            );
         }
     });
 
@@ -586,179 +584,98 @@ fn main() -> Result<(), slint::PlatformError> {
         let ui_handle = ui.as_weak();
         let workshop_handle = arc_workshop_service.clone();
         let keycloak_handle = arc_keycloak.clone();
+        let schedule_projects = schedule_projects.clone();
         move |project_id: i32| {
             let ui = ui_handle.unwrap();
-            ui.global::<Backend>().set_projectView(false);
-            ui.global::<Backend>().set_projectManagementView(false);
-            ui.global::<Backend>().set_projectDetailView(true);
-            ui.global::<Backend>().set_lagerOverviewView(false);
-            ui.global::<Backend>().set_showClientPopUp(false);
-            ui.global::<Backend>().set_showMaterialPopUp(false);
-            ui.global::<Backend>().set_showMaterialTypePopUp(false);
-            // Refresh the token
-            let token = keycloak_handle.lock().unwrap().refresh_token();
-            // Check if the token was successfully retrieved otherwise handle the error
-            let token = match token {
-                Err(e) => {
-                    ui.global::<Backend>().set_projectDetailViewErrorMessage(e.to_string().into());
-                    return;
-                }
-                Ok(token) => token,
-            };
-            let project = workshop_handle
-                .lock()
-                .unwrap()
-                .get_project_by_id(project_id, &token);
-            // Check if the project was successfully retrieved otherwise handle the error
-            let project = match project {
-                Err(e) => {
-                    ui.global::<Backend>().set_projectDetailViewErrorMessage(e.to_string().into());
-                    return;
-                }
-                Ok(project) => project,
-            };
-
-            let client = workshop_handle
-                .lock()
-                .unwrap()
-                .get_client_by_id(project.client_id, &token);
-            // Check if the client was successfully retrieved otherwise handle the error
-            let client = match client {
-                Err(e) => {
-                    ui.global::<Backend>().set_projectDetailViewErrorMessage(e.to_string().into());
-                    return;
-                }
-                Ok(client) => client,
-            };
-
-            let project_materials = workshop_handle
-                .lock()
-                .unwrap()
-                .get_project_materials(&token);
-            // Check if the project materials were successfully retrieved otherwise handle the error
-            let project_materials = match project_materials {
-                Err(e) => {
-                    ui.global::<Backend>().set_projectDetailViewErrorMessage(e.to_string().into());
-                    return;
-                }
-                Ok(project_materials) => project_materials,
-            };
-
-            let materials = workshop_handle.lock().unwrap().get_materials(&token);
-            // Check if the materials were successfully retrieved otherwise handle the error
-            let materials = match materials {
-                Err(e) => {
-                    ui.global::<Backend>().set_projectDetailViewErrorMessage(e.to_string().into());
-                    return;
-                }
-                Ok(materials) => materials,
-            };
-
-            let material_types = workshop_handle.lock().unwrap().get_material_types(&token);
-            // Check if the material types were successfully retrieved otherwise handle the error
-            let material_types = match material_types {
-                Err(e) => {
-                    ui.global::<Backend>().set_projectDetailViewErrorMessage(e.to_string().into());
-                    return;
-                }
-                Ok(material_types) => material_types,
-            };
-
-            let materials: Vec<Material> = project_materials
-                .iter()
-                .map(|m| {
-                    let material: Result<r_Material, Error> = materials
-                        .iter()
-                        .find(|mat| mat.id == m.material_id)
-                        .cloned()
-                        .ok_or(Error::new("Material not found".to_owned(), 404));
-                    let material = match material {
-                        Ok(material) => {
-                            let m_type: Result<r_MaterialType, Error> = material_types
-                                .iter()
-                                .find(|t| t.id == material.type_id)
-                                .cloned()
-                                .ok_or(Error::new("Material type not found".to_owned(), 404));
-                            let m_type = match m_type {
-                                Ok(m_type) => MaterialType {
-                                    id: m_type.id,
-                                    name: m_type.name.clone().into(),
-                                    description: m_type.description.clone().into(),
-                                },
-                                Err(e) => {
-                                    ui.global::<Backend>().set_projectDetailViewErrorMessage(e.to_string().into());
-                                    return Material {
-                                        id: 0,
-                                        name: "".into(),
-                                        description: "".into(),
-                                        m_type: MaterialType {
-                                            id: 0,
-                                            name: "".into(),
-                                            description: "".into(),
-                                        },
-                                        quantity: 0,
-                                        price: 0.0,
-                                        threshold_value: 0,
-                                    };
-                                }
-                            };
-                            Material {
-                                id: material.id,
-                                name: material.name.clone().into(),
-                                description: material.description.clone().into(),
-                                m_type,
-                                quantity: m.amount,
-                                price: material.costs,
-                                threshold_value: material.threshold_value,
-                            }
-                        }
+            show_view(&ui, View::ProjectDetail);
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            let schedule_projects = schedule_projects.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token
+                    let token = keycloak_handle.lock().await.refresh_token().await?;
+                    // Get the project with its client and materials and the other projects for the schedule
+                    workshop_handle.lock().await.load_all(&token).await
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the project and its data were successfully retrieved otherwise handle the error
+                    let collections = match result {
                         Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
                             ui.global::<Backend>().set_projectDetailViewErrorMessage(e.to_string().into());
-                            return Material {
-                                id: 0,
-                                name: "".into(),
-                                description: "".into(),
-                                m_type: MaterialType {
-                                    id: 0,
-                                    name: "".into(),
-                                    description: "".into(),
-                                },
-                                quantity: 0,
-                                price: 0.0,
-                                threshold_value: 0,
-                            };
+                            return;
                         }
+                        Ok(collections) => collections,
                     };
-                    material
-                })
-                .collect();
 
-            let project = ProjectModel {
-                id: project.id,
-                name: project.name.clone().into(),
-                client: ClientModel {
-                    id: client.id,
-                    firstName: client.firstname.clone().into(),
-                    lastName: client.lastname.clone().into(),
-                    phone: client.phone.clone().into(),
-                },
-                mats: ModelRc::new(VecModel::from(materials)),
-                calculated_costs: project.estimated_costs,
-                current_costs: project.costs,
-                project_deadline: project.endpoint.clone().into(),
-                project_start: project.startpoint.clone().into(),
-            };
+                    let projects = collections.projects.clone();
+                    let clients = collections.clients.clone();
+                    let graph = DomainGraph::new(collections);
+                    // Someone else may have deleted the project in the meantime
+                    let Some(project) = graph.project(project_id) else {
+                        let message = format!("Project #{} was not found, it may have been deleted", project_id);
+                        ui.global::<Backend>().set_projectDetailViewErrorMessage(message.into());
+                        return;
+                    };
+                    let saved = projects.iter().find(|p| p.id == project_id);
+                    // The days of older projects may not be readable, they have to be entered again before a save
+                    let message = match saved.map(|p| p.check_dates()) {
+                        Some(Err(e)) => e.to_string(),
+                        _ => String::new(),
+                    };
+                    ui.global::<Backend>().set_projectDetailViewErrorMessage(message.into());
 
-            ui.global::<Backend>().set_project(project);
+                    let estimated_costs = saved.map(|p| p.estimated_costs).unwrap_or_default();
+                    show_client_choice(&ui, &clients, project.client.id);
+                    show_project_form(&ui, project, estimated_costs);
+                    *schedule_projects.borrow_mut() = projects;
+                    show_schedule_warning(&ui, &schedule_projects.borrow(), weekly_capacity);
+                },
+            );
         }
     });
 
     // Navigate to the Material popup
     ui.global::<Backend>().on_showAddNewMaterialPopUp({
         let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
         move || {
             let ui = ui_handle.unwrap();
             ui.global::<Backend>().set_showMaterialPopUp(true);
+            // Clear the form right away, the types follow once they are loaded
+            show_material_form(&ui, None);
+            show_material_type_choice(&ui, &[], 0);
+
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token
+                    let token = keycloak_handle.lock().await.refresh_token().await?;
+                    workshop_handle.lock().await.get_all::<r_MaterialType>(&token).await
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    match result {
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_materialPopUpErrorMessage(e.to_string().into());
+                        }
+                        Ok(material_types) => {
+                            show_material_type_choice(&ui, &material_types, 0);
+                        }
+                    }
+                },
+            );
         }
     });
 
@@ -767,6 +684,7 @@ fn main() -> Result<(), slint::PlatformError> {
         let ui_handle = ui.as_weak();
         move || {
             let ui = ui_handle.unwrap();
+            show_material_type_form(&ui, None);
             ui.global::<Backend>().set_showMaterialTypePopUp(true);
         }
     });
@@ -776,6 +694,7 @@ fn main() -> Result<(), slint::PlatformError> {
         let ui_handle = ui.as_weak();
         move || {
             let ui = ui_handle.unwrap();
+            show_client_form(&ui, None);
             ui.global::<Backend>().set_showClientPopUp(true);
         }
     });
@@ -807,336 +726,3047 @@ fn main() -> Result<(), slint::PlatformError> {
         }
     });
 
-    // Navigate to the project detail view
+    // Navigate to the project detail view with an empty project
     ui.global::<Backend>().on_create_new_project({
         let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        let schedule_projects = schedule_projects.clone();
         move || {
             let ui = ui_handle.unwrap();
-            ui.global::<Backend>().set_projectView(false);
-            ui.global::<Backend>().set_projectManagementView(false);
-            ui.global::<Backend>().set_projectDetailView(true);
-            ui.global::<Backend>().set_lagerOverviewView(false);
-            ui.global::<Backend>().set_showClientPopUp(false);
-            ui.global::<Backend>().set_showMaterialPopUp(false);
-            ui.global::<Backend>().set_showMaterialTypePopUp(false);
+            show_view(&ui, View::ProjectDetail);
+            // Clear the form right away, the clients follow once they are loaded
+            show_project_form(&ui, ProjectModel::default(), Money::ZERO);
+            show_client_choice(&ui, &[], 0);
+            schedule_projects.borrow_mut().clear();
+
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            let schedule_projects = schedule_projects.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token
+                    let token = keycloak_handle.lock().await.refresh_token().await?;
+                    // Get the clients that can be chosen for the project and the projects for the schedule
+                    let collections = workshop_handle.lock().await.load_all(&token).await?;
+                    Ok::<_, Error>((collections.clients, collections.projects))
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    match result {
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_projectDetailViewErrorMessage(e.to_string().into());
+                        }
+                        Ok((clients, projects)) => {
+                            show_client_choice(&ui, &clients, 0);
+                            *schedule_projects.borrow_mut() = projects;
+                            show_schedule_warning(&ui, &schedule_projects.borrow(), weekly_capacity);
+                        }
+                    }
+                },
+            );
         }
     });
 
-    // Edit a material of a project
-    ui.global::<Backend>().on_editMaterial({
+    // Check the dates of the project form whenever one of them changes
+    ui.global::<Backend>().on_check_project_schedule({
         let ui_handle = ui.as_weak();
-        let workshop_handle = arc_workshop_service.clone();
-        let keycloak_handle = arc_keycloak.clone();
-        move |material_id: i32| {
+        let schedule_projects = schedule_projects.clone();
+        move || {
             let ui = ui_handle.unwrap();
-            ui.global::<Backend>().set_showMaterialPopUp(true);
+            show_schedule_warning(&ui, &schedule_projects.borrow(), weekly_capacity);
+        }
+    });
 
-            // Refresh the token
-            let token = keycloak_handle.lock().unwrap().refresh_token();
-            // Check if the token was successfully retrieved otherwise handle the error
-            let token = match token {
-                Err(e) => {
-                    ui.global::<Backend>().set_projectDetailViewErrorMessage(e.to_string().into());
-                    return;
-                }
-                Ok(token) => token,
-            };
+    // The days of the date picker, `offset` moves the month of the date in the field
+    ui.global::<Backend>().on_calendar_days(|text: SharedString, offset: i32| {
+        let selected = date::parse_input(&text);
+        let month = date::add_months(selected.unwrap_or_else(date::today), offset);
+        ModelRc::new(VecModel::from(view_models::calendar_days(month, selected, date::today())))
+    });
 
-            let project = ui.global::<Backend>().get_project();
-            let materials = workshop_handle.lock().unwrap().get_materials(&token);
-            let project_materials = workshop_handle
-                .lock()
-                .unwrap()
-                .get_project_materials(&token);
-            let material_types = workshop_handle.lock().unwrap().get_material_types(&token);
-
-            let materials = match materials {
-                Ok(materials) => materials,
-                Err(e) => {
-                    ui.global::<Backend>().set_projectDetailViewErrorMessage(e.to_string().into());
-                    return;
-                }
-            };
+    ui.global::<Backend>().on_calendar_title(|text: SharedString, offset: i32| {
+        let month = date::add_months(date::parse_input(&text).unwrap_or_else(date::today), offset);
+        month.format("%B %Y").to_string().into()
+    });
 
-            let project_materials = match project_materials {
-                Ok(project_materials) => project_materials,
-                Err(e) => {
-                    ui.global::<Backend>().set_projectDetailViewErrorMessage(e.to_string().into());
-                    return;
-                }
-            };
+    // Quick actions of the date picker, an empty or invalid field counts from today
+    ui.global::<Backend>().on_date_plus_week(|text: SharedString| {
+        let day = date::parse_input(&text).unwrap_or_else(date::today);
+        date::display(day + chrono::Duration::weeks(1)).into()
+    });
 
-            let material_types = match material_types {
-                Ok(material_types) => material_types,
-                Err(e) => {
-                    ui.global::<Backend>().set_projectDetailViewErrorMessage(e.to_string().into());
-                    return;
-                }
+    ui.global::<Backend>().on_date_end_of_month(|text: SharedString| {
+        let day = date::parse_input(&text).unwrap_or_else(date::today);
+        date::display(date::end_of_month(day)).into()
+    });
+
+    // Create a new project or update the shown one
+    ui.global::<Backend>().on_save_project({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        move |name: SharedString,
+              client_index: i32,
+              start: SharedString,
+              deadline: SharedString,
+              costs: SharedString,
+              hours: SharedString| {
+            let ui = ui_handle.unwrap();
+            // A project without an id is not saved yet
+            let project_id = ui.global::<Backend>().get_project().id;
+            let client = usize::try_from(client_index)
+                .ok()
+                .and_then(|index| ui.global::<Backend>().get_clients().row_data(index));
+
+            // Check every field so all errors can be shown at once
+            let mut errors = FieldErrors::default();
+            let name = errors.check("name", validation::required(&name));
+            if client.is_none() {
+                errors.add("client", "Please choose a client");
+            }
+            let start = errors.check("start", validation::date(&start));
+            let deadline = errors.check("deadline", validation::date(&deadline));
+            if let (Some(start), Some(deadline)) = (start, deadline) {
+                errors.check("deadline", r_Project::check_schedule(start, deadline));
+            }
+            let estimated_costs = errors.check("costs", validation::money(&costs));
+            let estimated_hours = errors.check("hours", validation::positive_decimal(&hours));
+            ui.global::<Backend>().set_projectErrors(ProjectErrors {
+                name: errors.get("name").into(),
+                client: errors.get("client").into(),
+                start: errors.get("start").into(),
+                deadline: errors.get("deadline").into(),
+                costs: errors.get("costs").into(),
+                hours: errors.get("hours").into(),
+            });
+            let (Some(name), Some(client), Some(start), Some(deadline), Some(estimated_costs), Some(estimated_hours)) =
+                (name, client, start, deadline, estimated_costs, estimated_hours)
+            else {
+                return;
             };
 
-            // Get materials of the project and the amount from the project materials and display it
-            let material: Material =
-                match materials.iter().find(|m: &&r_Material| m.id == material_id) {
-                    Some(material) => {
-                        let m_type: Result<r_MaterialType, Error> = material_types
-                            .iter()
-                            .find(|t| t.id == material.type_id)
-                            .cloned()
-                            .ok_or(Error::new("Material type not found".to_owned(), 404));
-                        let m_type = match m_type {
-                            Ok(m_type) => MaterialType {
-                                id: m_type.id,
-                                name: m_type.name.clone().into(),
-                                description: m_type.description.clone().into(),
-                            },
-                            Err(e) => {
-                                ui.global::<Backend>().set_projectDetailViewErrorMessage(e.to_string().into());
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token, the user needs the permission for this
+                    let token = keycloak_handle
+                        .lock()
+                        .await
+                        .authorize(Permission::EditProjects)
+                        .await?;
+                    let workshop = workshop_handle.lock().await;
+                    if project_id == 0 {
+                        let project = r_Project {
+                            id: 0, // We can ignore it because the database will set it
+                            name,
+                            client_id: client.id,
+                            description: String::new(),
+                            startpoint: Some(start),
+                            endpoint: Some(deadline),
+                            estimated_costs,
+                            estimated_hours,
+                            costs: Money::ZERO,
+                            // Every project starts as a request of the client
+                            status: ProjectStatus::Inquiry,
+                        };
+                        return Ok::<_, Error>(workshop.create(&project, &token).await?.id);
+                    }
+                    // Get the project again so the fields that are not part of the form are kept
+                    let mut project = workshop.get_by_id::<r_Project>(project_id, &token).await?;
+                    project.name = name;
+                    project.client_id = client.id;
+                    project.startpoint = Some(start);
+                    project.endpoint = Some(deadline);
+                    project.estimated_costs = estimated_costs;
+                    project.estimated_hours = estimated_hours;
+                    workshop.update(&project, &token).await?;
+                    Ok(project.id)
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Show the saved project otherwise handle the error
+                    match result {
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
                                 return;
                             }
-                        };
-                        let project_material: Option<&r_ProjectMaterial> = project_materials
-                            .iter()
-                            .find(|m| m.material_id == material_id && m.project_id == project.id);
-                        let quantity = match project_material {
-                            Some(m) => m.amount,
-                            None => 0,
-                        };
-                        Material {
-                            id: material.id,
-                            name: material.name.clone().into(),
-                            description: material.description.clone().into(),
-                            m_type,
-                            quantity,
-                            price: material.costs,
-                            threshold_value: material.threshold_value,
+                            ui.global::<Backend>().set_projectDetailViewErrorMessage(e.to_string().into());
+                        }
+                        Ok(project_id) => {
+                            ui.global::<Backend>().invoke_route_to_project_i(project_id);
                         }
                     }
-                    None => {
-                        ui.global::<Backend>().set_projectDetailViewErrorMessage("Material not found".into());
-                        return;
-                    }
-                };
+                },
+            );
+        }
+    });
 
-            todo!("Subtract the amount of the material from the project material amount")
+    // Open the material popup to edit a material of the warehouse
+    ui.global::<Backend>().on_editMaterial({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        move |material_id: i32| {
+            let ui = ui_handle.unwrap();
+            ui.global::<Backend>().set_showMaterialPopUp(true);
+            show_material_form(&ui, None);
+
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token
+                    let token = keycloak_handle.lock().await.refresh_token().await?;
+                    let workshop = workshop_handle.lock().await;
+                    let material = workshop.get_by_id::<r_Material>(material_id, &token).await?;
+                    let material_types = workshop.get_all::<r_MaterialType>(&token).await?;
+                    Ok::<_, Error>((material, material_types))
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the material was successfully retrieved otherwise handle the error
+                    match result {
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_materialPopUpErrorMessage(e.to_string().into());
+                        }
+                        Ok((material, material_types)) => {
+                            show_material_form(&ui, Some(&material));
+                            show_material_type_choice(&ui, &material_types, material.type_id);
+                        }
+                    }
+                },
+            );
         }
     });
 
-    // Delete a project
-    ui.global::<Backend>().on_delete_project_i({
+    // Create a new material or update the one in the popup
+    ui.global::<Backend>().on_save_materials({
         let ui_handle = ui.as_weak();
         let workshop_handle = arc_workshop_service.clone();
         let keycloak_handle = arc_keycloak.clone();
-        move |project_id: i32| {
+        let material_list = material_list.clone();
+        move |name: SharedString,
+              price: SharedString,
+              type_index: i32,
+              amount: SharedString,
+              threshold: SharedString,
+              description: SharedString| {
             let ui = ui_handle.unwrap();
-            // Refresh the token
-            let token = keycloak_handle.lock().unwrap().refresh_token();
-            // Check if the token was successfully retrieved otherwise handle the error
-            let token = match token {
-                Err(e) => {
-                    ui.global::<Backend>().set_projectManagementErrorMessage(e.to_string().into());
-                    return;
-                }
-                Ok(token) => token,
-            };
-            // Delete the project
-            let result = workshop_handle
-                .lock()
-                .unwrap()
-                .delete_project(project_id, &token);
-            match result {
-                Ok(_) => {
-                    ui.global::<Backend>().invoke_route_to_project_management();
-                }
-                Err(e) => {
-                    ui.global::<Backend>().set_projectManagementErrorMessage(e.to_string().into());
-                }
+            // A material without an id is not saved yet
+            let material_id = ui.global::<Backend>().get_editedMaterialId();
+            let material_type = usize::try_from(type_index)
+                .ok()
+                .and_then(|index| ui.global::<Backend>().get_materialTypes().row_data(index));
+
+            // Check every field so all errors can be shown at once
+            let mut errors = FieldErrors::default();
+            let name = errors.check("name", validation::required(&name));
+            let costs = errors.check("price", validation::money(&price));
+            if material_type.is_none() {
+                errors.add("type", "Please choose a type");
             }
+            let amount = errors.check("amount", validation::positive_integer(&amount));
+            let threshold_value = errors.check("threshold", validation::positive_integer(&threshold));
+            ui.global::<Backend>().set_materialErrors(MaterialErrors {
+                name: errors.get("name").into(),
+                price: errors.get("price").into(),
+                r#type: errors.get("type").into(),
+                amount: errors.get("amount").into(),
+                threshold: errors.get("threshold").into(),
+            });
+            let (Some(name), Some(costs), Some(material_type), Some(amount), Some(threshold_value)) =
+                (name, costs, material_type, amount, threshold_value)
+            else {
+                return;
+            };
+            let description = description.trim().to_string();
+
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            let material_list = material_list.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token, the user needs the permission for this
+                    let mut keycloak = keycloak_handle.lock().await;
+                    let token = keycloak.authorize(Permission::EditMaterials).await?;
+                    let user = keycloak.session().username.clone();
+                    drop(keycloak);
+                    let workshop = workshop_handle.lock().await;
+                    let material = r_Material {
+                        id: material_id,
+                        name,
+                        description,
+                        type_id: material_type.id,
+                        amount,
+                        costs,
+                        threshold_value,
+                    };
+                    // A different amount in the form is recorded as a correction of the counted stock
+                    workshop.save_material(&material, &user, &token).await?;
+                    // Get the list again so the warehouse shows the saved material
+                    let materials = workshop.get_all::<r_Material>(&token).await?;
+                    let material_types = workshop.get_all::<r_MaterialType>(&token).await?;
+                    Ok::<_, Error>((materials, material_types))
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the material was successfully saved otherwise handle the error
+                    match result {
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_materialPopUpErrorMessage(e.to_string().into());
+                        }
+                        Ok((materials, material_types)) => {
+                            ui.global::<Backend>().set_showMaterialPopUp(false);
+                            material_list.set_items(
+                                DomainGraph::new(Collections {
+                                    materials,
+                                    material_types,
+                                    ..Default::default()
+                                })
+                                .materials(),
+                            );
+                        }
+                    }
+                },
+            );
         }
     });
 
-    // Delete a material of a project
-    ui.global::<Backend>().on_deleteMaterial({
+    // Delete a material of the warehouse
+    ui.global::<Backend>().on_delete_material({
         let ui_handle = ui.as_weak();
         let workshop_handle = arc_workshop_service.clone();
         let keycloak_handle = arc_keycloak.clone();
+        let material_list = material_list.clone();
         move |material_id: i32| {
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            let material_list = material_list.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token, the user needs the permission for this
+                    let token = keycloak_handle
+                        .lock()
+                        .await
+                        .authorize(Permission::DeleteMaterials)
+                        .await?;
+                    let workshop = workshop_handle.lock().await;
+                    // A material that is used in a project can not be deleted
+                    let project_materials = workshop.get_all::<r_ProjectMaterial>(&token).await?;
+                    let usages = project_materials.iter().filter(|m| m.material_id == material_id).count();
+                    if usages > 0 {
+                        return Err(Error::Conflict(format!(
+                            "The material is still used in {} project(s)",
+                            usages
+                        )));
+                    }
+                    workshop.delete::<r_Material>(material_id, &token).await?;
+                    // Get the list again so the warehouse no longer shows the material
+                    let materials = workshop.get_all::<r_Material>(&token).await?;
+                    let material_types = workshop.get_all::<r_MaterialType>(&token).await?;
+                    Ok((materials, material_types))
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the material was successfully deleted otherwise handle the error
+                    match result {
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_lagerOverviewErrorMessage(e.to_string().into());
+                        }
+                        Ok((materials, material_types)) => {
+                            ui.global::<Backend>().set_lagerOverviewErrorMessage("".into());
+                            material_list.set_items(
+                                DomainGraph::new(Collections {
+                                    materials,
+                                    material_types,
+                                    ..Default::default()
+                                })
+                                .materials(),
+                            );
+                        }
+                    }
+                },
+            );
+        }
+    });
+
+    // Route to the goods receipt with an empty delivery
+    ui.global::<Backend>().on_route_to_goods_receipt({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        let receipt_lines = receipt_lines.clone();
+        move || {
             let ui = ui_handle.unwrap();
-            // Refresh the token
-            let token = keycloak_handle.lock().unwrap().refresh_token();
-            // Check if the token was successfully retrieved otherwise handle the error
-            let token = match token {
-                Err(e) => {
-                    ui.global::<Backend>().set_projectDetailViewErrorMessage(e.to_string().into());
-                    return;
-                }
-                Ok(token) => token,
-            };
-            // Get the project
-            let project = ui.global::<Backend>().get_project();
-            // Get the project materials
-            let project_materials = workshop_handle
-                .lock()
-                .unwrap()
-                .get_project_materials(&token);
-            // Check if the project materials were successfully retrieved otherwise handle the error
-            let project_materials = match project_materials {
-                Ok(project_materials) => project_materials,
-                Err(e) => {
-                    ui.global::<Backend>().set_projectDetailViewErrorMessage(e.to_string().into());
-                    return;
-                }
-            };
-            // Find the project material that we want to delete
-            let project_material: Option<&r_ProjectMaterial> = project_materials
-                .iter()
-                .find(|m| m.material_id == material_id && m.project_id == project.id);
-            // Check if the project material was found otherwise handle the error
-            let project_material = match project_material {
-                Some(m) => m,
-                None => {
-                    ui.global::<Backend>().set_projectDetailViewErrorMessage("Material not found".into());
-                    return;
-                }
-            };
-            // Delete the material from the project
-            let result = workshop_handle
-                .lock()
-                .unwrap()
-                .delete_project_material(project_material.id, &token);
-            // Check if the material was successfully deleted otherwise handle the error
-            if let Err(e) = result {
-                ui.global::<Backend>().set_projectDetailViewErrorMessage(e.to_string().into());
-                return;
-            }
+            show_view(&ui, View::GoodsReceipt);
+            // Start with an empty delivery of today
+            ui.global::<Backend>().set_receiptSupplier("".into());
+            ui.global::<Backend>().set_receiptDeliveryNote("".into());
+            ui.global::<Backend>().set_receiptDate(date::display(date::today()).into());
+            ui.global::<Backend>().set_receiptQuantity("".into());
+            ui.global::<Backend>().set_receiptPrice("".into());
+            ui.global::<Backend>().set_receiptErrors(ReceiptErrors::default());
+            ui.global::<Backend>().set_receiptOrderId(0);
+            receipt_lines.borrow_mut().clear();
+            show_receipt_lines(&ui, &receipt_lines.borrow());
+
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            let receipt_lines = receipt_lines.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token
+                    let token = keycloak_handle.lock().await.refresh_token().await?;
+                    let mut workshop = workshop_handle.lock().await;
+                    // Get the materials that can be delivered and the deliveries that were booked
+                    let Collections { materials, material_types, .. } = workshop.load_all(&token).await?;
+                    let receipts = workshop.get_all::<GoodsReceipt>(&token).await?;
+                    Ok::<_, Error>((materials, material_types, receipts))
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    let (materials, material_types, mut receipts) = match result {
+                        Ok(data) => data,
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_goodsReceiptErrorMessage(e.to_string().into());
+                            return;
+                        }
+                    };
+                    ui.global::<Backend>().set_goodsReceiptErrorMessage("".into());
+                    let materials = DomainGraph::new(Collections {
+                        materials,
+                        material_types,
+                        ..Default::default()
+                    })
+                    .materials();
+                    let names: Vec<SharedString> = materials.iter().map(|m| m.name.clone()).collect();
+                    ui.global::<Backend>().set_receiptMaterialName(names.first().cloned().unwrap_or_default());
+                    ui.global::<Backend>().set_receiptMaterialIndex(0);
+                    ui.global::<Backend>().set_receiptMaterialNames(ModelRc::new(VecModel::from(names)));
+                    ui.global::<Backend>().set_receiptMaterials(ModelRc::new(VecModel::from(materials)));
+                    show_receipt_lines(&ui, &receipt_lines.borrow());
+
+                    receipts.sort_by_key(|r| std::cmp::Reverse((r.date, r.id)));
+                    let receipts: Vec<GoodsReceiptModel> = receipts
+                        .iter()
+                        .map(|r| GoodsReceiptModel {
+                            id: r.id,
+                            date: date::display(r.date).into(),
+                            supplier: r.supplier.clone().into(),
+                            delivery_note: r.delivery_note.clone().into(),
+                            lines: r.lines.len() as i32,
+                            total: r.total().to_string().into(),
+                            user: r.user.clone().into(),
+                        })
+                        .collect();
+                    ui.global::<Backend>().set_goodsReceipts(ModelRc::new(VecModel::from(receipts)));
+                },
+            );
+        }
+    });
 
-            // Set the project again
-            ui.global::<Backend>().invoke_route_to_project_i(project.id);
+    // Route to supplier management
+    ui.global::<Backend>().on_route_to_supplier_management({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        let supplier_list = supplier_list.clone();
+        let supplier_offers = supplier_offers.clone();
+        move || {
+            let ui = ui_handle.unwrap();
+            show_view(&ui, View::SupplierManagement);
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            let supplier_list = supplier_list.clone();
+            let supplier_offers = supplier_offers.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token
+                    let token = keycloak_handle.lock().await.refresh_token().await?;
+                    let mut workshop = workshop_handle.lock().await;
+                    // Get the suppliers with their offers and the materials that can be offered
+                    let Collections { materials, material_types, .. } = workshop.load_all(&token).await?;
+                    let suppliers = workshop.get_all::<Supplier>(&token).await?;
+                    let offers = workshop.get_all::<SupplierMaterial>(&token).await?;
+                    Ok::<_, Error>((suppliers, offers, materials, material_types))
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the data was successfully retrieved otherwise handle the error
+                    let (mut suppliers, offers, materials, material_types) = match result {
+                        Ok(data) => data,
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_supplierManagementErrorMessage(e.to_string().into());
+                            return;
+                        }
+                    };
+                    ui.global::<Backend>().set_supplierManagementErrorMessage("".into());
+
+                    suppliers.sort_by_key(|s| (s.name.to_lowercase(), s.id));
+                    supplier_list.set_items(suppliers.iter().map(supplier_model).collect());
+                    let materials = DomainGraph::new(Collections {
+                        materials,
+                        material_types,
+                        ..Default::default()
+                    })
+                    .materials();
+                    let names: Vec<SharedString> = materials.iter().map(|m| m.name.clone()).collect();
+                    ui.global::<Backend>().set_offerMaterialName(names.first().cloned().unwrap_or_default());
+                    ui.global::<Backend>().set_offerMaterialIndex(0);
+                    ui.global::<Backend>().set_offerMaterialNames(ModelRc::new(VecModel::from(names)));
+                    ui.global::<Backend>().set_offerMaterials(ModelRc::new(VecModel::from(materials)));
+                    *supplier_offers.borrow_mut() = offers;
+                    // The chosen supplier stays chosen as long as they exist
+                    show_supplier_materials(
+                        &ui,
+                        &supplier_list.items(),
+                        &supplier_offers.borrow(),
+                        ui.global::<Backend>().get_selectedSupplierId(),
+                    );
+                },
+            );
         }
     });
 
-    // Save a new material type
-    ui.global::<Backend>().on_save_material_type({
+    // Route to the reorder suggestions
+    ui.global::<Backend>().on_route_to_reorder({
         let ui_handle = ui.as_weak();
         let workshop_handle = arc_workshop_service.clone();
         let keycloak_handle = arc_keycloak.clone();
-        move |name: SharedString, description: SharedString| {
+        let reorder_groups = reorder_groups.clone();
+        move || {
             let ui = ui_handle.unwrap();
-            // Refresh the token
-            let token = keycloak_handle.lock().unwrap().refresh_token();
-            // Check if the token was successfully retrieved otherwise handle the error
-            let token = match token {
-                Err(e) => {
-                    ui.global::<Backend>().set_projectManagementErrorMessage(e.to_string().into());
-                    return;
-                }
-                Ok(token) => token,
-            };
-            let material_type: r_MaterialType = r_MaterialType {
-                id: 0, // We can ignore it because the database will set it
-                name: name.to_string(),
-                description: description.to_string(),
-            };
-            let result = workshop_handle
-                .lock()
-                .unwrap()
-                .create_material_type(material_type, &token);
-            // Check if the material type was successfully created otherwise handle the error
-            match result {
-                Err(e) => {
-                    ui.global::<Backend>().set_projectManagementErrorMessage(e.to_string().into());
-                    return;
-                }
-                Ok(_) => {
-                    ui.global::<Backend>().set_showMaterialTypePopUp(false);
-                    let types = workshop_handle.lock().unwrap().get_material_types(&token);
-                    // Check if the material types were successfully retrieved otherwise handle the error
-                    let types = match types {
+            show_view(&ui, View::Reorder);
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            let reorder_groups = reorder_groups.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token
+                    let token = keycloak_handle.lock().await.refresh_token().await?;
+                    let mut workshop = workshop_handle.lock().await;
+                    // The stocks, the demand of the projects, where the materials can be bought and what is ordered
+                    let collections = workshop.load_all(&token).await?;
+                    let suppliers = workshop.get_all::<Supplier>(&token).await?;
+                    let supplier_materials = workshop.get_all::<SupplierMaterial>(&token).await?;
+                    let purchase_orders = workshop.get_all::<PurchaseOrder>(&token).await?;
+                    Ok::<_, Error>(ReorderData {
+                        materials: collections.materials,
+                        projects: collections.projects,
+                        project_materials: collections.project_materials,
+                        suppliers,
+                        supplier_materials,
+                        purchase_orders,
+                    })
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    let data = match result {
+                        Ok(data) => data,
                         Err(e) => {
-                            ui.global::<Backend>().set_projectManagementErrorMessage(e.to_string().into());
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_reorderErrorMessage(e.to_string().into());
                             return;
                         }
-                        Ok(types) => types,
                     };
-                }
-            }
+                    ui.global::<Backend>().set_reorderErrorMessage("".into());
+
+                    let groups = reorder::suggestions(data);
+                    let models: Vec<ReorderGroupModel> = groups.iter().map(reorder_group_model).collect();
+                    ui.global::<Backend>().set_reorderGroups(ModelRc::new(VecModel::from(models)));
+                    *reorder_groups.borrow_mut() = groups;
+                },
+            );
         }
     });
 
-    // Save a new client
-    ui.global::<Backend>().on_saveClient({
+    // Route to the purchase orders, the chosen order stays chosen as long as it exists
+    ui.global::<Backend>().on_route_to_purchase_orders({
         let ui_handle = ui.as_weak();
         let workshop_handle = arc_workshop_service.clone();
         let keycloak_handle = arc_keycloak.clone();
-        move |first_name: SharedString, last_name: SharedString, phone: SharedString| {
+        let order_list = order_list.clone();
+        let purchase_orders = purchase_orders.clone();
+        move || {
             let ui = ui_handle.unwrap();
-            // Refresh the token
-            let token = keycloak_handle.lock().unwrap().refresh_token();
-            // Check if the token was successfully retrieved otherwise handle the error
-            let token = match token {
-                Err(e) => {
-                    ui.global::<Backend>().set_projectManagementErrorMessage(e.to_string().into());
-                    return;
-                }
-                Ok(token) => token,
-            };
-            let client: r_Client = r_Client {
-                id: 0, // We can ignore it because the database will set it
-                firstname: first_name.to_string(),
-                lastname: last_name.to_string(),
-                phone: phone.to_string(),
-            };
-            let result = workshop_handle
-                .lock()
-                .unwrap()
-                .create_client(client, &token);
-            // Check if the client was successfully created otherwise handle the error
-            match result {
-                Err(e) => {
-                    ui.global::<Backend>().set_projectManagementErrorMessage(e.to_string().into());
-                    return;
-                }
-                Ok(_) => {
-                    ui.global::<Backend>().set_showClientPopUp(false);
-                }
-            }
+            show_view(&ui, View::PurchaseOrders);
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            let order_list = order_list.clone();
+            let purchase_orders = purchase_orders.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token
+                    let token = keycloak_handle.lock().await.refresh_token().await?;
+                    let mut workshop = workshop_handle.lock().await;
+                    // Get the orders with the names of their suppliers and materials
+                    let materials = workshop.load_all(&token).await?.materials;
+                    let orders = workshop.get_all::<PurchaseOrder>(&token).await?;
+                    let suppliers = workshop.get_all::<Supplier>(&token).await?;
+                    Ok::<_, Error>((orders, suppliers, materials))
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the data was successfully retrieved otherwise handle the error
+                    let (mut orders, suppliers, materials) = match result {
+                        Ok(data) => data,
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_purchaseOrderErrorMessage(e.to_string().into());
+                            return;
+                        }
+                    };
+                    ui.global::<Backend>().set_purchaseOrderErrorMessage("".into());
+
+                    // The newest order first
+                    orders.sort_by_key(|o| std::cmp::Reverse(o.id));
+                    let today = date::today();
+                    order_list.set_items(
+                        orders
+                            .iter()
+                            .map(|o| purchase_order_model(o, &suppliers, &materials, today, vat_rate))
+                            .collect(),
+                    );
+                    *purchase_orders.borrow_mut() = orders;
+                    show_purchase_order(&ui, &order_list.items(), ui.global::<Backend>().get_selectedOrderId());
+                },
+            );
         }
     });
 
-    // Search for a material
-    ui.global::<Backend>().on_search_material({
+    // Write a draft order with the suggestions for a supplier, it is expected after the lead time of the supplier
+    ui.global::<Backend>().on_create_purchase_order({
         let ui_handle = ui.as_weak();
-        move |search: SharedString| {
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        let reorder_groups = reorder_groups.clone();
+        move |supplier_id: i32| {
             let ui = ui_handle.unwrap();
-            let materials = ui.global::<Backend>().get_materials();
-            materials.clone().filter(move |m| {
-                m.name.to_lowercase().contains(&search.to_lowercase())
-                    || m.description
-                        .to_lowercase()
-                        .contains(&search.to_lowercase())
-            });
-            ui.global::<Backend>().set_materials(materials);
+            let groups = reorder_groups.borrow();
+            let group = groups
+                .iter()
+                .find(|g| g.supplier.as_ref().is_some_and(|s| s.id == supplier_id));
+            let Some(group) = group else {
+                ui.global::<Backend>().set_reorderErrorMessage("There is nothing to order at this supplier".into());
+                return;
+            };
+            let lead_time = group.supplier.as_ref().map(|s| s.lead_time_days).unwrap_or(0);
+            let expected_date = date::today() + chrono::Duration::days(lead_time.into());
+            let lines: Vec<OrderLine> = group
+                .lines
+                .iter()
+                .map(|line| OrderLine {
+                    material_id: line.material.id,
+                    quantity: line.quantity,
+                    received: 0,
+                    unit_price: line.unit_price(),
+                })
+                .collect();
+
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token, the user needs the permission for this
+                    let mut keycloak = keycloak_handle.lock().await;
+                    let token = keycloak.authorize(Permission::EditMaterials).await?;
+                    let user = keycloak.session().username.clone();
+                    drop(keycloak);
+                    let order = PurchaseOrder {
+                        id: 0, // We can ignore it because the database will set it
+                        supplier_id,
+                        status: OrderStatus::Draft,
+                        order_date: None,
+                        expected_date,
+                        user,
+                        lines,
+                    };
+                    workshop_handle.lock().await.create(&order, &token).await
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the order was successfully written otherwise handle the error
+                    match result {
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_reorderErrorMessage(e.to_string().into());
+                        }
+                        Ok(order) => {
+                            // Show the new draft so it can be checked and sent
+                            ui.global::<Backend>().set_selectedOrderId(order.id);
+                            ui.global::<Backend>().invoke_route_to_purchase_orders();
+                        }
+                    }
+                },
+            );
         }
     });
 
-    // Search for a project
-    ui.global::<Backend>().on_search_project({
+    // Show the lines of an order
+    ui.global::<Backend>().on_select_purchase_order({
         let ui_handle = ui.as_weak();
-        move |search: SharedString| {
+        let order_list = order_list.clone();
+        move |order_id: i32| {
             let ui = ui_handle.unwrap();
-            let projects = ui.global::<Backend>().get_allProjects();
-            projects
-                .clone()
-                .filter(move |p| p.name.to_lowercase().contains(&search.to_lowercase()));
-            ui.global::<Backend>().set_allProjects(projects);
+            show_purchase_order(&ui, &order_list.items(), order_id);
         }
     });
 
-    // Exit application
-    ui.global::<Backend>().on_request_exit({
-        move || {
-            std::process::exit(0);
+    // Search for a purchase order
+    ui.global::<Backend>().on_search_purchase_order({
+        let order_list = order_list.clone();
+        move |search: SharedString| {
+            order_list.set_search(&search);
         }
     });
 
-    ui.run()
+    // Mark a draft as sent to the supplier
+    ui.global::<Backend>().on_send_purchase_order({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        move |order_id: i32| {
+            change_purchase_order(&ui_handle, &workshop_handle, &keycloak_handle, order_id, |order| {
+                order.send(date::today())
+            });
+        }
+    });
+
+    // Cancel an order nothing arrived for
+    ui.global::<Backend>().on_cancel_purchase_order({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        move |order_id: i32| {
+            change_purchase_order(&ui_handle, &workshop_handle, &keycloak_handle, order_id, |order| order.cancel());
+        }
+    });
+
+    // Move the day the order is expected
+    ui.global::<Backend>().on_save_expected_date({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        move |order_id: i32, date: SharedString| {
+            let ui = ui_handle.unwrap();
+            let date = match validation::date(&date) {
+                Ok(date) => date,
+                Err(message) => {
+                    ui.global::<Backend>().set_orderDateError(message.into());
+                    return;
+                }
+            };
+            ui.global::<Backend>().set_orderDateError("".into());
+            change_purchase_order(&ui_handle, &workshop_handle, &keycloak_handle, order_id, move |order| {
+                order.set_expected_date(date)
+            });
+        }
+    });
+
+    // Open the goods receipt with the lines of the order that did not arrive yet
+    ui.global::<Backend>().on_receive_purchase_order({
+        let ui_handle = ui.as_weak();
+        let purchase_orders = purchase_orders.clone();
+        let receipt_lines = receipt_lines.clone();
+        move |order_id: i32| {
+            let ui = ui_handle.unwrap();
+            let order = purchase_orders.borrow().iter().find(|o| o.id == order_id).cloned();
+            let Some(order) = order.filter(|o| o.status.is_open()) else {
+                ui.global::<Backend>().set_purchaseOrderErrorMessage("The order is not open for deliveries".into());
+                return;
+            };
+            let supplier = ui.global::<Backend>().get_selectedOrder().supplier_name;
+
+            ui.global::<Backend>().invoke_route_to_goods_receipt();
+            *receipt_lines.borrow_mut() = order
+                .lines
+                .iter()
+                .filter(|l| l.open_quantity() > 0)
+                .map(|l| ReceiptLine {
+                    material_id: l.material_id,
+                    quantity: l.open_quantity(),
+                    unit_price: l.unit_price,
+                })
+                .collect();
+            ui.global::<Backend>().set_receiptSupplier(supplier);
+            ui.global::<Backend>().set_receiptOrderId(order.id);
+            show_receipt_lines(&ui, &receipt_lines.borrow());
+        }
+    });
+
+    // Add a line to the delivery that is entered
+    ui.global::<Backend>().on_add_receipt_line({
+        let ui_handle = ui.as_weak();
+        let receipt_lines = receipt_lines.clone();
+        move |material_index: i32, quantity: SharedString, price: SharedString| {
+            let ui = ui_handle.unwrap();
+            let material = usize::try_from(material_index)
+                .ok()
+                .and_then(|index| ui.global::<Backend>().get_receiptMaterials().row_data(index));
+
+            // Check every field so all errors can be shown at once
+            let mut errors = FieldErrors::default();
+            if material.is_none() {
+                errors.add("material", "Please choose a material");
+            }
+            let quantity = errors.check(
+                "quantity",
+                validation::positive_integer(&quantity).and_then(|quantity| match quantity {
+                    0 => Err("The amount must be at least 1".to_string()),
+                    quantity => Ok(quantity),
+                }),
+            );
+            let unit_price = errors.check("price", validation::money(&price));
+            ui.global::<Backend>().set_receiptErrors(ReceiptErrors {
+                material: errors.get("material").into(),
+                quantity: errors.get("quantity").into(),
+                price: errors.get("price").into(),
+                ..ui.global::<Backend>().get_receiptErrors()
+            });
+            let (Some(material), Some(quantity), Some(unit_price)) = (material, quantity, unit_price) else {
+                return;
+            };
+
+            receipt_lines.borrow_mut().push(ReceiptLine {
+                material_id: material.id,
+                quantity,
+                unit_price,
+            });
+            ui.global::<Backend>().set_receiptQuantity("".into());
+            ui.global::<Backend>().set_receiptPrice("".into());
+            show_receipt_lines(&ui, &receipt_lines.borrow());
+        }
+    });
+
+    // Remove a line of the delivery that is entered
+    ui.global::<Backend>().on_remove_receipt_line({
+        let ui_handle = ui.as_weak();
+        let receipt_lines = receipt_lines.clone();
+        move |index: i32| {
+            let ui = ui_handle.unwrap();
+            if let Ok(index) = usize::try_from(index) {
+                if index < receipt_lines.borrow().len() {
+                    receipt_lines.borrow_mut().remove(index);
+                }
+            }
+            show_receipt_lines(&ui, &receipt_lines.borrow());
+        }
+    });
+
+    // Book the delivery into the warehouse
+    ui.global::<Backend>().on_book_receipt({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        let receipt_lines = receipt_lines.clone();
+        move || {
+            let ui = ui_handle.unwrap();
+
+            let supplier = ui.global::<Backend>().get_receiptSupplier();
+            let delivery_note = ui.global::<Backend>().get_receiptDeliveryNote();
+            let date = ui.global::<Backend>().get_receiptDate();
+            let lines = receipt_lines.borrow().clone();
+            let order_id = ui.global::<Backend>().get_receiptOrderId();
+
+            // Check every field so all errors can be shown at once
+            let mut errors = FieldErrors::default();
+            let supplier = errors.check("supplier", validation::required(&supplier));
+            let delivery_note = errors.check("delivery_note", validation::required(&delivery_note));
+            let date = errors.check("date", validation::date(&date));
+            if lines.is_empty() {
+                errors.add("lines", "Please add at least one line");
+            }
+            ui.global::<Backend>().set_receiptErrors(ReceiptErrors {
+                supplier: errors.get("supplier").into(),
+                delivery_note: errors.get("delivery_note").into(),
+                date: errors.get("date").into(),
+                lines: errors.get("lines").into(),
+                ..Default::default()
+            });
+            let (Some(supplier), Some(delivery_note), Some(date)) = (supplier, delivery_note, date) else {
+                return;
+            };
+            if lines.is_empty() {
+                return;
+            }
+
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token, the user needs the permission for this
+                    let mut keycloak = keycloak_handle.lock().await;
+                    let token = keycloak.authorize(Permission::EditMaterials).await?;
+                    let user = keycloak.session().username.clone();
+                    drop(keycloak);
+                    let receipt = GoodsReceipt {
+                        id: 0, // We can ignore it because the database will set it
+                        supplier,
+                        delivery_note,
+                        date,
+                        user,
+                        lines,
+                        purchase_order_id: (order_id != 0).then_some(order_id),
+                    };
+                    let workshop = workshop_handle.lock().await;
+                    match receipt.purchase_order_id {
+                        Some(_) => workshop.receive_order(&receipt, valuation, &token).await.map(|_| ()),
+                        None => workshop.receive_goods(&receipt, valuation, &token).await.map(|_| ()),
+                    }
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the delivery was successfully booked otherwise handle the error
+                    match result {
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_goodsReceiptErrorMessage(e.to_string().into());
+                        }
+                        // Show the order with the lines that are still open
+                        Ok(()) if order_id != 0 => {
+                            ui.global::<Backend>().set_selectedOrderId(order_id);
+                            ui.global::<Backend>().invoke_route_to_purchase_orders();
+                        }
+                        Ok(()) => {
+                            // Start the next delivery and show the booked one in the list
+                            ui.global::<Backend>().invoke_route_to_goods_receipt();
+                        }
+                    }
+                },
+            );
+        }
+    });
+
+    // Show the stock history of a material
+    ui.global::<Backend>().on_select_material({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        move |material_id: i32| {
+            let ui = ui_handle.unwrap();
+            ui.global::<Backend>().set_selectedMaterialId(material_id);
+            ui.global::<Backend>().set_movementAmountError("".into());
+
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token
+                    let token = keycloak_handle.lock().await.refresh_token().await?;
+                    let workshop = workshop_handle.lock().await;
+                    let material = workshop.get_by_id::<r_Material>(material_id, &token).await?;
+                    let movements = workshop.get_all::<StockMovement>(&token).await?;
+                    // The names of the projects the material was taken for
+                    let projects = workshop.get_all::<r_Project>(&token).await?;
+                    let material_types = workshop.get_all::<r_MaterialType>(&token).await?;
+                    Ok::<_, Error>((material, movements, projects, material_types))
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    let (material, movements, projects, material_types) = match result {
+                        Ok(data) => data,
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_lagerOverviewErrorMessage(e.to_string().into());
+                            return;
+                        }
+                    };
+                    // The user may have chosen another material in the meantime
+                    if ui.global::<Backend>().get_selectedMaterialId() != material_id {
+                        return;
+                    }
+                    show_stock_history(&ui, material, &movements, projects, material_types);
+                },
+            );
+        }
+    });
+
+    // Book a delivery, correction or write-off of a material
+    ui.global::<Backend>().on_book_movement({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        move |material_id: i32, reason_index: i32, amount: SharedString| {
+            let ui = ui_handle.unwrap();
+            let Some(reason) = usize::try_from(reason_index)
+                .ok()
+                .and_then(|index| MovementReason::MANUAL.get(index).copied())
+            else {
+                ui.global::<Backend>().set_movementAmountError("Please choose a reason".into());
+                return;
+            };
+            // A correction can go both ways, deliveries and write-offs are entered as the number of pieces
+            let delta = match reason {
+                MovementReason::Correction => validation::nonzero_integer(&amount),
+                MovementReason::WriteOff => validation::nonzero_integer(&amount)
+                    .and_then(|_| validation::positive_integer(&amount))
+                    .map(|amount| -amount),
+                _ => validation::nonzero_integer(&amount).and_then(|_| validation::positive_integer(&amount)),
+            };
+            let delta = match delta {
+                Ok(delta) => delta,
+                Err(message) => {
+                    ui.global::<Backend>().set_movementAmountError(message.into());
+                    return;
+                }
+            };
+            ui.global::<Backend>().set_movementAmountError("".into());
+
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token, the user needs the permission for this
+                    let mut keycloak = keycloak_handle.lock().await;
+                    let token = keycloak.authorize(Permission::EditMaterials).await?;
+                    let user = keycloak.session().username.clone();
+                    drop(keycloak);
+                    workshop_handle
+                        .lock()
+                        .await
+                        .book_movement(material_id, delta, reason, None, &user, &token)
+                        .await
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the movement was successfully booked otherwise handle the error
+                    match result {
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_movementAmountError(e.to_string().into());
+                        }
+                        Ok(_) => {
+                            ui.global::<Backend>().set_movementAmount("".into());
+                            // Show the new stock in the list and the movement in the history
+                            ui.global::<Backend>().invoke_route_to_warehouse_management();
+                            ui.global::<Backend>().invoke_select_material(material_id);
+                        }
+                    }
+                },
+            );
+        }
+    });
+
+    // Book the difference between the stock and the ledger as a correction, so both agree again
+    ui.global::<Backend>().on_reconcile_stock({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        move |material_id: i32| {
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token, the user needs the permission for this
+                    let mut keycloak = keycloak_handle.lock().await;
+                    let token = keycloak.authorize(Permission::EditMaterials).await?;
+                    let user = keycloak.session().username.clone();
+                    drop(keycloak);
+                    let workshop = workshop_handle.lock().await;
+                    // The counted stock of the material is right, the ledger is missing movements
+                    let material = workshop.get_by_id::<r_Material>(material_id, &token).await?;
+                    let movements = workshop.get_all::<StockMovement>(&token).await?;
+                    let difference = material.amount - stock_movement::ledger_amount(&movements, material_id);
+                    if difference != 0 {
+                        workshop
+                            .record_movement(material_id, difference, MovementReason::Correction, None, &user, &token)
+                            .await?;
+                    }
+                    Ok::<_, Error>(())
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the ledger was successfully reconciled otherwise handle the error
+                    match result {
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_lagerOverviewErrorMessage(e.to_string().into());
+                        }
+                        Ok(()) => {
+                            ui.global::<Backend>().set_lagerOverviewErrorMessage("".into());
+                            ui.global::<Backend>().invoke_select_material(material_id);
+                        }
+                    }
+                },
+            );
+        }
+    });
+
+    // Open the material picker of the project detail view
+    ui.global::<Backend>().on_showMaterialPicker({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        let picker_list = picker_list.clone();
+        move || {
+            let ui = ui_handle.unwrap();
+            // Materials can only be added to a project that is saved
+            if ui.global::<Backend>().get_project().id == 0 {
+                ui.global::<Backend>()
+                    .set_projectDetailViewErrorMessage("Please save the project before adding materials".into());
+                return;
+            }
+            ui.global::<Backend>().set_showMaterialPickerPopUp(true);
+            ui.global::<Backend>().set_pickerSearch("".into());
+            picker_list.set_search("");
+            ui.global::<Backend>().set_pickerSelectedId(0);
+            ui.global::<Backend>().set_pickerAmount("1".into());
+            ui.global::<Backend>().set_pickerErrorMessage("".into());
+
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            let picker_list = picker_list.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token
+                    let token = keycloak_handle.lock().await.refresh_token().await?;
+                    // The materials of the warehouse with their types
+                    let collections = workshop_handle.lock().await.load_all(&token).await?;
+                    Ok::<_, Error>((collections.materials, collections.material_types))
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    let (materials, material_types) = match result {
+                        Ok(data) => data,
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_pickerErrorMessage(e.to_string().into());
+                            return;
+                        }
+                    };
+                    let graph = DomainGraph::new(Collections {
+                        materials,
+                        material_types,
+                        ..Default::default()
+                    });
+                    picker_list.set_items(graph.materials());
+                },
+            );
+        }
+    });
+
+    // Close the material picker
+    ui.global::<Backend>().on_hideMaterialPicker({
+        let ui_handle = ui.as_weak();
+        move || {
+            let ui = ui_handle.unwrap();
+            ui.global::<Backend>().set_showMaterialPickerPopUp(false);
+        }
+    });
+
+    // Search in the materials of the picker
+    ui.global::<Backend>().on_search_picker_material({
+        let picker_list = picker_list.clone();
+        move |search: SharedString| {
+            picker_list.set_search(&search);
+        }
+    });
+
+    // Add the picked material to the project and take the amount out of the warehouse
+    ui.global::<Backend>().on_assign_material({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        let picker_list = picker_list.clone();
+        move |material_id: i32, amount: SharedString| {
+            let ui = ui_handle.unwrap();
+            let project_id = ui.global::<Backend>().get_project().id;
+            let picked = picker_list.items().into_iter().find(|m| m.id == material_id);
+            let Some(picked) = picked else {
+                ui.global::<Backend>().set_pickerErrorMessage("Please choose a material".into());
+                return;
+            };
+            let amount = match validation::positive_integer(&amount) {
+                Ok(amount) if amount > 0 => amount,
+                Ok(_) => {
+                    ui.global::<Backend>().set_pickerErrorMessage("The amount must be at least 1".into());
+                    return;
+                }
+                Err(message) => {
+                    ui.global::<Backend>().set_pickerErrorMessage(message.into());
+                    return;
+                }
+            };
+            // Refuse right away if the stock we know of is too small, the server data is checked again below
+            if amount > picked.quantity {
+                let error = Error::InsufficientStock {
+                    material: picked.name.to_string(),
+                    available: picked.quantity,
+                };
+                ui.global::<Backend>().set_pickerErrorMessage(error.to_string().into());
+                return;
+            }
+
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token, the user needs the permission for this
+                    let mut keycloak = keycloak_handle.lock().await;
+                    let token = keycloak.authorize(Permission::EditProjects).await?;
+                    let user = keycloak.session().username.clone();
+                    drop(keycloak);
+                    let workshop = workshop_handle.lock().await;
+                    // Take the material from the warehouse, this fails if someone else took it in the meantime
+                    workshop
+                        .book_movement(material_id, -amount, MovementReason::Consumption, Some(project_id), &user, &token)
+                        .await?;
+
+                    // Add to the material of the project if it is already there
+                    let existing = workshop
+                        .get_all::<r_ProjectMaterial>(&token)
+                        .await
+                        .map(|all| all.into_iter().find(|m| m.project_id == project_id && m.material_id == material_id));
+                    let result = match existing {
+                        Ok(Some(mut project_material)) => {
+                            project_material.amount += amount;
+                            // What is taken no longer has to be reserved
+                            project_material.reserved = (project_material.reserved - amount).max(0);
+                            workshop.update(&project_material, &token).await
+                        }
+                        Ok(None) => {
+                            let project_material = r_ProjectMaterial {
+                                id: 0, // We can ignore it because the database will set it
+                                project_id,
+                                material_id,
+                                amount,
+                                reserved: 0,
+                            };
+                            workshop.create(&project_material, &token).await.map(|_| ())
+                        }
+                        Err(e) => Err(e),
+                    };
+                    // Put the stock back if the material could not be added to the project
+                    if let Err(e) = result {
+                        let restore = workshop
+                            .book_movement(material_id, amount, MovementReason::Return, Some(project_id), &user, &token)
+                            .await;
+                        // If that fails as well the user has to know the stock is missing the amount
+                        return Err(match restore {
+                            Ok(_) => e,
+                            Err(restore) => not_restored(e, restore),
+                        });
+                    }
+                    Ok(())
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the material was successfully added otherwise handle the error
+                    if let Err(e) = result {
+                        if session_expired(&ui, &e) {
+                            return;
+                        }
+                        ui.global::<Backend>().set_pickerErrorMessage(e.to_string().into());
+                        return;
+                    }
+
+                    ui.global::<Backend>().set_showMaterialPickerPopUp(false);
+
+                    // Show the project again with the new material
+                    ui.global::<Backend>().invoke_route_to_project_i(project_id);
+                },
+            );
+        }
+    });
+
+    // Reserve a material for the project, it stays in the warehouse but counts as demand for the reorder
+    ui.global::<Backend>().on_reserve_material({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        move |material_id: i32, amount: SharedString| {
+            let ui = ui_handle.unwrap();
+            let project_id = ui.global::<Backend>().get_project().id;
+            if material_id == 0 {
+                ui.global::<Backend>().set_pickerErrorMessage("Please choose a material".into());
+                return;
+            }
+            let amount = match validation::positive_integer(&amount) {
+                Ok(amount) if amount > 0 => amount,
+                Ok(_) => {
+                    ui.global::<Backend>().set_pickerErrorMessage("The amount must be at least 1".into());
+                    return;
+                }
+                Err(message) => {
+                    ui.global::<Backend>().set_pickerErrorMessage(message.into());
+                    return;
+                }
+            };
+
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token, the user needs the permission for this
+                    let token = keycloak_handle
+                        .lock()
+                        .await
+                        .authorize(Permission::EditProjects)
+                        .await?;
+                    let workshop = workshop_handle.lock().await;
+                    // Add to the reservation of the project if the material is already there
+                    let existing = workshop
+                        .get_all::<r_ProjectMaterial>(&token)
+                        .await?
+                        .into_iter()
+                        .find(|m| m.project_id == project_id && m.material_id == material_id);
+                    match existing {
+                        Some(mut project_material) => {
+                            project_material.reserved += amount;
+                            workshop.update(&project_material, &token).await
+                        }
+                        None => {
+                            let project_material = r_ProjectMaterial {
+                                id: 0, // We can ignore it because the database will set it
+                                project_id,
+                                material_id,
+                                amount: 0,
+                                reserved: amount,
+                            };
+                            workshop.create(&project_material, &token).await.map(|_| ())
+                        }
+                    }
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the material was successfully reserved otherwise handle the error
+                    if let Err(e) = result {
+                        if session_expired(&ui, &e) {
+                            return;
+                        }
+                        ui.global::<Backend>().set_pickerErrorMessage(e.to_string().into());
+                        return;
+                    }
+
+                    ui.global::<Backend>().set_showMaterialPickerPopUp(false);
+                    ui.global::<Backend>().invoke_route_to_project_i(project_id);
+                },
+            );
+        }
+    });
+
+    // Delete a project
+    ui.global::<Backend>().on_delete_project_i({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        move |project_id: i32| {
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token, the user needs the permission for this
+                    let mut keycloak = keycloak_handle.lock().await;
+                    let token = keycloak.authorize(Permission::DeleteProjects).await?;
+                    let user = keycloak.session().username.clone();
+                    drop(keycloak);
+                    // Delete the project, its materials go back to the warehouse
+                    workshop_handle
+                        .lock()
+                        .await
+                        .delete_project(project_id, &user, &token)
+                        .await
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    match result {
+                        Ok(_) => {
+                            ui.global::<Backend>().invoke_route_to_project_management();
+                        }
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_projectManagementErrorMessage(e.to_string().into());
+                        }
+                    }
+                },
+            );
+        }
+    });
+
+    // Delete a material of a project
+    ui.global::<Backend>().on_deleteMaterial({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        move |material_id: i32| {
+            let ui = ui_handle.unwrap();
+            // Get the project
+            let project = ui.global::<Backend>().get_project();
+            let project_id = project.id;
+
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token, the user needs the permission for this
+                    let mut keycloak = keycloak_handle.lock().await;
+                    let token = keycloak.authorize(Permission::EditProjects).await?;
+                    let user = keycloak.session().username.clone();
+                    drop(keycloak);
+                    let workshop = workshop_handle.lock().await;
+                    // Get the project materials
+                    let project_materials = workshop.get_all::<r_ProjectMaterial>(&token).await?;
+                    // Find the project material that we want to delete
+                    let project_material: &r_ProjectMaterial = project_materials
+                        .iter()
+                        .find(|m| m.material_id == material_id && m.project_id == project_id)
+                        .ok_or(Error::NotFound("Material not found".to_owned()))?;
+                    // Return the amount to the warehouse and delete the material from the project
+                    workshop.remove_project_material(project_material, &user, &token).await
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the material was successfully deleted otherwise handle the error
+                    if let Err(e) = result {
+                        if session_expired(&ui, &e) {
+                            return;
+                        }
+                        ui.global::<Backend>().set_projectDetailViewErrorMessage(e.to_string().into());
+                        return;
+                    }
+
+                    // Set the project again
+                    ui.global::<Backend>().invoke_route_to_project_i(project_id);
+                },
+            );
+        }
+    });
+
+    // Save a new material type
+    ui.global::<Backend>().on_save_material_type({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        let material_list = material_list.clone();
+        let type_list = type_list.clone();
+        move |name: SharedString, description: SharedString| {
+            let ui = ui_handle.unwrap();
+            // The description is optional
+            let mut errors = FieldErrors::default();
+            let name = errors.check("name", validation::required(&name));
+            ui.global::<Backend>().set_materialTypeErrors(MaterialTypeErrors {
+                name: errors.get("name").into(),
+            });
+            let Some(name) = name else {
+                return;
+            };
+
+            let material_type: r_MaterialType = r_MaterialType {
+                // A new type has the id 0, the database will set it
+                id: ui.global::<Backend>().get_editedMaterialTypeId(),
+                name,
+                description: description.trim().to_string(),
+            };
+
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            let material_list = material_list.clone();
+            let type_list = type_list.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token, the user needs the permission for this
+                    let token = keycloak_handle
+                        .lock()
+                        .await
+                        .authorize(Permission::EditMaterials)
+                        .await?;
+                    let workshop = workshop_handle.lock().await;
+                    if material_type.id == 0 {
+                        workshop.create(&material_type, &token).await?;
+                    } else {
+                        workshop.update(&material_type, &token).await?;
+                    }
+                    // Get the lists again so every view offers the saved type
+                    let materials = workshop.get_all::<r_Material>(&token).await?;
+                    let material_types = workshop.get_all::<r_MaterialType>(&token).await?;
+                    Ok::<_, Error>((materials, material_types))
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the material type was successfully saved otherwise handle the error
+                    match result {
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_materialTypePopUpErrorMessage(e.to_string().into());
+                        }
+                        Ok((materials, material_types)) => {
+                            ui.global::<Backend>().set_showMaterialTypePopUp(false);
+                            show_material_types(&ui, &material_list, &type_list, materials, material_types);
+                        }
+                    }
+                },
+            );
+        }
+    });
+
+    // Open the material type popup with the type to edit
+    ui.global::<Backend>().on_editMaterialType({
+        let ui_handle = ui.as_weak();
+        let type_list = type_list.clone();
+        move |type_id: i32| {
+            let ui = ui_handle.unwrap();
+            let usage = type_list.items().into_iter().find(|t| t.m_type.id == type_id);
+            show_material_type_form(&ui, usage.map(|t| t.m_type).as_ref());
+            ui.global::<Backend>().set_showMaterialTypePopUp(true);
+        }
+    });
+
+    // Delete a material type, if materials still use it the user has to choose another type for them first
+    ui.global::<Backend>().on_delete_material_type({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        let material_list = material_list.clone();
+        let type_list = type_list.clone();
+        move |type_id: i32| {
+            let ui = ui_handle.unwrap();
+            let usages = type_list.items();
+            if let Some(usage) = usages.iter().find(|t| t.m_type.id == type_id && t.material_count > 0) {
+                show_reassign_choice(&ui, &usages, usage);
+                return;
+            }
+
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            let material_list = material_list.clone();
+            let type_list = type_list.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token, the user needs the permission for this
+                    let token = keycloak_handle
+                        .lock()
+                        .await
+                        .authorize(Permission::DeleteMaterials)
+                        .await?;
+                    let workshop = workshop_handle.lock().await;
+                    // Someone else may have used the type in the meantime
+                    let materials = workshop.get_all::<r_Material>(&token).await?;
+                    let count = materials.iter().filter(|m| m.type_id == type_id).count();
+                    if count > 0 {
+                        return Err(Error::Conflict(format!(
+                            "The material type is still used by {} material(s)",
+                            count
+                        )));
+                    }
+                    workshop.delete::<r_MaterialType>(type_id, &token).await?;
+                    let material_types = workshop.get_all::<r_MaterialType>(&token).await?;
+                    Ok((materials, material_types))
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the material type was successfully deleted otherwise handle the error
+                    match result {
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_materialTypeManagementErrorMessage(e.to_string().into());
+                        }
+                        Ok((materials, material_types)) => {
+                            ui.global::<Backend>().set_materialTypeManagementErrorMessage("".into());
+                            show_material_types(&ui, &material_list, &type_list, materials, material_types);
+                        }
+                    }
+                },
+            );
+        }
+    });
+
+    // Give all materials of a type another type and delete the type afterwards
+    ui.global::<Backend>().on_reassign_material_type({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        let material_list = material_list.clone();
+        let type_list = type_list.clone();
+        move |type_id: i32, target_index: i32| {
+            let ui = ui_handle.unwrap();
+            let target = usize::try_from(target_index)
+                .ok()
+                .and_then(|index| ui.global::<Backend>().get_reassignTypes().row_data(index));
+            let Some(target) = target else {
+                ui.global::<Backend>()
+                    .set_materialTypeManagementErrorMessage("Please choose the type the materials get".into());
+                return;
+            };
+
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            let material_list = material_list.clone();
+            let type_list = type_list.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token, the user needs both permissions for this
+                    let mut keycloak = keycloak_handle.lock().await;
+                    keycloak.authorize(Permission::EditMaterials).await?;
+                    let token = keycloak.authorize(Permission::DeleteMaterials).await?;
+                    drop(keycloak);
+                    let workshop = workshop_handle.lock().await;
+                    for mut material in workshop.get_all::<r_Material>(&token).await? {
+                        if material.type_id == type_id {
+                            material.type_id = target.id;
+                            workshop.update(&material, &token).await?;
+                        }
+                    }
+                    workshop.delete::<r_MaterialType>(type_id, &token).await?;
+                    let materials = workshop.get_all::<r_Material>(&token).await?;
+                    let material_types = workshop.get_all::<r_MaterialType>(&token).await?;
+                    Ok::<_, Error>((materials, material_types))
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the materials were moved and the type deleted otherwise handle the error
+                    match result {
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_materialTypeManagementErrorMessage(e.to_string().into());
+                        }
+                        Ok((materials, material_types)) => {
+                            ui.global::<Backend>().set_materialTypeManagementErrorMessage("".into());
+                            ui.global::<Backend>().set_deletedMaterialTypeId(0);
+                            show_material_types(&ui, &material_list, &type_list, materials, material_types);
+                        }
+                    }
+                },
+            );
+        }
+    });
+
+    // Search for a material type
+    ui.global::<Backend>().on_search_material_type({
+        let type_list = type_list.clone();
+        move |search: SharedString| {
+            type_list.set_search(&search);
+        }
+    });
+
+    // Move a project to the next step of its workflow
+    ui.global::<Backend>().on_change_project_status({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        move |project_id: i32, status_index: i32| {
+            let ui = ui_handle.unwrap();
+            let status = usize::try_from(status_index)
+                .ok()
+                .and_then(|index| ProjectStatus::ALL.get(index).copied());
+            let Some(status) = status else {
+                ui.global::<Backend>().set_projectDetailViewErrorMessage("Unknown status".into());
+                return;
+            };
+
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token, the user needs the permission for this
+                    let token = keycloak_handle
+                        .lock()
+                        .await
+                        .authorize(Permission::EditProjects)
+                        .await?;
+                    let workshop = workshop_handle.lock().await;
+                    // The status on the server decides which step is allowed, not the one that is shown
+                    let mut project = workshop.get_by_id::<r_Project>(project_id, &token).await?;
+                    project.change_status(status)?;
+                    workshop.update(&project, &token).await
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the status was successfully changed otherwise handle the error
+                    if let Err(e) = result {
+                        if session_expired(&ui, &e) {
+                            return;
+                        }
+                        ui.global::<Backend>().set_projectDetailViewErrorMessage(e.to_string().into());
+                        return;
+                    }
+                    ui.global::<Backend>().invoke_route_to_project_i(project_id);
+                },
+            );
+        }
+    });
+
+    // Save a new client
+    ui.global::<Backend>().on_saveClient({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        move |first_name: SharedString, last_name: SharedString, phone: SharedString| {
+            let ui = ui_handle.unwrap();
+            // Check every field so all errors can be shown at once, the phone number is optional
+            let mut errors = FieldErrors::default();
+            let first_name = errors.check("first_name", validation::required(&first_name));
+            let last_name = errors.check("last_name", validation::required(&last_name));
+            ui.global::<Backend>().set_clientErrors(ClientErrors {
+                firstName: errors.get("first_name").into(),
+                lastName: errors.get("last_name").into(),
+            });
+            let (Some(first_name), Some(last_name)) = (first_name, last_name) else {
+                return;
+            };
+
+            let client: r_Client = r_Client {
+                // A new client has the id 0, the database will set it
+                id: ui.global::<Backend>().get_editedClientId(),
+                firstname: first_name,
+                lastname: last_name,
+                phone: phone.trim().to_string(),
+            };
+
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token, the user needs the permission for this
+                    let token = keycloak_handle
+                        .lock()
+                        .await
+                        .authorize(Permission::EditClients)
+                        .await?;
+                    let workshop = workshop_handle.lock().await;
+                    if client.id == 0 {
+                        workshop.create(&client, &token).await?;
+                    } else {
+                        workshop.update(&client, &token).await?;
+                    }
+                    Ok::<_, Error>(())
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the client was successfully saved otherwise handle the error
+                    match result {
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_clientPopUpErrorMessage(e.to_string().into());
+                        }
+                        Ok(()) => {
+                            ui.global::<Backend>().set_showClientPopUp(false);
+                            // Show the saved client in the list
+                            if ui.global::<Backend>().get_clientManagementView() {
+                                ui.global::<Backend>().invoke_route_to_client_management();
+                            }
+                        }
+                    }
+                },
+            );
+        }
+    });
+
+    // Open the client popup with the client to edit
+    ui.global::<Backend>().on_editClient({
+        let ui_handle = ui.as_weak();
+        let client_list = client_list.clone();
+        move |client_id: i32| {
+            let ui = ui_handle.unwrap();
+            let client = client_list.items().into_iter().find(|c| c.id == client_id);
+            show_client_form(&ui, client.as_ref());
+            ui.global::<Backend>().set_showClientPopUp(true);
+        }
+    });
+
+    // Delete a client that has no projects
+    ui.global::<Backend>().on_delete_client({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        move |client_id: i32| {
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token, the user needs the permission for this
+                    let token = keycloak_handle
+                        .lock()
+                        .await
+                        .authorize(Permission::DeleteClients)
+                        .await?;
+                    let workshop = workshop_handle.lock().await;
+                    // A client that still has projects can not be deleted
+                    let projects = workshop.get_all::<r_Project>(&token).await?;
+                    let count = projects.iter().filter(|p| p.client_id == client_id).count();
+                    if count > 0 {
+                        return Err(Error::Conflict(format!("The client still has {} project(s)", count)));
+                    }
+                    workshop.delete::<r_Client>(client_id, &token).await
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the client was successfully deleted otherwise handle the error
+                    match result {
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_clientManagementErrorMessage(e.to_string().into());
+                        }
+                        Ok(()) => {
+                            ui.global::<Backend>().invoke_route_to_client_management();
+                        }
+                    }
+                },
+            );
+        }
+    });
+
+    // Show the projects of a client
+    ui.global::<Backend>().on_select_client({
+        let ui_handle = ui.as_weak();
+        let client_graph = client_graph.clone();
+        move |client_id: i32| {
+            let ui = ui_handle.unwrap();
+            show_client_projects(&ui, &client_graph.borrow(), client_id);
+        }
+    });
+
+    // Search for a client
+    ui.global::<Backend>().on_search_client({
+        let client_list = client_list.clone();
+        move |search: SharedString| {
+            client_list.set_search(&search);
+        }
+    });
+
+    // Open the supplier popup with the supplier to edit, 0 for a new one
+    ui.global::<Backend>().on_editSupplier({
+        let ui_handle = ui.as_weak();
+        let supplier_list = supplier_list.clone();
+        move |supplier_id: i32| {
+            let ui = ui_handle.unwrap();
+            let supplier = supplier_list.items().into_iter().find(|s| s.id == supplier_id);
+            show_supplier_form(&ui, supplier.as_ref());
+            ui.global::<Backend>().set_showSupplierPopUp(true);
+        }
+    });
+
+    ui.global::<Backend>().on_hideSupplierPopUp({
+        let ui_handle = ui.as_weak();
+        move || {
+            let ui = ui_handle.unwrap();
+            ui.global::<Backend>().set_showSupplierPopUp(false);
+        }
+    });
+
+    // Save a new or edited supplier
+    ui.global::<Backend>().on_save_supplier({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        move || {
+            let ui = ui_handle.unwrap();
+            // Check every field so all errors can be shown at once, the contact is optional
+            let mut errors = FieldErrors::default();
+            let backend = ui.global::<Backend>();
+            let name = errors.check("name", validation::required(&backend.get_supplierName()));
+            let lead_time = errors.check("lead_time", validation::positive_integer(&backend.get_supplierLeadTime()));
+            let minimum_order = backend.get_supplierMinimumOrder();
+            let minimum = errors.check("minimum_order", validation::positive_integer(&minimum_order));
+            backend.set_supplierErrors(SupplierErrors {
+                name: errors.get("name").into(),
+                lead_time: errors.get("lead_time").into(),
+                minimum_order: errors.get("minimum_order").into(),
+            });
+            let (Some(name), Some(lead_time), Some(minimum)) = (name, lead_time, minimum) else {
+                return;
+            };
+
+            let supplier = Supplier {
+                // A new supplier has the id 0, the database will set it
+                id: backend.get_editedSupplierId(),
+                name,
+                contact: backend.get_supplierContact().trim().to_string(),
+                lead_time_days: lead_time,
+                minimum_order_quantity: minimum,
+            };
+
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token, the user needs the permission for this
+                    let token = keycloak_handle
+                        .lock()
+                        .await
+                        .authorize(Permission::EditMaterials)
+                        .await?;
+                    let workshop = workshop_handle.lock().await;
+                    if supplier.id == 0 {
+                        workshop.create(&supplier, &token).await?;
+                    } else {
+                        workshop.update(&supplier, &token).await?;
+                    }
+                    Ok::<_, Error>(())
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the supplier was successfully saved otherwise handle the error
+                    match result {
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_supplierPopUpErrorMessage(e.to_string().into());
+                        }
+                        Ok(()) => {
+                            ui.global::<Backend>().set_showSupplierPopUp(false);
+                            ui.global::<Backend>().invoke_route_to_supplier_management();
+                        }
+                    }
+                },
+            );
+        }
+    });
+
+    // Delete a supplier together with the materials they offer
+    ui.global::<Backend>().on_delete_supplier({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        move |supplier_id: i32| {
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token, the user needs the permission for this
+                    let token = keycloak_handle
+                        .lock()
+                        .await
+                        .authorize(Permission::DeleteMaterials)
+                        .await?;
+                    let workshop = workshop_handle.lock().await;
+                    // The orders are kept as a record of what was bought, so their supplier can not be deleted
+                    let orders = workshop.get_all::<PurchaseOrder>(&token).await?;
+                    let count = orders.iter().filter(|o| o.supplier_id == supplier_id).count();
+                    if count > 0 {
+                        return Err(Error::Conflict(format!("The supplier still has {} purchase order(s)", count)));
+                    }
+                    // The offers would point to nothing without the supplier, the list of the view may be outdated
+                    let offers = workshop.get_all::<SupplierMaterial>(&token).await?;
+                    for offer in offers.iter().filter(|o| o.supplier_id == supplier_id) {
+                        workshop.delete::<SupplierMaterial>(offer.id, &token).await?;
+                    }
+                    workshop.delete::<Supplier>(supplier_id, &token).await
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the supplier was successfully deleted otherwise handle the error
+                    if let Err(e) = result {
+                        if session_expired(&ui, &e) {
+                            return;
+                        }
+                        ui.global::<Backend>().set_supplierManagementErrorMessage(e.to_string().into());
+                        return;
+                    }
+                    ui.global::<Backend>().invoke_route_to_supplier_management();
+                },
+            );
+        }
+    });
+
+    // Show the materials a supplier offers
+    ui.global::<Backend>().on_select_supplier({
+        let ui_handle = ui.as_weak();
+        let supplier_list = supplier_list.clone();
+        let supplier_offers = supplier_offers.clone();
+        move |supplier_id: i32| {
+            let ui = ui_handle.unwrap();
+            show_supplier_materials(&ui, &supplier_list.items(), &supplier_offers.borrow(), supplier_id);
+        }
+    });
+
+    // Search for a supplier
+    ui.global::<Backend>().on_search_supplier({
+        let supplier_list = supplier_list.clone();
+        move |search: SharedString| {
+            supplier_list.set_search(&search);
+        }
+    });
+
+    // Add an offer of the chosen supplier or change the one they already have for the material
+    ui.global::<Backend>().on_save_supplier_material({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        let supplier_offers = supplier_offers.clone();
+        move || {
+            let ui = ui_handle.unwrap();
+            let backend = ui.global::<Backend>();
+            let supplier_id = backend.get_selectedSupplierId();
+            let material = usize::try_from(backend.get_offerMaterialIndex())
+                .ok()
+                .and_then(|index| backend.get_offerMaterials().row_data(index));
+            let mut errors = FieldErrors::default();
+            if material.is_none() {
+                errors.add("material", "Please choose a material");
+            }
+            let price = errors.check("price", validation::money(&backend.get_offerPrice()));
+            backend.set_supplierMaterialErrors(SupplierMaterialErrors {
+                material: errors.get("material").into(),
+                price: errors.get("price").into(),
+            });
+            let (Some(material), Some(price)) = (material, price) else {
+                return;
+            };
+
+            let existing = supplier_offers
+                .borrow()
+                .iter()
+                .find(|o| o.supplier_id == supplier_id && o.material_id == material.id)
+                .map(|o| o.id);
+            let offer = SupplierMaterial {
+                // A new offer has the id 0, the database will set it
+                id: existing.unwrap_or(0),
+                supplier_id,
+                material_id: material.id,
+                article_number: backend.get_offerArticleNumber().trim().to_string(),
+                price,
+                preferred: backend.get_offerPreferred(),
+            };
+            // A material has only one preferred supplier
+            let demoted: Vec<SupplierMaterial> = match offer.preferred {
+                true => supplier_offers
+                    .borrow()
+                    .iter()
+                    .filter(|o| o.material_id == material.id && o.id != offer.id && o.preferred)
+                    .map(|o| SupplierMaterial {
+                        preferred: false,
+                        ..o.clone()
+                    })
+                    .collect(),
+                false => Vec::new(),
+            };
+
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token, the user needs the permission for this
+                    let token = keycloak_handle
+                        .lock()
+                        .await
+                        .authorize(Permission::EditMaterials)
+                        .await?;
+                    let workshop = workshop_handle.lock().await;
+                    for other in demoted {
+                        workshop.update(&other, &token).await?;
+                    }
+                    if offer.id == 0 {
+                        workshop.create(&offer, &token).await?;
+                    } else {
+                        workshop.update(&offer, &token).await?;
+                    }
+                    Ok::<_, Error>(())
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the offer was successfully saved otherwise handle the error
+                    match result {
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_supplierManagementErrorMessage(e.to_string().into());
+                        }
+                        Ok(()) => {
+                            ui.global::<Backend>().set_offerArticleNumber("".into());
+                            ui.global::<Backend>().set_offerPrice("".into());
+                            ui.global::<Backend>().set_offerPreferred(false);
+                            ui.global::<Backend>().invoke_route_to_supplier_management();
+                        }
+                    }
+                },
+            );
+        }
+    });
+
+    // Delete an offer of the chosen supplier
+    ui.global::<Backend>().on_delete_supplier_material({
+        let ui_handle = ui.as_weak();
+        let workshop_handle = arc_workshop_service.clone();
+        let keycloak_handle = arc_keycloak.clone();
+        move |offer_id: i32| {
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            runtime::spawn(
+                async move {
+                    // Refresh the token, the user needs the permission for this
+                    let token = keycloak_handle
+                        .lock()
+                        .await
+                        .authorize(Permission::DeleteMaterials)
+                        .await?;
+                    workshop_handle.lock().await.delete::<SupplierMaterial>(offer_id, &token).await
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    // Check if the offer was successfully deleted otherwise handle the error
+                    if let Err(e) = result {
+                        if session_expired(&ui, &e) {
+                            return;
+                        }
+                        ui.global::<Backend>().set_supplierManagementErrorMessage(e.to_string().into());
+                        return;
+                    }
+                    ui.global::<Backend>().invoke_route_to_supplier_management();
+                },
+            );
+        }
+    });
+
+    // Search for a material
+    ui.global::<Backend>().on_search_material({
+        let material_list = material_list.clone();
+        move |search: SharedString| {
+            material_list.set_search(&search);
+        }
+    });
+
+    // Only show the projects of a status
+    ui.global::<Backend>().on_filter_project_status({
+        let project_list = project_list.clone();
+        move |filter_index: i32| {
+            project_list.set_scope(project_scope(filter_index));
+        }
+    });
+
+    // Search for a project
+    ui.global::<Backend>().on_search_project({
+        let project_list = project_list.clone();
+        move |search: SharedString| {
+            project_list.set_search(&search);
+        }
+    });
+
+    // Send the changes of the outbox, a conflict is shown to the user who decides which version wins
+    ui.global::<Backend>().on_replay_outbox({
+        let ui_handle = ui.as_weak();
+        let keycloak_handle = arc_keycloak.clone();
+        let workshop_handle = arc_workshop_service.clone();
+        let current_conflict = current_conflict.clone();
+        move || {
+            // A conflict has to be resolved before the later changes can be sent
+            if current_conflict.borrow().is_some() {
+                return;
+            }
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            let current_conflict = current_conflict.clone();
+            runtime::spawn(
+                async move {
+                    let token = keycloak_handle.lock().await.refresh_token().await?;
+                    workshop_handle.lock().await.replay_outbox(&token).await
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    match result {
+                        // The server is still not reachable, the changes stay in the outbox
+                        Err(Error::Network(_) | Error::Timeout) => {}
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_syncErrorMessage(e.to_string().into());
+                        }
+                        Ok(Some(conflict)) => {
+                            show_conflict(&ui, &conflict);
+                            *current_conflict.borrow_mut() = Some(conflict);
+                        }
+                        Ok(None) => {
+                            ui.global::<Backend>().set_syncErrorMessage("".into());
+                            refresh_current_view(&ui);
+                        }
+                    }
+                },
+            );
+        }
+    });
+
+    // Settle the shown conflict and send the rest of the outbox
+    ui.global::<Backend>().on_resolve_conflict({
+        let ui_handle = ui.as_weak();
+        let keycloak_handle = arc_keycloak.clone();
+        let workshop_handle = arc_workshop_service.clone();
+        let current_conflict = current_conflict.clone();
+        move |keep_mine| {
+            let Some(conflict) = current_conflict.borrow().clone() else {
+                return;
+            };
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            let current_conflict = current_conflict.clone();
+            runtime::spawn(
+                async move {
+                    let token = keycloak_handle.lock().await.refresh_token().await?;
+                    workshop_handle
+                        .lock()
+                        .await
+                        .resolve_conflict(&conflict, keep_mine, &token)
+                        .await
+                },
+                move |result| {
+                    let ui = ui_handle.unwrap();
+                    match result {
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            ui.global::<Backend>().set_syncErrorMessage(e.to_string().into());
+                        }
+                        Ok(()) => {
+                            ui.global::<Backend>().set_syncErrorMessage("".into());
+                            ui.global::<Backend>().set_showConflictPopUp(false);
+                            *current_conflict.borrow_mut() = None;
+                            ui.global::<Backend>().invoke_replay_outbox();
+                        }
+                    }
+                },
+            );
+        }
+    });
+
+    // Refresh the data in the background, only the models whose collections changed are built again
+    let poll_timer = slint::Timer::default();
+    if let Some(interval) = config.poll_interval() {
+        let ui_handle = ui.as_weak();
+        let keycloak_handle = arc_keycloak.clone();
+        let workshop_handle = arc_workshop_service.clone();
+        let project_list = project_list.clone();
+        let material_list = material_list.clone();
+        let client_list = client_list.clone();
+        let type_list = type_list.clone();
+        let client_graph = client_graph.clone();
+        let shown = shown.clone();
+        // A slow server should not get a second sync before the first one is done
+        let polling = Rc::new(Cell::new(false));
+        poll_timer.start(slint::TimerMode::Repeated, interval, move || {
+            let ui = ui_handle.unwrap();
+            if ui.global::<Backend>().get_loginView() || polling.get() {
+                return;
+            }
+            polling.set(true);
+            let ui_handle = ui_handle.clone();
+            let workshop_handle = workshop_handle.clone();
+            let keycloak_handle = keycloak_handle.clone();
+            let project_list = project_list.clone();
+            let material_list = material_list.clone();
+            let client_list = client_list.clone();
+            let type_list = type_list.clone();
+            let client_graph = client_graph.clone();
+            let shown = shown.clone();
+            let polling = polling.clone();
+            runtime::spawn(
+                async move {
+                    let token = keycloak_handle.lock().await.refresh_token().await?;
+                    let mut workshop = workshop_handle.lock().await;
+                    let (collections, _) = workshop.sync(&token).await?;
+                    Ok::<_, Error>((collections, workshop.revisions()))
+                },
+                move |result| {
+                    polling.set(false);
+                    let ui = ui_handle.unwrap();
+                    let (collections, revisions) = match result {
+                        Ok(data) => data,
+                        Err(e) => {
+                            if session_expired(&ui, &e) {
+                                return;
+                            }
+                            // Nobody asked for this refresh, the next navigation shows the error
+                            eprintln!("Failed to refresh the data: {}", e);
+                            return;
+                        }
+                    };
+                    // Compared with what the lists show, a route may have synced without building them again
+                    let shown_now = shown.get();
+                    let projects = revisions.changes_since(&shown_now.projects).projects_view();
+                    let materials = revisions.changes_since(&shown_now.materials).materials_view()
+                        || revisions.changes_since(&shown_now.types).materials_view();
+                    let clients = revisions.changes_since(&shown_now.clients).clients_view();
+                    if !(projects || materials || clients) {
+                        return;
+                    }
+
+                    let graph = DomainGraph::new(collections);
+                    if projects {
+                        project_list.set_items(graph.projects());
+                        ui.global::<Backend>()
+                            .set_recentProjects(ModelRc::new(VecModel::from(graph.open_projects())));
+                        // Only the shown project is replaced, the form keeps what the user typed
+                        let project_id = ui.global::<Backend>().get_project().id;
+                        if let Some(project) = graph.project(project_id) {
+                            ui.global::<Backend>().set_project(project);
+                        }
+                    }
+                    if materials {
+                        material_list.set_items(graph.materials());
+                        type_list.set_items(graph.material_type_usages());
+                        ui.global::<Backend>()
+                            .set_materialAlerts(ModelRc::new(VecModel::from(graph.material_alerts())));
+                    }
+                    if clients {
+                        client_list.set_items(graph.clients());
+                        show_client_projects(&ui, &graph, ui.global::<Backend>().get_selectedClientId());
+                        *client_graph.borrow_mut() = graph;
+                    }
+                    shown.set(Shown {
+                        projects: revisions,
+                        materials: revisions,
+                        types: revisions,
+                        clients: revisions,
+                    });
+                },
+            );
+        });
+    }
+
+    // Exit application
+    ui.global::<Backend>().on_request_exit({
+        move || {
+            std::process::exit(0);
+        }
+    });
+
+    ui.run()
+}
+
+// The revisions of the collections each list was last built from
+#[derive(Clone, Copy, Default)]
+struct Shown {
+    projects: Revisions,
+    materials: Revisions,
+    types: Revisions,
+    clients: Revisions,
+}
+
+// The views of the window, exactly one of them is shown at a time
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum View {
+    Login,
+    Dashboard,
+    ProjectManagement,
+    ProjectDetail,
+    Warehouse,
+    ClientManagement,
+    MaterialTypeManagement,
+    GoodsReceipt,
+    SupplierManagement,
+    Reorder,
+    PurchaseOrders,
+}
+
+// Show the view and hide all others together with every open popup
+fn show_view(ui: &WorkshopClient, view: View) {
+    let backend = ui.global::<Backend>();
+    backend.set_loginView(view == View::Login);
+    backend.set_projectView(view == View::Dashboard);
+    backend.set_projectManagementView(view == View::ProjectManagement);
+    backend.set_projectDetailView(view == View::ProjectDetail);
+    backend.set_lagerOverviewView(view == View::Warehouse);
+    backend.set_clientManagementView(view == View::ClientManagement);
+    backend.set_materialTypeManagementView(view == View::MaterialTypeManagement);
+    backend.set_goodsReceiptView(view == View::GoodsReceipt);
+    backend.set_supplierManagementView(view == View::SupplierManagement);
+    backend.set_reorderView(view == View::Reorder);
+    backend.set_purchaseOrderView(view == View::PurchaseOrders);
+    backend.set_showClientPopUp(false);
+    backend.set_showMaterialPopUp(false);
+    backend.set_showMaterialTypePopUp(false);
+    backend.set_showMaterialPickerPopUp(false);
+    backend.set_showSupplierPopUp(false);
+}
+
+// Send the user back to the login view if the session could not be refreshed, returns true if that happened
+fn session_expired(ui: &WorkshopClient, error: &Error) -> bool {
+    if *error != Error::SessionExpired {
+        return false;
+    }
+    ui.global::<Backend>().invoke_request_logout();
+    ui.global::<Backend>().set_login_error(error.to_string().into());
+    true
+}
+
+// Hide everything in the UI that the user is not allowed to do, the handlers check the same permissions again
+fn show_permissions(ui: &WorkshopClient, session: &UserSession) {
+    ui.global::<Backend>().set_canEditProjects(session.allows(Permission::EditProjects));
+    ui.global::<Backend>().set_canDeleteProjects(session.allows(Permission::DeleteProjects));
+    ui.global::<Backend>().set_canEditMaterials(session.allows(Permission::EditMaterials));
+    ui.global::<Backend>().set_canDeleteMaterials(session.allows(Permission::DeleteMaterials));
+    ui.global::<Backend>().set_canEditClients(session.allows(Permission::EditClients));
+    ui.global::<Backend>().set_canDeleteClients(session.allows(Permission::DeleteClients));
+}
+
+// Fill the project form with the project
+fn show_project_form(ui: &WorkshopClient, project: ProjectModel, estimated_costs: Money) {
+    // A new project starts with empty fields instead of zeros
+    let is_new = project.id == 0;
+    ui.global::<Backend>().set_projectName(project.name.clone());
+    ui.global::<Backend>().set_projectStart(project.project_start.clone());
+    ui.global::<Backend>().set_projectDeadline(project.project_deadline.clone());
+    ui.global::<Backend>().set_projectCosts(match is_new {
+        true => "".into(),
+        false => estimated_costs.input_text().into(),
+    });
+    ui.global::<Backend>().set_projectHours(match is_new {
+        true => "".into(),
+        false => project.estimated_hours.to_string().into(),
+    });
+    ui.global::<Backend>().set_projectErrors(ProjectErrors::default());
+    ui.global::<Backend>().set_projectDetailViewErrorMessage("".into());
+    ui.global::<Backend>().set_projectScheduleWarning("".into());
+    ui.global::<Backend>().set_project(project);
+}
+
+// Warn if the deadline of the form collides with a project of the same client or the workshop has no time left
+fn show_schedule_warning(ui: &WorkshopClient, projects: &[r_Project], weekly_capacity: Option<f32>) {
+    let backend = ui.global::<Backend>();
+    let start = date::parse_input(&backend.get_projectStart());
+    let deadline = date::parse_input(&backend.get_projectDeadline());
+    // Invalid dates are reported when the project is saved
+    let (Some(start), Some(deadline)) = (start, deadline) else {
+        backend.set_projectScheduleWarning("".into());
+        return;
+    };
+    if r_Project::check_schedule(start, deadline).is_err() {
+        backend.set_projectScheduleWarning("".into());
+        return;
+    }
+    let client = usize::try_from(backend.get_projectClientIndex())
+        .ok()
+        .and_then(|index| backend.get_clients().row_data(index));
+    let project_id = backend.get_project().id;
+    let saved = projects.iter().find(|p| p.id == project_id);
+    // The project as it would be saved, the checks only need the schedule
+    let planned = r_Project {
+        id: project_id,
+        name: String::new(),
+        client_id: client.map(|c| c.id).unwrap_or(0),
+        description: String::new(),
+        startpoint: Some(start),
+        endpoint: Some(deadline),
+        estimated_costs: Money::ZERO,
+        estimated_hours: validation::positive_decimal(&backend.get_projectHours()).unwrap_or(0.0),
+        costs: Money::ZERO,
+        status: saved.map(|p| p.status).unwrap_or(ProjectStatus::Inquiry),
+    };
+    // Nothing is planned for a project that is done
+    if planned.status.is_closed() {
+        backend.set_projectScheduleWarning("".into());
+        return;
+    }
+
+    let mut warnings: Vec<String> = Vec::new();
+    let collisions: Vec<String> = schedule::client_collisions(&planned, projects)
+        .iter()
+        .filter_map(|p| p.schedule().map(|(start, end)| (p, start, end)))
+        .map(|(p, start, end)| format!("{} ({} - {})", p.name, date::display(start), date::display(end)))
+        .collect();
+    if planned.client_id != 0 && !collisions.is_empty() {
+        warnings.push(format!("The client has other projects on the deadline: {}", collisions.join(", ")));
+    }
+    if let Some(capacity) = weekly_capacity {
+        if let Some(load) = schedule::overloaded_week(&planned, projects, capacity) {
+            warnings.push(format!(
+                "The week of {} needs {:.0} of {:.0} hours of the workshop",
+                date::display(load.week),
+                load.hours,
+                capacity
+            ));
+        }
+    }
+    backend.set_projectScheduleWarning(warnings.join("\n").into());
+}
+
+// Offer all clients for the project form and preselect the client with the id
+fn show_client_choice(ui: &WorkshopClient, clients: &[r_Client], client_id: i32) {
+    let mut clients = clients.to_vec();
+    clients.sort_by(|a, b| (&a.lastname, &a.firstname).cmp(&(&b.lastname, &b.firstname)));
+    let client_names: Vec<SharedString> = clients
+        .iter()
+        .map(|c| format!("{} {}", c.firstname, c.lastname).into())
+        .collect();
+    let client_index = clients.iter().position(|c| c.id == client_id);
+    let client_models: Vec<ClientModel> = clients
+        .iter()
+        .map(|c| ClientModel {
+            id: c.id,
+            firstName: c.firstname.clone().into(),
+            lastName: c.lastname.clone().into(),
+            phone: c.phone.clone().into(),
+        })
+        .collect();
+
+    ui.global::<Backend>().set_clients(ModelRc::new(VecModel::from(client_models)));
+    ui.global::<Backend>().set_clientNames(ModelRc::new(VecModel::from(client_names.clone())));
+    ui.global::<Backend>().set_projectClientIndex(client_index.map(|i| i as i32).unwrap_or(-1));
+    ui.global::<Backend>().set_projectClientName(client_index.map(|i| client_names[i].clone()).unwrap_or_default());
+}
+
+// Fill the client popup with the client, `None` clears it for a new one
+fn show_client_form(ui: &WorkshopClient, client: Option<&ClientModel>) {
+    let client = client.cloned().unwrap_or_default();
+    ui.global::<Backend>().set_editedClientId(client.id);
+    ui.global::<Backend>().set_clientFirstName(client.firstName);
+    ui.global::<Backend>().set_clientLastName(client.lastName);
+    ui.global::<Backend>().set_clientPhone(client.phone);
+    ui.global::<Backend>().set_clientErrors(ClientErrors::default());
+    ui.global::<Backend>().set_clientPopUpErrorMessage("".into());
+}
+
+// Show the projects of the chosen client with the totals of their costs, an unknown client clears the panel
+fn show_client_projects(ui: &WorkshopClient, graph: &DomainGraph, client_id: i32) {
+    let (client, projects) = match graph.has_client(client_id) {
+        true => (graph.client(client_id), graph.client_projects(client_id)),
+        false => (ClientModel::default(), Vec::new()),
+    };
+    let (calculated_costs, current_costs) = graph.client_costs(client.id);
+
+    ui.global::<Backend>().set_selectedClientId(client.id);
+    ui.global::<Backend>().set_selectedClient(client);
+    ui.global::<Backend>().set_clientProjects(ModelRc::new(VecModel::from(projects)));
+    ui.global::<Backend>().set_clientCalculatedCosts(calculated_costs.to_string().into());
+    ui.global::<Backend>().set_clientCurrentCosts(current_costs.to_string().into());
+}
+
+// Show the material with its movements, the newest first
+fn show_stock_history(
+    ui: &WorkshopClient,
+    material: r_Material,
+    movements: &[StockMovement],
+    projects: Vec<r_Project>,
+    material_types: Vec<r_MaterialType>,
+) {
+    let ledger_amount = stock_movement::ledger_amount(movements, material.id);
+    let mut movements: Vec<&StockMovement> = movements.iter().filter(|m| m.material_id == material.id).collect();
+    // The newest first, times with different offsets are compared as points in time and unreadable ones come last
+    movements.sort_by_key(|m| std::cmp::Reverse((m.time(), m.id)));
+    let graph = DomainGraph::new(Collections {
+        projects,
+        materials: vec![material.clone()],
+        material_types,
+        ..Default::default()
+    });
+
+    let rows: Vec<StockMovementModel> = movements
+        .into_iter()
+        .map(|m| StockMovementModel {
+            // Shown in local time, a timestamp the client can not read is shown as it is
+            date: m
+                .time()
+                .map(|t| date::display_date_time(t.with_timezone(&Local)))
+                .unwrap_or_else(|| m.timestamp.clone())
+                .into(),
+            reason: m.reason.label().into(),
+            delta: format!("{:+}", m.delta).into(),
+            project: m
+                .project_id
+                .map(|id| match graph.project(id) {
+                    Some(project) => project.name.to_string(),
+                    None => format!("Unknown project #{}", id),
+                })
+                .unwrap_or_default()
+                .into(),
+            user: m.user.clone().into(),
+        })
+        .collect();
+
+    if let Some(material) = graph.materials().into_iter().next() {
+        ui.global::<Backend>().set_selectedMaterial(material);
+    }
+    ui.global::<Backend>().set_ledgerAmount(ledger_amount);
+    ui.global::<Backend>().set_stockMovements(ModelRc::new(VecModel::from(rows)));
+}
+
+// Show the lines of the delivery that is entered with their totals
+fn show_receipt_lines(ui: &WorkshopClient, lines: &[ReceiptLine]) {
+    let materials = ui.global::<Backend>().get_receiptMaterials();
+    let rows: Vec<ReceiptLineModel> = lines
+        .iter()
+        .map(|line| ReceiptLineModel {
+            material_id: line.material_id,
+            name: materials
+                .iter()
+                .find(|m| m.id == line.material_id)
+                .map(|m| m.name)
+                .unwrap_or_else(|| format!("Unknown material #{}", line.material_id).into()),
+            quantity: line.quantity,
+            unit_price: line.unit_price.to_string().into(),
+            total: line.total().to_string().into(),
+        })
+        .collect();
+    let total: Money = lines.iter().map(ReceiptLine::total).sum();
+
+    ui.global::<Backend>().set_receiptLines(ModelRc::new(VecModel::from(rows)));
+    ui.global::<Backend>().set_receiptTotal(total.to_string().into());
+}
+
+// Fill the material popup with the material, `None` clears it for a new one
+fn show_material_form(ui: &WorkshopClient, material: Option<&r_Material>) {
+    match material {
+        Some(material) => {
+            ui.global::<Backend>().set_editedMaterialId(material.id);
+            ui.global::<Backend>().set_materialName(material.name.clone().into());
+            ui.global::<Backend>().set_materialPrice(material.costs.input_text().into());
+            ui.global::<Backend>().set_materialAmount(material.amount.to_string().into());
+            ui.global::<Backend>().set_materialThreshold(material.threshold_value.to_string().into());
+            ui.global::<Backend>().set_materialDescription(material.description.clone().into());
+        }
+        None => {
+            ui.global::<Backend>().set_editedMaterialId(0);
+            ui.global::<Backend>().set_materialName("".into());
+            ui.global::<Backend>().set_materialPrice("".into());
+            ui.global::<Backend>().set_materialAmount("".into());
+            ui.global::<Backend>().set_materialThreshold("".into());
+            ui.global::<Backend>().set_materialDescription("".into());
+        }
+    }
+    ui.global::<Backend>().set_materialErrors(MaterialErrors::default());
+    ui.global::<Backend>().set_materialPopUpErrorMessage("".into());
+}
+
+// Fill the material type popup with the type, `None` clears it for a new one
+fn show_material_type_form(ui: &WorkshopClient, material_type: Option<&MaterialType>) {
+    let material_type = material_type.cloned().unwrap_or_default();
+    ui.global::<Backend>().set_editedMaterialTypeId(material_type.id);
+    ui.global::<Backend>().set_editedTypeName(material_type.name);
+    ui.global::<Backend>().set_editedTypeDescription(material_type.description);
+    ui.global::<Backend>().set_materialTypeErrors(MaterialTypeErrors::default());
+    ui.global::<Backend>().set_materialTypePopUpErrorMessage("".into());
+}
+
+// Show the changed material types in every view that offers them, the type chosen in the material popup stays chosen
+fn show_material_types(
+    ui: &WorkshopClient,
+    material_list: &FilteredList<Material>,
+    type_list: &FilteredList<MaterialTypeUsage>,
+    materials: Vec<r_Material>,
+    material_types: Vec<r_MaterialType>,
+) {
+    let chosen_type = usize::try_from(ui.global::<Backend>().get_materialTypeIndex())
+        .ok()
+        .and_then(|index| ui.global::<Backend>().get_materialTypes().row_data(index))
+        .map(|t| t.id)
+        .unwrap_or(0);
+    show_material_type_choice(ui, &material_types, chosen_type);
+
+    let graph = DomainGraph::new(Collections {
+        materials,
+        material_types,
+        ..Default::default()
+    });
+    type_list.set_items(graph.material_type_usages());
+    material_list.set_items(graph.materials());
+}
+
+// Ask which type the materials of a type get before the type is deleted
+fn show_reassign_choice(ui: &WorkshopClient, usages: &[MaterialTypeUsage], usage: &MaterialTypeUsage) {
+    let other_types: Vec<MaterialType> = usages
+        .iter()
+        .filter(|t| t.m_type.id != usage.m_type.id)
+        .map(|t| t.m_type.clone())
+        .collect();
+    let type_names: Vec<SharedString> = other_types.iter().map(|t| t.name.clone()).collect();
+
+    ui.global::<Backend>().set_reassignMessage(
+        format!(
+            "{} material(s) still use {}, choose their new type:",
+            usage.material_count, usage.m_type.name
+        )
+        .into(),
+    );
+    ui.global::<Backend>().set_reassignTypes(ModelRc::new(VecModel::from(other_types)));
+    ui.global::<Backend>().set_reassignTypeNames(ModelRc::new(VecModel::from(type_names)));
+    ui.global::<Backend>().set_reassignTypeIndex(-1);
+    ui.global::<Backend>().set_reassignTypeName("".into());
+    ui.global::<Backend>().set_deletedMaterialTypeId(usage.m_type.id);
+}
+
+// Offer all material types for the material popup and preselect the type with the id
+fn show_material_type_choice(ui: &WorkshopClient, material_types: &[r_MaterialType], type_id: i32) {
+    let mut material_types = material_types.to_vec();
+    material_types.sort_by(|a, b| a.name.cmp(&b.name));
+    let type_names: Vec<SharedString> = material_types.iter().map(|t| t.name.clone().into()).collect();
+    let type_index = material_types.iter().position(|t| t.id == type_id);
+    let type_models: Vec<MaterialType> = material_types
+        .iter()
+        .map(|t| MaterialType {
+            id: t.id,
+            name: t.name.clone().into(),
+            description: t.description.clone().into(),
+        })
+        .collect();
+
+    ui.global::<Backend>().set_materialTypes(ModelRc::new(VecModel::from(type_models)));
+    ui.global::<Backend>().set_materialTypeNames(ModelRc::new(VecModel::from(type_names.clone())));
+    ui.global::<Backend>().set_materialTypeIndex(type_index.map(|i| i as i32).unwrap_or(-1));
+    ui.global::<Backend>().set_materialTypeName(type_index.map(|i| type_names[i].clone()).unwrap_or_default());
+}
+
+// Fill the supplier popup with the supplier, `None` clears it for a new one
+fn show_supplier_form(ui: &WorkshopClient, supplier: Option<&SupplierModel>) {
+    let supplier = supplier.cloned().unwrap_or_default();
+    let is_new = supplier.id == 0;
+    ui.global::<Backend>().set_editedSupplierId(supplier.id);
+    ui.global::<Backend>().set_supplierName(supplier.name);
+    ui.global::<Backend>().set_supplierContact(supplier.contact);
+    ui.global::<Backend>().set_supplierLeadTime(match is_new {
+        true => "".into(),
+        false => supplier.lead_time_days.to_string().into(),
+    });
+    ui.global::<Backend>().set_supplierMinimumOrder(match is_new {
+        true => "".into(),
+        false => supplier.minimum_order_quantity.to_string().into(),
+    });
+    ui.global::<Backend>().set_supplierErrors(SupplierErrors::default());
+    ui.global::<Backend>().set_supplierPopUpErrorMessage("".into());
+}
+
+// Show the offers of the chosen supplier sorted by material, an unknown supplier clears the panel
+fn show_supplier_materials(
+    ui: &WorkshopClient,
+    suppliers: &[SupplierModel],
+    offers: &[SupplierMaterial],
+    supplier_id: i32,
+) {
+    let supplier = suppliers
+        .iter()
+        .find(|s| s.id == supplier_id)
+        .cloned()
+        .unwrap_or_default();
+    let materials: Vec<Material> = ui.global::<Backend>().get_offerMaterials().iter().collect();
+    let mut offers: Vec<SupplierMaterialModel> = offers
+        .iter()
+        .filter(|o| supplier.id != 0 && o.supplier_id == supplier.id)
+        .map(|o| SupplierMaterialModel {
+            id: o.id,
+            material_id: o.material_id,
+            material_name: match materials.iter().find(|m| m.id == o.material_id) {
+                Some(material) => material.name.clone(),
+                None => format!("Unknown material #{}", o.material_id).into(),
+            },
+            article_number: o.article_number.clone().into(),
+            price: o.price.to_string().into(),
+            preferred: o.preferred,
+        })
+        .collect();
+    offers.sort_by_key(|o| (o.material_name.to_lowercase(), o.id));
+
+    ui.global::<Backend>().set_selectedSupplierId(supplier.id);
+    ui.global::<Backend>().set_selectedSupplier(supplier);
+    ui.global::<Backend>().set_supplierMaterials(ModelRc::new(VecModel::from(offers)));
+    ui.global::<Backend>().set_supplierMaterialErrors(SupplierMaterialErrors::default());
+}
+
+fn supplier_model(supplier: &Supplier) -> SupplierModel {
+    SupplierModel {
+        id: supplier.id,
+        name: supplier.name.clone().into(),
+        contact: supplier.contact.clone().into(),
+        lead_time_days: supplier.lead_time_days,
+        minimum_order_quantity: supplier.minimum_order_quantity,
+    }
+}
+
+fn reorder_group_model(group: &ReorderGroup) -> ReorderGroupModel {
+    let lines: Vec<ReorderLineModel> = group
+        .lines
+        .iter()
+        .map(|line| ReorderLineModel {
+            material_name: line.material.name.clone().into(),
+            article_number: line.offer.as_ref().map(|o| o.article_number.clone()).unwrap_or_default().into(),
+            stock: line.material.amount,
+            threshold: line.material.threshold_value,
+            demand: line.demand,
+            on_order: line.on_order,
+            quantity: line.quantity,
+            unit_price: line.unit_price().to_string().into(),
+            total: line.total().to_string().into(),
+        })
+        .collect();
+    let supplier = group.supplier.as_ref().map(supplier_model).unwrap_or_default();
+    ReorderGroupModel {
+        supplier_id: supplier.id,
+        supplier_name: supplier.name,
+        contact: supplier.contact,
+        lead_time_days: supplier.lead_time_days,
+        lines: ModelRc::new(VecModel::from(lines)),
+        total: group.total().to_string().into(),
+    }
+}
+
+// Show the chosen order with its lines, an unknown order clears the panel
+fn show_purchase_order(ui: &WorkshopClient, orders: &[PurchaseOrderModel], order_id: i32) {
+    let order = orders.iter().find(|o| o.id == order_id).cloned().unwrap_or_default();
+    ui.global::<Backend>().set_selectedOrderId(order.id);
+    ui.global::<Backend>().set_orderExpectedDate(order.expected_date.clone());
+    ui.global::<Backend>().set_orderDateError("".into());
+    ui.global::<Backend>().set_selectedOrder(order);
+}
+
+// Load the order, change its status with `change` and save it, the view shows the result
+fn change_purchase_order(
+    ui_handle: &slint::Weak<WorkshopClient>,
+    workshop_handle: &Arc<Mutex<WorkshopService>>,
+    keycloak_handle: &Arc<Mutex<Keycloak>>,
+    order_id: i32,
+    change: impl FnOnce(&mut PurchaseOrder) -> Result<(), Error> + Send + 'static,
+) {
+    let ui_handle = ui_handle.clone();
+    let workshop_handle = workshop_handle.clone();
+    let keycloak_handle = keycloak_handle.clone();
+    runtime::spawn(
+        async move {
+            // Refresh the token, the user needs the permission for this
+            let token = keycloak_handle
+                .lock()
+                .await
+                .authorize(Permission::EditMaterials)
+                .await?;
+            let workshop = workshop_handle.lock().await;
+            // The status on the server decides what is allowed, not the one that is shown
+            let mut order = workshop.get_by_id::<PurchaseOrder>(order_id, &token).await?;
+            change(&mut order)?;
+            workshop.update(&order, &token).await
+        },
+        move |result| {
+            let ui = ui_handle.unwrap();
+            // Check if the order was successfully changed otherwise handle the error
+            if let Err(e) = result {
+                if session_expired(&ui, &e) {
+                    return;
+                }
+                ui.global::<Backend>().set_purchaseOrderErrorMessage(e.to_string().into());
+                return;
+            }
+            ui.global::<Backend>().invoke_route_to_purchase_orders();
+        },
+    );
+}
+
+fn purchase_order_model(
+    order: &PurchaseOrder,
+    suppliers: &[Supplier],
+    materials: &[r_Material],
+    today: NaiveDate,
+    vat_rate: VatRate,
+) -> PurchaseOrderModel {
+    let lines: Vec<OrderLineModel> = order
+        .lines
+        .iter()
+        .map(|line| OrderLineModel {
+            material_name: match materials.iter().find(|m| m.id == line.material_id) {
+                Some(material) => material.name.clone().into(),
+                None => format!("Unknown material #{}", line.material_id).into(),
+            },
+            quantity: line.quantity,
+            received: line.received,
+            unit_price: line.unit_price.to_string().into(),
+            total: line.total().to_string().into(),
+        })
+        .collect();
+    PurchaseOrderModel {
+        id: order.id,
+        supplier_name: match suppliers.iter().find(|s| s.id == order.supplier_id) {
+            Some(supplier) => supplier.name.clone().into(),
+            None => format!("Unknown supplier #{}", order.supplier_id).into(),
+        },
+        status: order.status.label().into(),
+        order_date: order.order_date.map(date::display).unwrap_or_default().into(),
+        expected_date: date::display(order.expected_date).into(),
+        total: order.total().to_string().into(),
+        vat_rate: vat_rate.label().into(),
+        vat: order.total().vat(vat_rate).to_string().into(),
+        gross: order.total().gross(vat_rate).to_string().into(),
+        overdue: order.is_overdue(today),
+        can_send: order.status == OrderStatus::Draft,
+        can_cancel: matches!(order.status, OrderStatus::Draft | OrderStatus::Sent),
+        can_receive: order.status.is_open(),
+        lines: ModelRc::new(VecModel::from(lines)),
+    }
+}
+
+// Projects are found by their name and the name and phone number of their client
+fn project_matches(project: &ProjectModel, search: &str) -> bool {
+    project.name.to_lowercase().contains(search)
+        || format!("{} {}", project.client.firstName, project.client.lastName)
+            .to_lowercase()
+            .contains(search)
+        || project.client.phone.to_lowercase().contains(search)
+}
+
+// Materials are found by their name, description and type
+fn material_matches(material: &Material, search: &str) -> bool {
+    material.name.to_lowercase().contains(search)
+        || material.description.to_lowercase().contains(search)
+        || material.m_type.name.to_lowercase().contains(search)
+}
+
+// Clients are found by their name and phone number
+fn client_matches(client: &ClientModel, search: &str) -> bool {
+    format!("{} {}", client.firstName, client.lastName)
+        .to_lowercase()
+        .contains(search)
+        || client.phone.to_lowercase().contains(search)
+}
+
+// Suppliers are found by their name and contact
+fn supplier_matches(supplier: &SupplierModel, search: &str) -> bool {
+    supplier.name.to_lowercase().contains(search) || supplier.contact.to_lowercase().contains(search)
+}
+
+// The projects the status filter of the project management lets through: 0 the open ones, 1 all, then one status
+fn project_scope(filter_index: i32) -> Option<Filter<ProjectModel>> {
+    match filter_index {
+        0 => Some(Box::new(|project: &ProjectModel| {
+            usize::try_from(project.status.index)
+                .ok()
+                .and_then(|index| ProjectStatus::ALL.get(index))
+                .is_some_and(|status| !status.is_closed())
+        })),
+        1 => None,
+        status_index => Some(Box::new(move |project: &ProjectModel| project.status.index == status_index - 2)),
+    }
+}
+
+// Orders are found by their number, supplier and status
+fn purchase_order_matches(order: &PurchaseOrderModel, search: &str) -> bool {
+    format!("#{}", order.id).contains(search)
+        || order.supplier_name.to_lowercase().contains(search)
+        || order.status.to_lowercase().contains(search)
+}
+
+// Material types are found by their name and description
+fn material_type_matches(usage: &MaterialTypeUsage, search: &str) -> bool {
+    usage.m_type.name.to_lowercase().contains(search) || usage.m_type.description.to_lowercase().contains(search)
+}
+
+// Show the conflicting change with the fields that differ from the server
+fn show_conflict(ui: &WorkshopClient, conflict: &Conflict) {
+    let fields: Vec<ConflictField> = conflict
+        .differences()
+        .into_iter()
+        .map(|d| ConflictField {
+            field: d.field.into(),
+            mine: d.mine.into(),
+            server: d.server.into(),
+        })
+        .collect();
+    ui.global::<Backend>().set_conflictDescription(conflict.description().into());
+    ui.global::<Backend>().set_conflictFields(ModelRc::new(VecModel::from(fields)));
+    ui.global::<Backend>().set_showConflictPopUp(true);
+}
+
+// Load the visible view again, e.g. after the outbox gave the items created offline their real ids
+fn refresh_current_view(ui: &WorkshopClient) {
+    let backend = ui.global::<Backend>();
+    if backend.get_projectView() {
+        backend.invoke_route_to_project_view();
+    } else if backend.get_projectDetailView() && backend.get_project().id > 0 {
+        backend.invoke_route_to_project_i(backend.get_project().id);
+    } else if backend.get_projectManagementView() || backend.get_projectDetailView() {
+        backend.invoke_route_to_project_management();
+    } else if backend.get_lagerOverviewView() {
+        backend.invoke_route_to_warehouse_management();
+    } else if backend.get_clientManagementView() {
+        backend.invoke_route_to_client_management();
+    } else if backend.get_materialTypeManagementView() {
+        backend.invoke_route_to_material_type_management();
+    } else if backend.get_supplierManagementView() {
+        backend.invoke_route_to_supplier_management();
+    } else if backend.get_reorderView() {
+        backend.invoke_route_to_reorder();
+    } else if backend.get_purchaseOrderView() {
+        backend.invoke_route_to_purchase_orders();
+    }
+    // The goods receipt is not loaded again, that would throw away the delivery that is entered
+}
+
+// The note in the appbar while the data comes from the local cache or changes wait for the server, empty otherwise
+fn sync_message(state: SyncState) -> String {
+    let offline = match state.offline_since {
+        None => None,
+        Some(since) if since.date_naive() == date::today() => {
+            Some(format!("Offline, data as of {}", since.format("%H:%M")))
+        }
+        Some(since) => Some(format!("Offline, data as of {}", date::display_date_time(since))),
+    };
+    let pending = match state.pending_changes {
+        0 => None,
+        1 => Some("1 change waits for the server".to_string()),
+        count => Some(format!("{} changes wait for the server", count)),
+    };
+    offline.into_iter().chain(pending).collect::<Vec<_>>().join(", ")
 }
@@ -7,10 +7,20 @@
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+use crate::models::resource::Resource;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct Client {
     pub id: i32,
     pub firstname: String,
     pub lastname: String,
     pub phone: String,
 }
+
+impl Resource for Client {
+    const PATH: &'static str = "Clients";
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+}
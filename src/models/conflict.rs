@@ -0,0 +1,112 @@
+/*
+
+    Author: Justin
+    Description: This file contains the model for a change from the outbox that no longer fits the data on the server,
+                 because someone else changed or deleted the same item while the client was offline.
+
+*/
+
+use serde_json::Value;
+
+use crate::utils::cache::Operation;
+
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    // The entry of the outbox that could not be sent
+    pub entry_id: i64,
+    pub resource: String,
+    pub item_id: i32,
+    pub operation: Operation,
+    // The item as the client wants it, None for a delete
+    pub mine: Option<Value>,
+    // The item as it is on the server now, None if it was deleted there
+    pub server: Option<Value>,
+}
+
+// One field that differs between the change of the client and the server
+#[derive(Debug, Clone, PartialEq)]
+pub struct Difference {
+    pub field: String,
+    pub mine: String,
+    pub server: String,
+}
+
+impl Conflict {
+    pub fn description(&self) -> String {
+        let item = format!("{} {}", self.resource.trim_end_matches('s'), self.item_id);
+        match (self.operation, &self.server) {
+            (Operation::Delete, _) => format!("{} was deleted offline but changed on the server meanwhile", item),
+            (_, None) => format!("{} was changed offline but deleted on the server meanwhile", item),
+            (_, Some(_)) => format!("{} was changed offline and on the server", item),
+        }
+    }
+
+    // The fields whose values differ, a missing item shows up as empty values
+    pub fn differences(&self) -> Vec<Difference> {
+        let fields = |item: &Option<Value>| match item {
+            Some(Value::Object(fields)) => fields.clone(),
+            _ => serde_json::Map::new(),
+        };
+        let (mine, server) = (fields(&self.mine), fields(&self.server));
+        let mut names: Vec<&String> = mine.keys().chain(server.keys()).collect();
+        names.sort();
+        names.dedup();
+
+        let text = |value: Option<&Value>| match value {
+            Some(Value::String(text)) => text.clone(),
+            Some(value) => value.to_string(),
+            None => String::new(),
+        };
+        names
+            .into_iter()
+            .filter(|name| mine.get(*name) != server.get(*name))
+            .map(|name| Difference {
+                field: name.clone(),
+                mine: text(mine.get(name)),
+                server: text(server.get(name)),
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use serde_json::json;
+
+    fn conflict(operation: Operation, mine: Option<Value>, server: Option<Value>) -> Conflict {
+        Conflict {
+            entry_id: 1,
+            resource: "Materials".to_string(),
+            item_id: 7,
+            operation,
+            mine,
+            server,
+        }
+    }
+
+    #[test]
+    fn only_the_fields_that_differ_are_listed() {
+        let mine = json!({"id": 7, "name": "Hinge", "amount": 4, "costs": 2.5});
+        let server = json!({"id": 7, "name": "Hinge, brass", "amount": 4, "costs": 2.75});
+        let conflict = conflict(Operation::Update, Some(mine), Some(server));
+        assert_eq!(
+            conflict.differences(),
+            vec![
+                Difference { field: "costs".to_string(), mine: "2.5".to_string(), server: "2.75".to_string() },
+                Difference { field: "name".to_string(), mine: "Hinge".to_string(), server: "Hinge, brass".to_string() },
+            ]
+        );
+        assert_eq!(conflict.description(), "Material 7 was changed offline and on the server");
+    }
+
+    #[test]
+    fn a_missing_item_shows_empty_values() {
+        let conflict = conflict(Operation::Delete, None, Some(json!({"id": 7, "amount": 4})));
+        let differences = conflict.differences();
+        assert_eq!(differences.len(), 2);
+        assert!(differences.iter().all(|d| d.mine.is_empty() && !d.server.is_empty()));
+        assert_eq!(conflict.description(), "Material 7 was deleted offline but changed on the server meanwhile");
+    }
+}
@@ -0,0 +1,266 @@
+/*
+
+    Author: Justin
+    Description: This file contains the handling of calendar days. The API sends and expects days as `2024-04-18`, the
+                 models keep them as `NaiveDate` so they are compared and sorted by time instead of as text. The UI shows
+                 them in the order of day, month and year the locale of the system uses and accepts both that and the
+                 format of the API when a date is typed in.
+
+*/
+
+use chrono::{DateTime, Datelike, Duration, Local, Months, NaiveDate};
+use serde::{Deserialize, Deserializer, Serializer};
+
+use crate::models::locale;
+
+// The format of the API
+pub const API_FORMAT: &str = "%Y-%m-%d";
+
+pub fn today() -> NaiveDate {
+    Local::now().date_naive()
+}
+
+// A day of the API, a time after the day (`2024-04-18T00:00:00`) is ignored
+pub fn parse_api(value: &str) -> Option<NaiveDate> {
+    let day = value.trim().get(..10)?;
+    NaiveDate::parse_from_str(day, API_FORMAT).ok()
+}
+
+// The format of the locale, e.g. `18.04.2024` for German or `04/18/2024` for American English
+fn display_format() -> &'static str {
+    match (locale::language(), locale::system_locale()) {
+        (_, "en_US") | (_, "en_PH") => "%m/%d/%Y",
+        ("de" | "cs" | "da" | "fi" | "nb" | "nn" | "no" | "pl" | "ru" | "sk" | "tr" | "uk", _) => "%d.%m.%Y",
+        ("en" | "es" | "fr" | "it" | "pt" | "el", _) => "%d/%m/%Y",
+        ("nl", _) => "%d-%m-%Y",
+        // Everyone else gets the unambiguous format of the API
+        _ => API_FORMAT,
+    }
+}
+
+// The day as the user reads it
+pub fn display(date: NaiveDate) -> String {
+    date.format(display_format()).to_string()
+}
+
+// The day and the time as the user reads it, e.g. `18.04.2024 14:30`
+pub fn display_date_time(time: DateTime<Local>) -> String {
+    format!("{} {}", display(time.date_naive()), time.format("%H:%M"))
+}
+
+// A typed day in the format of the locale or of the API
+pub fn parse_input(value: &str) -> Option<NaiveDate> {
+    let value = value.trim();
+    NaiveDate::parse_from_str(value, display_format())
+        .or_else(|_| NaiveDate::parse_from_str(value, API_FORMAT))
+        .ok()
+}
+
+// The Monday of the week of the day
+pub fn week_start(date: NaiveDate) -> NaiveDate {
+    date - Duration::days(date.weekday().num_days_from_monday().into())
+}
+
+pub fn month_start(date: NaiveDate) -> NaiveDate {
+    date.with_day(1).unwrap_or(date)
+}
+
+pub fn end_of_month(date: NaiveDate) -> NaiveDate {
+    add_months(month_start(date), 1).pred_opt().unwrap_or(date)
+}
+
+// The same day `months` months later or earlier, the 31st becomes the last day of a shorter month
+pub fn add_months(date: NaiveDate, months: i32) -> NaiveDate {
+    let shifted = match months >= 0 {
+        true => date.checked_add_months(Months::new(months.unsigned_abs())),
+        false => date.checked_sub_months(Months::new(months.unsigned_abs())),
+    };
+    shifted.unwrap_or(date)
+}
+
+// The six weeks from Monday to Sunday that show the month of the day in a calendar
+pub fn month_grid(date: NaiveDate) -> Vec<NaiveDate> {
+    let first = week_start(month_start(date));
+    (0..42).map(|day| first + Duration::days(day)).collect()
+}
+
+// Monday to Friday
+pub fn is_workday(date: NaiveDate) -> bool {
+    date.weekday().num_days_from_monday() < 5
+}
+
+// How close a deadline is, for the highlighting in the lists
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Urgency {
+    // The day passed
+    Overdue,
+    // Today or later in the current week, which ends on Sunday
+    DueThisWeek,
+    Later,
+}
+
+impl Urgency {
+    pub fn of(deadline: NaiveDate, today: NaiveDate) -> Urgency {
+        if deadline < today {
+            Urgency::Overdue
+        } else if deadline <= week_start(today) + Duration::days(6) {
+            Urgency::DueThisWeek
+        } else {
+            Urgency::Later
+        }
+    }
+}
+
+// `#[serde(with = "date::api")]` for a `NaiveDate`
+pub mod api {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(date: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&date.format(API_FORMAT).to_string())
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveDate, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        parse_api(&value)
+            .ok_or_else(|| serde::de::Error::custom(format!("{} is not a date like 2024-04-18", value)))
+    }
+}
+
+// `#[serde(with = "date::optional_api")]` for an `Option<NaiveDate>`, a missing day is `null` or an empty text
+pub mod optional_api {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(date: &Option<NaiveDate>, serializer: S) -> Result<S::Ok, S::Error> {
+        match date {
+            Some(date) => super::api::serialize(date, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<NaiveDate>, D::Error> {
+        match Option::<String>::deserialize(deserializer)? {
+            None => Ok(None),
+            Some(value) if value.trim().is_empty() => Ok(None),
+            Some(value) => parse_api(&value)
+                .map(Some)
+                .ok_or_else(|| serde::de::Error::custom(format!("{} is not a date like 2024-04-18", value))),
+        }
+    }
+}
+
+// `#[serde(with = "date::lenient_api")]` for an `Option<NaiveDate>` that older data may hold in any shape, a day that
+// can not be read becomes `None` instead of failing the whole collection. The model has to make sure such an item is
+// not written back before the day was entered again.
+pub mod lenient_api {
+    use super::*;
+
+    pub use super::optional_api::serialize;
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<NaiveDate>, D::Error> {
+        match Option::<serde_json::Value>::deserialize(deserializer)? {
+            None | Some(serde_json::Value::Null) => Ok(None),
+            Some(serde_json::Value::String(value)) if value.trim().is_empty() => Ok(None),
+            Some(value) => Ok(value.as_str().and_then(parse_api)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::models::project::Project;
+
+    fn day(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn parse_input_reads_the_locale_and_the_api_format() {
+        let date = day(2024, 4, 18);
+        assert_eq!(parse_input("2024-04-18"), Some(date));
+        assert_eq!(parse_input(" 2024-04-18 "), Some(date));
+        // Whatever the locale of the machine running the tests is, its own format is read back
+        assert_eq!(parse_input(&display(date)), Some(date));
+        assert_eq!(parse_input("2024-02-30"), None);
+        assert_eq!(parse_input("tomorrow"), None);
+        assert_eq!(parse_input(""), None);
+    }
+
+    #[test]
+    fn urgency_ends_with_the_sunday_of_the_week() {
+        // A Thursday
+        let today = day(2024, 4, 18);
+        assert_eq!(Urgency::of(day(2024, 4, 17), today), Urgency::Overdue);
+        assert_eq!(Urgency::of(today, today), Urgency::DueThisWeek);
+        assert_eq!(Urgency::of(day(2024, 4, 21), today), Urgency::DueThisWeek);
+        assert_eq!(Urgency::of(day(2024, 4, 22), today), Urgency::Later);
+        // On a Sunday the next day is already in the next week
+        assert_eq!(Urgency::of(day(2024, 4, 22), day(2024, 4, 21)), Urgency::Later);
+    }
+
+    #[test]
+    fn add_months_keeps_the_day_or_takes_the_last_of_the_month() {
+        assert_eq!(add_months(day(2024, 4, 18), 1), day(2024, 5, 18));
+        assert_eq!(add_months(day(2024, 1, 31), 1), day(2024, 2, 29));
+        assert_eq!(add_months(day(2023, 1, 31), 1), day(2023, 2, 28));
+        assert_eq!(add_months(day(2024, 3, 31), -1), day(2024, 2, 29));
+        assert_eq!(add_months(day(2024, 12, 15), 1), day(2025, 1, 15));
+        assert_eq!(add_months(day(2024, 1, 15), -1), day(2023, 12, 15));
+        assert_eq!(add_months(day(2024, 4, 18), 0), day(2024, 4, 18));
+        assert_eq!(end_of_month(day(2024, 2, 10)), day(2024, 2, 29));
+    }
+
+    #[test]
+    fn month_grid_is_six_weeks_from_monday() {
+        // April 2024 starts on a Monday
+        let april = month_grid(day(2024, 4, 18));
+        assert_eq!(april.len(), 42);
+        assert_eq!(april[0], day(2024, 4, 1));
+        assert_eq!(april[41], day(2024, 5, 12));
+
+        // September 2024 starts on a Sunday, the grid starts in August
+        let september = month_grid(day(2024, 9, 30));
+        assert_eq!(september[0], day(2024, 8, 26));
+        assert_eq!(september[6], day(2024, 9, 1));
+        assert!(september.chunks(7).all(|week| week[0].weekday() == chrono::Weekday::Mon));
+    }
+
+    #[test]
+    fn a_project_with_an_unreadable_day_is_still_read() {
+        let project: Project = serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "name": "Old",
+            "client_id": 2,
+            "description": "",
+            "startpoint": "18.04.2024",
+            "endpoint": 20240430,
+            "estimated_costs": 0,
+            "estimated_hours": 8.0,
+            "costs": 0
+        }))
+        .unwrap();
+        assert_eq!(project.startpoint, None);
+        assert_eq!(project.endpoint, None);
+        assert_eq!(project.urgency(day(2024, 4, 18)), None);
+        // Writing it back would erase the days on the server
+        let mut project = project;
+        let next = project.status.next()[0];
+        assert!(project.change_status(next).is_err());
+
+        let project: Project = serde_json::from_value(serde_json::json!({
+            "id": 1,
+            "name": "New",
+            "client_id": 2,
+            "description": "",
+            "startpoint": "2024-04-18T00:00:00",
+            "estimated_costs": 0,
+            "estimated_hours": 8.0,
+            "costs": 0
+        }))
+        .unwrap();
+        assert_eq!(project.startpoint, Some(day(2024, 4, 18)));
+        assert_eq!(project.endpoint, None);
+        assert_eq!(serde_json::to_value(&project).unwrap()["startpoint"], "2024-04-18");
+    }
+}
@@ -1,38 +1,101 @@
 /*
 
     Author: Justin Kosten
-    Description: This file contains the model for the Error object. This object is used to represent an error in the actix-web server.
+    Description: This file contains the model for the Error object. This object is used to represent everything that can go wrong
+                 while talking to the workshop API or keycloak. Errors of the server keep the HTTP status and the response body.
 
 */
 
-use serde::{Deserialize, Serialize};
-
-#[derive(Serialize, Deserialize)]
-pub struct Error {
-    pub message: String,
-    pub code: i32,
+#[derive(Debug, Clone, PartialEq)]
+pub enum Error {
+    // The server could not be reached at all
+    Network(String),
+    // The server did not answer in time
+    Timeout,
+    // 401, the token is missing, invalid or expired
+    Unauthorized(String),
+    // 403, the user is not allowed to do this
+    Forbidden(String),
+    // 404, the requested item does not exist
+    NotFound(String),
+    // 409, the item was changed or is still referenced
+    Conflict(String),
+    // 400 or 422, the server rejected the data we sent
+    Validation { status: u16, body: String },
+    // The response could not be parsed
+    Decode(String),
+    // Every other status the server answered with
+    Server { status: u16, body: String },
+    // The configuration of the client is broken
+    Config(String),
+    // The refresh token ran out or was revoked, the user has to login again
+    SessionExpired,
+    // More of a material was requested than the warehouse has
+    InsufficientStock { material: String, available: i32 },
+    // The local copy of the data could not be read or written
+    Cache(String),
 }
 
 impl Error {
-    pub fn new(message: String, code: i32) -> Error {
-        Error {
-            message,
-            code
+    // Turn a failed response of the server into our own error
+    pub fn from_status(status: u16, body: String) -> Error {
+        match status {
+            401 => Error::Unauthorized(body),
+            403 => Error::Forbidden(body),
+            404 => Error::NotFound(body),
+            409 => Error::Conflict(body),
+            400 | 422 => Error::Validation { status, body },
+            _ => Error::Server { status, body },
         }
     }
 }
 
-impl std::fmt::Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "Error {}: {}", self.code, self.message)
+impl From<reqwest::Error> for Error {
+    fn from(error: reqwest::Error) -> Error {
+        if error.is_timeout() {
+            Error::Timeout
+        } else if error.is_decode() {
+            Error::Decode(error.to_string())
+        } else if let Some(status) = error.status() {
+            Error::from_status(status.as_u16(), error.to_string())
+        } else {
+            Error::Network(error.to_string())
+        }
+    }
+}
+
+impl From<rusqlite::Error> for Error {
+    fn from(error: rusqlite::Error) -> Error {
+        Error::Cache(error.to_string())
     }
 }
 
-impl Default for Error {
-    fn default() -> Error {
-        Error {
-            message: "An error occurred".to_string(),
-            code: 500
+// Show the body of the server if there is one, otherwise a short description of the status
+fn write_status(f: &mut std::fmt::Formatter, status: u16, body: &str, fallback: &str) -> std::fmt::Result {
+    match body.trim().is_empty() {
+        true => write!(f, "Error {}: {}", status, fallback),
+        false => write!(f, "Error {}: {}", status, body.trim()),
+    }
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::Network(message) => write!(f, "Server not reachable: {}", message),
+            Error::Timeout => write!(f, "The server did not answer in time"),
+            Error::Unauthorized(body) => write_status(f, 401, body, "Not authorized"),
+            Error::Forbidden(body) => write_status(f, 403, body, "Not allowed"),
+            Error::NotFound(body) => write_status(f, 404, body, "Not found"),
+            Error::Conflict(body) => write_status(f, 409, body, "Conflict"),
+            Error::Validation { status, body } => write_status(f, *status, body, "Invalid data"),
+            Error::Decode(message) => write!(f, "Invalid response: {}", message),
+            Error::Server { status, body } => write_status(f, *status, body, "Server error"),
+            Error::Config(message) => write!(f, "Invalid configuration: {}", message),
+            Error::SessionExpired => write!(f, "Your session expired, please login again"),
+            Error::InsufficientStock { material, available } => {
+                write!(f, "Only {} of {} in stock", available, material)
+            }
+            Error::Cache(message) => write!(f, "Local cache not available: {}", message),
         }
     }
-}
\ No newline at end of file
+}
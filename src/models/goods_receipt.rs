@@ -0,0 +1,99 @@
+/*
+
+    Author: Justin
+    Description: This file contains the model for a goods receipt. A receipt is a delivery of a supplier with its lines of
+                 material, it is kept so every booked delivery can be checked later.
+
+*/
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::models::date;
+use crate::models::money::Money;
+use crate::models::resource::Resource;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct ReceiptLine {
+    pub material_id: i32,
+    pub quantity: i32,
+    // The price per piece on the delivery note
+    pub unit_price: Money,
+}
+
+impl ReceiptLine {
+    // Rounded to cents like a line of an invoice
+    pub fn total(&self) -> Money {
+        (self.unit_price * self.quantity).round_to_cents()
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct GoodsReceipt {
+    pub id: i32,
+    pub supplier: String,
+    pub delivery_note: String,
+    // The day the goods arrived
+    #[serde(with = "date::api")]
+    pub date: NaiveDate,
+    // Username of the keycloak session that booked the receipt
+    pub user: String,
+    pub lines: Vec<ReceiptLine>,
+    // The order the delivery was received against, None for a delivery without order
+    #[serde(default)]
+    pub purchase_order_id: Option<i32>,
+}
+
+impl GoodsReceipt {
+    pub fn total(&self) -> Money {
+        self.lines.iter().map(ReceiptLine::total).sum()
+    }
+}
+
+impl Resource for GoodsReceipt {
+    const PATH: &'static str = "GoodsReceipts";
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+}
+
+// How the costs of a material follow the prices it is bought for
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Valuation {
+    // The costs are the price of the last delivery
+    #[default]
+    LastPrice,
+    // The costs are the average price of the stock, weighted by the amount
+    MovingAverage,
+}
+
+impl Valuation {
+    pub fn from_name(name: &str) -> Option<Valuation> {
+        match name {
+            "last_price" => Some(Valuation::LastPrice),
+            "moving_average" => Some(Valuation::MovingAverage),
+            _ => None,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Valuation::LastPrice => "last price",
+            Valuation::MovingAverage => "moving average",
+        }
+    }
+
+    // The costs of a piece after `quantity` pieces were delivered for `unit_price` each
+    pub fn unit_costs(&self, stock: i32, costs: Money, quantity: i32, unit_price: Money) -> Money {
+        match self {
+            Valuation::LastPrice => unit_price,
+            // Without stock there is nothing to average with, a negative stock is a mistake of the ledger
+            Valuation::MovingAverage if stock <= 0 => unit_price,
+            Valuation::MovingAverage => {
+                (costs * stock + unit_price * quantity).per_piece(stock + quantity)
+            }
+        }
+    }
+}
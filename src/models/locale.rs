@@ -0,0 +1,27 @@
+/*
+
+    Author: Justin
+    Description: This file contains the locale of the system. Dates and amounts of money are shown in the order and with
+                 the separators the user is used to.
+
+*/
+
+use std::sync::OnceLock;
+
+// The locale without encoding and modifier, e.g. `de_DE` for `de_DE.UTF-8`, empty if none is set
+pub fn system_locale() -> &'static str {
+    static LOCALE: OnceLock<String> = OnceLock::new();
+    LOCALE.get_or_init(|| {
+        // LC_ALL wins as in the C library, then the categories of dates and money and at last LANG
+        let locale = ["LC_ALL", "LC_TIME", "LC_MONETARY", "LANG"]
+            .iter()
+            .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+            .unwrap_or_default();
+        locale.split(['.', '@']).next().unwrap_or_default().to_string()
+    })
+}
+
+// The language of the locale, e.g. `de` for `de_DE`
+pub fn language() -> &'static str {
+    system_locale().split('_').next().unwrap_or_default()
+}
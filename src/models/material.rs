@@ -7,13 +7,25 @@
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+use crate::models::money::Money;
+use crate::models::resource::Resource;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct Material {
     pub id: i32,
     pub name: String,
     pub description: String,
     pub type_id: i32,
     pub amount: i32,
-    pub costs: f32,
+    // The costs of one piece
+    pub costs: Money,
     pub threshold_value: i32,
 }
+
+impl Resource for Material {
+    const PATH: &'static str = "Materials";
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+}
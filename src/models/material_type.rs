@@ -7,9 +7,19 @@
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+use crate::models::resource::Resource;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct MaterialType {
     pub id: i32,
     pub name: String,
     pub description: String,
 }
+
+impl Resource for MaterialType {
+    const PATH: &'static str = "MaterialTypes";
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+}
@@ -1,6 +1,16 @@
 pub mod client;
+pub mod date;
 pub mod error;
+pub mod locale;
+pub mod money;
 pub mod material;
 pub mod project;
 pub mod project_material;
-pub mod material_type;
\ No newline at end of file
+pub mod material_type;
+pub mod resource;
+pub mod user_session;
+pub mod conflict;
+pub mod stock_movement;
+pub mod goods_receipt;
+pub mod supplier;
+pub mod purchase_order;
@@ -0,0 +1,431 @@
+/*
+
+    Author: Justin
+    Description: This file contains the type for amounts of money. An amount is kept as a whole number of ten-thousandths
+                 of the currency, so the prices of small parts like screws stay exact and sums do not drift like floats.
+                 The API sends and expects plain numbers like `12.5`. Line totals and VAT are rounded to cents the way an
+                 invoice does it, the UI shows amounts with the separators of the locale and the configured currency.
+
+*/
+
+use std::fmt;
+use std::iter::Sum;
+use std::ops::{Add, AddAssign, Mul, Sub};
+use std::sync::OnceLock;
+
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::models::locale;
+
+// Ten-thousandths of the currency
+const SCALE: i64 = 10_000;
+const CENT: i64 = SCALE / 100;
+// The most decimals an amount can have
+const DECIMALS: usize = 4;
+
+// Used if the configuration does not set a currency
+pub const DEFAULT_CURRENCY: &str = "€";
+
+static CURRENCY: OnceLock<String> = OnceLock::new();
+
+// The symbol every amount is shown with, it can only be set once when the client starts
+pub fn set_currency(symbol: &str) {
+    let _ = CURRENCY.set(symbol.to_string());
+}
+
+fn currency() -> &'static str {
+    CURRENCY.get().map(String::as_str).unwrap_or(DEFAULT_CURRENCY)
+}
+
+// The ten-thousandths fit about ±922 trillion of the currency. Sums and products stop at that bound instead of wrapping
+// around, an amount that large is far beyond any order of the workshop.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Money(i64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    pub fn from_cents(cents: i64) -> Money {
+        Money(cents.saturating_mul(CENT))
+    }
+
+    // A number of the API, anything after the fourth decimal is rounded
+    pub fn from_f64(value: f64) -> Option<Money> {
+        let scaled = (value * SCALE as f64).round();
+        match scaled.is_finite() && scaled.abs() < i64::MAX as f64 {
+            true => Some(Money(scaled as i64)),
+            false => None,
+        }
+    }
+
+    pub fn to_f64(self) -> f64 {
+        self.0 as f64 / SCALE as f64
+    }
+
+    pub fn is_negative(self) -> bool {
+        self.0 < 0
+    }
+
+    // Half a cent is rounded away from zero
+    pub fn round_to_cents(self) -> Money {
+        Money((round_div(self.0.into(), CENT.into()) as i64).saturating_mul(CENT))
+    }
+
+    // The share of one piece if `pieces` pieces cost this amount together, e.g. for an average price
+    pub fn per_piece(self, pieces: i32) -> Money {
+        match pieces {
+            0 => Money::ZERO,
+            pieces if pieces < 0 => Money(round_div(-i128::from(self.0), -i128::from(pieces)) as i64),
+            pieces => Money(round_div(self.0.into(), pieces.into()) as i64),
+        }
+    }
+
+    // The VAT on this net amount. It is computed from the amount rounded to cents and rounded to cents itself, so the
+    // VAT of an order or invoice has to be computed from its total and not summed up from its lines
+    pub fn vat(self, rate: VatRate) -> Money {
+        let net = self.round_to_cents();
+        let vat = round_div(i128::from(net.0) * i128::from(rate.0), i128::from(VatRate::SCALE) * i128::from(CENT));
+        Money((vat as i64).saturating_mul(CENT))
+    }
+
+    // The net amount rounded to cents plus its VAT
+    pub fn gross(self, rate: VatRate) -> Money {
+        self.round_to_cents() + self.vat(rate)
+    }
+
+    // A typed amount with the separators of the locale or a dot as decimal separator, the currency can be left out
+    pub fn parse(text: &str) -> Option<Money> {
+        Money::parse_with(text, &NumberFormat::of_locale())
+    }
+
+    fn parse_with(text: &str, format: &NumberFormat) -> Option<Money> {
+        let text: String = text
+            .replace(currency(), "")
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect();
+        let (negative, text) = match text.strip_prefix('-') {
+            Some(rest) => (true, rest.to_string()),
+            None => (false, text),
+        };
+
+        let decimal = match (text.rfind('.'), text.rfind(',')) {
+            // The last of both separators is the decimal one, e.g. `1.234,56` or `1,234.56`
+            (Some(dot), Some(comma)) => Some(if dot > comma { '.' } else { ',' }),
+            (Some(_), None) => format.decimal_for(&text, '.'),
+            (None, Some(_)) => format.decimal_for(&text, ','),
+            (None, None) => None,
+        };
+        let (whole, fraction) = match decimal {
+            // `1.2.3` is neither grouped nor a decimal number
+            Some(decimal) if text.matches(decimal).count() > 1 => return None,
+            Some(decimal) => text.rsplit_once(decimal)?,
+            None => (text.as_str(), ""),
+        };
+        let whole: String = whole.chars().filter(|c| *c != '.' && *c != ',').collect();
+        if whole.is_empty() && fraction.is_empty() {
+            return None;
+        }
+        if !whole.chars().chain(fraction.chars()).all(|c| c.is_ascii_digit()) || fraction.len() > DECIMALS {
+            return None;
+        }
+
+        let whole: i64 = match whole.is_empty() {
+            true => 0,
+            false => whole.parse().ok()?,
+        };
+        let fraction: i64 = format!("{:0<width$}", fraction, width = DECIMALS).parse().ok()?;
+        let value = whole.checked_mul(SCALE)?.checked_add(fraction)?;
+        Some(Money(if negative { -value } else { value }))
+    }
+
+    // The amount for a form field, e.g. `1234,50`, without grouping and currency so it can be edited
+    pub fn input_text(self) -> String {
+        let format = NumberFormat::of_locale();
+        let (sign, whole, fraction) = self.parts();
+        format!("{}{}{}{}", sign, whole, format.decimal, fraction)
+    }
+
+    // The sign, the whole currency units and at least two decimals
+    fn parts(self) -> (&'static str, u64, String) {
+        let sign = if self.is_negative() { "-" } else { "" };
+        let value = self.0.unsigned_abs();
+        let fraction = format!("{:04}", value % SCALE as u64);
+        // Tenths and hundredths of a cent are only shown if there are some
+        let fraction = match fraction.trim_end_matches('0') {
+            short if short.len() <= 2 => fraction[..2].to_string(),
+            short => short.to_string(),
+        };
+        (sign, value / SCALE as u64, fraction)
+    }
+}
+
+// `1.234,56 €` for German or `€1,234.56` for English, more than two decimals are only shown for small unit prices
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let format = NumberFormat::of_locale();
+        let (sign, whole, fraction) = self.parts();
+        let digits = whole.to_string();
+        let mut grouped = String::new();
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                grouped.push_str(format.grouping);
+            }
+            grouped.push(digit);
+        }
+        let number = format!("{}{}{}", grouped, format.decimal, fraction);
+        match format.symbol_first {
+            true => write!(f, "{}{}{}", sign, currency(), number),
+            false => write!(f, "{}{} {}", sign, number, currency()),
+        }
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        Money(self.0.saturating_add(other.0))
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        *self = *self + other;
+    }
+}
+
+impl Sub for Money {
+    type Output = Money;
+
+    fn sub(self, other: Money) -> Money {
+        Money(self.0.saturating_sub(other.0))
+    }
+}
+
+// The price of a number of pieces
+impl Mul<i32> for Money {
+    type Output = Money;
+
+    fn mul(self, pieces: i32) -> Money {
+        Money(self.0.saturating_mul(i64::from(pieces)))
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+// The API knows amounts as numbers
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.to_f64())
+    }
+}
+
+// Numbers and texts like `"12.50"` are both accepted
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Money, D::Error> {
+        struct MoneyVisitor;
+
+        impl Visitor<'_> for MoneyVisitor {
+            type Value = Money;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "an amount of money like 12.5")
+            }
+
+            fn visit_f64<E: de::Error>(self, value: f64) -> Result<Money, E> {
+                Money::from_f64(value).ok_or_else(|| E::custom(format!("{} is not an amount of money", value)))
+            }
+
+            fn visit_i64<E: de::Error>(self, value: i64) -> Result<Money, E> {
+                value
+                    .checked_mul(SCALE)
+                    .map(Money)
+                    .ok_or_else(|| E::custom(format!("{} is not an amount of money", value)))
+            }
+
+            fn visit_u64<E: de::Error>(self, value: u64) -> Result<Money, E> {
+                i64::try_from(value)
+                    .map_err(|_| E::custom(format!("{} is not an amount of money", value)))
+                    .and_then(|value| self.visit_i64(value))
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Money, E> {
+                // The API always uses a dot, independent of the locale
+                let parsed = value.trim().parse::<f64>().ok().and_then(Money::from_f64);
+                parsed.ok_or_else(|| E::custom(format!("{} is not an amount of money", value)))
+            }
+        }
+
+        deserializer.deserialize_any(MoneyVisitor)
+    }
+}
+
+// A VAT rate in hundredths of a percent, e.g. 1900 for 19 %
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct VatRate(u32);
+
+impl VatRate {
+    const SCALE: u32 = 10_000;
+
+    pub fn from_percent(percent: f32) -> Option<VatRate> {
+        let rate = (f64::from(percent) * 100.0).round();
+        match rate.is_finite() && (0.0..=f64::from(VatRate::SCALE)).contains(&rate) {
+            true => Some(VatRate(rate as u32)),
+            false => None,
+        }
+    }
+
+    // `19 %` or `7,5 %`
+    pub fn label(&self) -> String {
+        let format = NumberFormat::of_locale();
+        let whole = self.0 / 100;
+        match self.0 % 100 {
+            0 => format!("{} %", whole),
+            fraction => {
+                let fraction = format!("{:02}", fraction);
+                format!("{}{}{} %", whole, format.decimal, fraction.trim_end_matches('0'))
+            }
+        }
+    }
+}
+
+// The separators and the position of the currency in the locale
+struct NumberFormat {
+    decimal: char,
+    grouping: &'static str,
+    symbol_first: bool,
+}
+
+impl NumberFormat {
+    fn of_locale() -> NumberFormat {
+        NumberFormat::of_language(locale::language())
+    }
+
+    fn of_language(language: &str) -> NumberFormat {
+        let (decimal, grouping, symbol_first) = match language {
+            "en" => ('.', ",", true),
+            "de" | "da" | "el" | "es" | "id" | "it" | "nl" | "pt" | "tr" => (',', ".", false),
+            "cs" | "fi" | "fr" | "hu" | "nb" | "nn" | "no" | "pl" | "ru" | "sk" | "sv" | "uk" => (',', " ", false),
+            _ => ('.', ",", false),
+        };
+        NumberFormat { decimal, grouping, symbol_first }
+    }
+
+    // Which separator a text with only `separator` in it uses as decimal separator. The grouping separator of the
+    // locale only groups if every group after the first has three digits, so `1.234` is 1234 in German but `12.5` and
+    // `0.035` are still read the way the API writes them.
+    fn decimal_for(&self, text: &str, separator: char) -> Option<char> {
+        let groups: Vec<&str> = text.split(separator).collect();
+        let first = groups[0];
+        let grouped = groups.len() > 1
+            && (1..=3).contains(&first.len())
+            && !first.starts_with('0')
+            && groups[1..].iter().all(|g| g.len() == 3);
+        match self.grouping.starts_with(separator) && grouped {
+            true => None,
+            false => Some(separator),
+        }
+    }
+}
+
+// `numerator / denominator` rounded to the nearest whole number, halves away from zero, `denominator` is positive
+fn round_div(numerator: i128, denominator: i128) -> i128 {
+    let quotient = numerator / denominator;
+    let remainder = numerator % denominator;
+    match remainder.abs() * 2 >= denominator {
+        true => quotient + numerator.signum(),
+        false => quotient,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str, language: &str) -> Option<Money> {
+        Money::parse_with(text, &NumberFormat::of_language(language))
+    }
+
+    #[test]
+    fn parses_both_separators_in_every_locale() {
+        for language in ["de", "en", "fr"] {
+            assert_eq!(parse("1.234,56", language), Some(Money::from_cents(123_456)), "{}", language);
+            assert_eq!(parse("1,234.56", language), Some(Money::from_cents(123_456)), "{}", language);
+            assert_eq!(parse("0.035", language), Some(Money(350)), "{}", language);
+            assert_eq!(parse("12.5", language), Some(Money::from_cents(1_250)), "{}", language);
+            assert_eq!(parse("-12.5 €", language), Some(Money::from_cents(-1_250)), "{}", language);
+            assert_eq!(parse("1.2.3", language), None, "{}", language);
+            assert_eq!(parse("0.00001", language), None, "{}", language);
+            assert_eq!(parse("12 Euro", language), None, "{}", language);
+            assert_eq!(parse("", language), None, "{}", language);
+        }
+    }
+
+    #[test]
+    fn a_lone_grouping_separator_of_the_locale_groups() {
+        assert_eq!(parse("1.234", "de"), Some(Money::from_cents(123_400)));
+        assert_eq!(parse("1.234", "en"), Some(Money(12_340)));
+        assert_eq!(parse("1,234", "en"), Some(Money::from_cents(123_400)));
+        assert_eq!(parse("1,234", "de"), Some(Money(12_340)));
+        assert_eq!(parse("12,5", "en"), Some(Money::from_cents(1_250)));
+    }
+
+    #[test]
+    fn half_a_cent_is_rounded_away_from_zero() {
+        assert_eq!(Money(50).round_to_cents(), Money::from_cents(1));
+        assert_eq!(Money(49).round_to_cents(), Money::ZERO);
+        assert_eq!(Money(-50).round_to_cents(), Money::from_cents(-1));
+        assert_eq!(Money(-49).round_to_cents(), Money::ZERO);
+        assert_eq!(Money(-150).round_to_cents(), Money::from_cents(-2));
+    }
+
+    #[test]
+    fn vat_of_the_total_differs_from_the_sum_of_the_lines() {
+        let seven = VatRate::from_percent(7.0).unwrap();
+        let nineteen = VatRate::from_percent(19.0).unwrap();
+
+        assert_eq!(Money::from_cents(10_000).vat(nineteen), Money::from_cents(1_900));
+        assert_eq!(Money::from_cents(10_000).gross(nineteen), Money::from_cents(11_900));
+        assert_eq!(Money::from_cents(1_234).vat(seven), Money::from_cents(86));
+
+        // Each line has less than half a cent of VAT, the total does not
+        let lines = [Money::from_cents(7); 3];
+        assert_eq!(lines.iter().map(|l| l.vat(seven)).sum::<Money>(), Money::ZERO);
+        assert_eq!(lines.iter().copied().sum::<Money>().vat(seven), Money::from_cents(1));
+
+        let lines = [Money::from_cents(2); 10];
+        assert_eq!(lines.iter().map(|l| l.vat(nineteen)).sum::<Money>(), Money::ZERO);
+        assert_eq!(lines.iter().copied().sum::<Money>().vat(nineteen), Money::from_cents(4));
+    }
+
+    #[test]
+    fn per_piece_rounds_with_the_sign_of_the_result() {
+        assert_eq!(Money::from_cents(100).per_piece(3), Money(3_333));
+        assert_eq!(Money::from_cents(100).per_piece(-3), Money(-3_333));
+        assert_eq!(Money::from_cents(-100).per_piece(3), Money(-3_333));
+        assert_eq!(Money::from_cents(-100).per_piece(-3), Money(3_333));
+        assert_eq!(Money(5).per_piece(-2), Money(-3));
+        assert_eq!(Money(-5).per_piece(-2), Money(3));
+        assert_eq!(Money::from_cents(100).per_piece(0), Money::ZERO);
+    }
+
+    #[test]
+    fn arithmetic_stops_at_the_bound() {
+        let max = Money(i64::MAX);
+        assert_eq!(max + Money::from_cents(1), max);
+        assert_eq!(Money(i64::MIN) - Money::from_cents(1), Money(i64::MIN));
+        assert_eq!(max * 2, max);
+        assert_eq!(max * -2, Money(i64::MIN));
+        assert_eq!([max, max].into_iter().sum::<Money>(), max);
+        let mut total = max;
+        total += max;
+        assert_eq!(total, max);
+        assert_eq!(Money::from_cents(i64::MAX), max);
+    }
+}
@@ -5,17 +5,166 @@
 
 */
 
+use chrono::NaiveDate;
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+use crate::models::date::{self, Urgency};
+use crate::models::error::Error;
+use crate::models::money::Money;
+use crate::models::resource::Resource;
+
+// The steps of a project from the first request of the client to the invoice
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ProjectStatus {
+    Inquiry,
+    Quoted,
+    Accepted,
+    // Projects from before the status was introduced are treated as running
+    #[default]
+    InProgress,
+    OnHold,
+    Finished,
+    Invoiced,
+    Cancelled,
+}
+
+impl ProjectStatus {
+    // In the order of the workflow, the UI refers to a status by its index in here
+    pub const ALL: [ProjectStatus; 8] = [
+        ProjectStatus::Inquiry,
+        ProjectStatus::Quoted,
+        ProjectStatus::Accepted,
+        ProjectStatus::InProgress,
+        ProjectStatus::OnHold,
+        ProjectStatus::Finished,
+        ProjectStatus::Invoiced,
+        ProjectStatus::Cancelled,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ProjectStatus::Inquiry => "Inquiry",
+            ProjectStatus::Quoted => "Quoted",
+            ProjectStatus::Accepted => "Accepted",
+            ProjectStatus::InProgress => "In progress",
+            ProjectStatus::OnHold => "On hold",
+            ProjectStatus::Finished => "Finished",
+            ProjectStatus::Invoiced => "Invoiced",
+            ProjectStatus::Cancelled => "Cancelled",
+        }
+    }
+
+    pub fn index(&self) -> usize {
+        ProjectStatus::ALL.iter().position(|s| s == self).unwrap_or(0)
+    }
+
+    // The states a project can go to from this one
+    pub fn next(&self) -> &'static [ProjectStatus] {
+        use ProjectStatus::*;
+        match self {
+            Inquiry => &[Quoted, Cancelled],
+            // A quote that was not accepted can be worked over
+            Quoted => &[Accepted, Inquiry, Cancelled],
+            Accepted => &[InProgress, Cancelled],
+            InProgress => &[OnHold, Finished, Cancelled],
+            OnHold => &[InProgress, Cancelled],
+            // Rework after the client saw the result
+            Finished => &[Invoiced, InProgress],
+            Invoiced | Cancelled => &[],
+        }
+    }
+
+    // The client agreed and the work is not done yet, the materials of these projects are still needed
+    pub fn is_active(&self) -> bool {
+        matches!(self, ProjectStatus::Accepted | ProjectStatus::InProgress | ProjectStatus::OnHold)
+    }
+
+    // Nothing more will happen with the project
+    pub fn is_closed(&self) -> bool {
+        matches!(self, ProjectStatus::Finished | ProjectStatus::Invoiced | ProjectStatus::Cancelled)
+    }
+}
+
+pub const UNREADABLE_SCHEDULE: &str =
+    "The start or the deadline of the project could not be read, enter both in the project form and save it first";
+
+// A deadline further away is taken as a typing error, it also bounds the weeks the schedule checks look at
+pub const MAX_SCHEDULE_YEARS: i32 = 10;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct Project {
     pub id: i32,
     pub name: String,
     pub client_id: i32,
     pub description: String,
-    pub startpoint: String,
-    pub endpoint: String,
-    pub estimated_costs: f32,
+    // Older projects can hold days that are not readable, those are `None` until the project is saved again
+    #[serde(default, with = "date::lenient_api")]
+    pub startpoint: Option<NaiveDate>,
+    // The deadline of the project
+    #[serde(default, with = "date::lenient_api")]
+    pub endpoint: Option<NaiveDate>,
+    pub estimated_costs: Money,
     pub estimated_hours: f32,
-    pub costs: f32,
+    pub costs: Money,
+    // Older servers do not know the status, see `ProjectStatus::default`
+    #[serde(default)]
+    pub status: ProjectStatus,
+}
+
+impl Project {
+    // The deadline can be on the day of the start but not before it, and not further away than `MAX_SCHEDULE_YEARS`
+    pub fn check_schedule(start: NaiveDate, deadline: NaiveDate) -> Result<(), String> {
+        if deadline < start {
+            return Err(format!("The deadline must not be before the start on {}", date::display(start)));
+        }
+        if deadline > date::add_months(start, MAX_SCHEDULE_YEARS * 12) {
+            return Err(format!("The deadline must be within {} years of the start", MAX_SCHEDULE_YEARS));
+        }
+        Ok(())
+    }
+
+    // How close the deadline is, a closed project or one without a deadline has nothing to hurry for
+    pub fn urgency(&self, today: NaiveDate) -> Option<Urgency> {
+        match self.status.is_closed() {
+            true => None,
+            false => self.endpoint.map(|deadline| Urgency::of(deadline, today)),
+        }
+    }
+
+    // The start and the deadline, if both are known
+    pub fn schedule(&self) -> Option<(NaiveDate, NaiveDate)> {
+        self.startpoint.zip(self.endpoint)
+    }
+
+    // A project whose days could not be read is only written again with both days entered in the form, otherwise the
+    // update would replace the days on the server with nothing
+    pub fn check_dates(&self) -> Result<(), Error> {
+        match self.schedule() {
+            Some(_) => Ok(()),
+            None => Err(Error::Conflict(UNREADABLE_SCHEDULE.to_string())),
+        }
+    }
+
+    // Move the project to `status` if the workflow allows it
+    pub fn change_status(&mut self, status: ProjectStatus) -> Result<(), Error> {
+        self.check_dates()?;
+        if !self.status.next().contains(&status) {
+            return Err(Error::Conflict(format!(
+                "A project that is {} can not become {}",
+                self.status.label().to_lowercase(),
+                status.label().to_lowercase()
+            )));
+        }
+        self.status = status;
+        Ok(())
+    }
+}
+
+impl Resource for Project {
+    const PATH: &'static str = "Projects";
+
+    fn id(&self) -> i32 {
+        self.id
+    }
 }
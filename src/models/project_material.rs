@@ -7,10 +7,24 @@
 
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Serialize, Debug, Clone)]
+use crate::models::resource::Resource;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
 pub struct ProjectMaterial {
     pub id: i32,
     pub project_id: i32,
     pub material_id: i32,
+    // The amount that was taken from the warehouse for the project
     pub amount: i32,
+    // The amount the project still needs but did not take yet, older servers do not know it
+    #[serde(default)]
+    pub reserved: i32,
+}
+
+impl Resource for ProjectMaterial {
+    const PATH: &'static str = "ProjectMaterials";
+
+    fn id(&self) -> i32 {
+        self.id
+    }
 }
@@ -0,0 +1,302 @@
+/*
+
+    Author: Justin
+    Description: This file contains the model for a purchase order. An order is written as a draft, sent to the supplier
+                 and closed by the deliveries that are received against it. The status only changes along the allowed
+                 steps, every other change is refused.
+
+*/
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::models::date;
+use crate::models::error::Error;
+use crate::models::goods_receipt::ReceiptLine;
+use crate::models::money::Money;
+use crate::models::resource::Resource;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    // Written but not sent yet, it can still be changed
+    Draft,
+    // Sent to the supplier, nothing arrived yet
+    Sent,
+    // Some lines are still open
+    PartiallyReceived,
+    // Every line arrived
+    Received,
+    Cancelled,
+}
+
+impl OrderStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            OrderStatus::Draft => "Draft",
+            OrderStatus::Sent => "Sent",
+            OrderStatus::PartiallyReceived => "Partially received",
+            OrderStatus::Received => "Received",
+            OrderStatus::Cancelled => "Cancelled",
+        }
+    }
+
+    // The supplier still has to deliver something
+    pub fn is_open(&self) -> bool {
+        matches!(self, OrderStatus::Sent | OrderStatus::PartiallyReceived)
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct OrderLine {
+    pub material_id: i32,
+    pub quantity: i32,
+    // The amount that arrived so far
+    #[serde(default)]
+    pub received: i32,
+    // The agreed price per piece
+    pub unit_price: Money,
+}
+
+impl OrderLine {
+    // The amount that still has to arrive
+    pub fn open_quantity(&self) -> i32 {
+        (self.quantity - self.received).max(0)
+    }
+
+    // Rounded to cents like a line of an invoice
+    pub fn total(&self) -> Money {
+        (self.unit_price * self.quantity).round_to_cents()
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct PurchaseOrder {
+    pub id: i32,
+    pub supplier_id: i32,
+    pub status: OrderStatus,
+    // The day the order was sent, None for a draft
+    #[serde(with = "date::optional_api", default)]
+    pub order_date: Option<NaiveDate>,
+    // The day the supplier promised to deliver
+    #[serde(with = "date::api")]
+    pub expected_date: NaiveDate,
+    // Username of the keycloak session that wrote the order
+    pub user: String,
+    pub lines: Vec<OrderLine>,
+}
+
+impl PurchaseOrder {
+    // The net total, the sum of the rounded lines
+    pub fn total(&self) -> Money {
+        self.lines.iter().map(OrderLine::total).sum()
+    }
+
+    // The supplier should have delivered before `today` but did not deliver everything
+    pub fn is_overdue(&self, today: NaiveDate) -> bool {
+        self.status.is_open() && self.expected_date < today
+    }
+
+    // The amount of a material that the supplier still has to deliver. A draft was not sent yet and a cancelled order
+    // brings nothing, so both count as nothing
+    pub fn open_quantity(&self, material_id: i32) -> i32 {
+        match self.status.is_open() {
+            true => self
+                .lines
+                .iter()
+                .filter(|l| l.material_id == material_id)
+                .map(OrderLine::open_quantity)
+                .sum(),
+            false => 0,
+        }
+    }
+
+    pub fn send(&mut self, today: NaiveDate) -> Result<(), Error> {
+        if self.status != OrderStatus::Draft {
+            return Err(self.refused("sent"));
+        }
+        self.status = OrderStatus::Sent;
+        self.order_date = Some(today);
+        Ok(())
+    }
+
+    // Only an order that nothing arrived for can be cancelled
+    pub fn cancel(&mut self) -> Result<(), Error> {
+        if !matches!(self.status, OrderStatus::Draft | OrderStatus::Sent) {
+            return Err(self.refused("cancelled"));
+        }
+        self.status = OrderStatus::Cancelled;
+        Ok(())
+    }
+
+    pub fn set_expected_date(&mut self, date: NaiveDate) -> Result<(), Error> {
+        if !matches!(self.status, OrderStatus::Draft | OrderStatus::Sent) {
+            return Err(self.refused("rescheduled"));
+        }
+        self.expected_date = date;
+        Ok(())
+    }
+
+    // Close the lines with the delivered amounts, materials that were not ordered are left out
+    pub fn receive(&mut self, delivered: &[ReceiptLine]) -> Result<(), Error> {
+        if !self.status.is_open() {
+            return Err(self.refused("received"));
+        }
+        if delivered.iter().any(|d| d.quantity < 0) {
+            return Err(Error::Conflict("A delivered quantity can not be negative".to_string()));
+        }
+        // A delivery without any of the ordered materials would only change the status
+        let ordered = |delivery: &&ReceiptLine| self.lines.iter().any(|l| l.material_id == delivery.material_id);
+        if delivered.iter().filter(ordered).all(|d| d.quantity == 0) {
+            return Err(Error::Conflict(format!("Nothing of order #{} was delivered", self.id)));
+        }
+        for delivery in delivered {
+            let mut left = delivery.quantity;
+            // Fill the open lines of the material first, an over-delivery goes to the last one
+            let lines: Vec<usize> = (0..self.lines.len())
+                .filter(|&i| self.lines[i].material_id == delivery.material_id)
+                .collect();
+            for (n, &i) in lines.iter().enumerate() {
+                let line = &mut self.lines[i];
+                let taken = match n + 1 == lines.len() {
+                    true => left,
+                    false => left.min(line.open_quantity()),
+                };
+                line.received += taken;
+                left -= taken;
+            }
+        }
+        self.status = match self.lines.iter().all(|l| l.open_quantity() == 0) {
+            true => OrderStatus::Received,
+            false => OrderStatus::PartiallyReceived,
+        };
+        Ok(())
+    }
+
+    fn refused(&self, action: &str) -> Error {
+        Error::Conflict(format!(
+            "Order #{} is {} and can not be {}",
+            self.id,
+            self.status.label().to_lowercase(),
+            action
+        ))
+    }
+}
+
+impl Resource for PurchaseOrder {
+    const PATH: &'static str = "PurchaseOrders";
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 4, day).unwrap()
+    }
+
+    fn line(material_id: i32, quantity: i32) -> OrderLine {
+        OrderLine {
+            material_id,
+            quantity,
+            received: 0,
+            unit_price: Money::from_cents(199),
+        }
+    }
+
+    fn delivery(material_id: i32, quantity: i32) -> ReceiptLine {
+        ReceiptLine {
+            material_id,
+            quantity,
+            unit_price: Money::from_cents(199),
+        }
+    }
+
+    fn order(status: OrderStatus, lines: Vec<OrderLine>) -> PurchaseOrder {
+        PurchaseOrder {
+            id: 7,
+            supplier_id: 1,
+            status,
+            order_date: None,
+            expected_date: day(20),
+            user: "anna".to_string(),
+            lines,
+        }
+    }
+
+    #[test]
+    fn a_draft_is_sent_and_dated() {
+        let mut order = order(OrderStatus::Draft, vec![line(1, 10)]);
+        order.send(day(18)).unwrap();
+        assert_eq!(order.status, OrderStatus::Sent);
+        assert_eq!(order.order_date, Some(day(18)));
+        assert!(matches!(order.send(day(19)), Err(Error::Conflict(_))));
+    }
+
+    #[test]
+    fn only_orders_without_deliveries_can_be_cancelled_or_rescheduled() {
+        for status in [OrderStatus::Draft, OrderStatus::Sent] {
+            let mut order = order(status, vec![line(1, 10)]);
+            order.set_expected_date(day(25)).unwrap();
+            assert_eq!(order.expected_date, day(25));
+            order.cancel().unwrap();
+            assert_eq!(order.status, OrderStatus::Cancelled);
+        }
+        for status in [OrderStatus::PartiallyReceived, OrderStatus::Received, OrderStatus::Cancelled] {
+            let mut order = order(status, vec![line(1, 10)]);
+            assert!(order.cancel().is_err());
+            assert!(order.set_expected_date(day(25)).is_err());
+            assert_eq!(order.status, status);
+        }
+    }
+
+    #[test]
+    fn deliveries_fill_the_open_lines() {
+        let mut order = order(OrderStatus::Sent, vec![line(1, 10), line(2, 4), line(1, 5)]);
+        order.receive(&[delivery(1, 12), delivery(3, 8)]).unwrap();
+        assert_eq!(order.status, OrderStatus::PartiallyReceived);
+        let received: Vec<i32> = order.lines.iter().map(|l| l.received).collect();
+        assert_eq!(received, vec![10, 0, 2]);
+        assert_eq!(order.open_quantity(1), 3);
+
+        // An over-delivery goes to the last line of the material
+        order.receive(&[delivery(1, 5), delivery(2, 4)]).unwrap();
+        assert_eq!(order.status, OrderStatus::Received);
+        assert_eq!(order.lines[2].received, 7);
+        assert_eq!(order.open_quantity(1), 0);
+        assert!(order.receive(&[delivery(1, 1)]).is_err());
+    }
+
+    #[test]
+    fn an_empty_delivery_is_refused() {
+        let mut order = order(OrderStatus::Sent, vec![line(1, 10)]);
+        assert!(order.receive(&[delivery(1, 0)]).is_err());
+        assert!(order.receive(&[delivery(2, 5)]).is_err());
+        assert!(order.receive(&[]).is_err());
+        assert!(order.receive(&[delivery(1, -2)]).is_err());
+        assert_eq!(order.status, OrderStatus::Sent);
+        assert_eq!(order.lines[0].received, 0);
+    }
+
+    #[test]
+    fn only_sent_orders_are_on_the_way() {
+        let lines = vec![line(1, 10), line(1, 5), line(2, 3)];
+        assert_eq!(order(OrderStatus::Sent, lines.clone()).open_quantity(1), 15);
+        assert_eq!(order(OrderStatus::Draft, lines.clone()).open_quantity(1), 0);
+        assert_eq!(order(OrderStatus::Cancelled, lines.clone()).open_quantity(1), 0);
+        assert_eq!(order(OrderStatus::Received, lines).open_quantity(2), 0);
+    }
+
+    #[test]
+    fn overdue_orders_and_totals() {
+        let sent = order(OrderStatus::Sent, vec![line(1, 3), line(2, 1)]);
+        assert!(sent.is_overdue(day(21)));
+        assert!(!sent.is_overdue(day(20)));
+        assert!(!order(OrderStatus::Draft, vec![line(1, 3)]).is_overdue(day(21)));
+        assert_eq!(sent.total(), Money::from_cents(796));
+    }
+}
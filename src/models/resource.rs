@@ -0,0 +1,18 @@
+/*
+
+    Author: Justin
+    Description: This file contains the Resource trait. Every model that implements it can be loaded, created, updated and
+                 deleted through a Repository without writing any endpoint specific code.
+
+*/
+
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+pub trait Resource: Serialize + DeserializeOwned + Clone + PartialEq + Send + Sync + 'static {
+    // Path of the collection on the API without slashes, e.g. "Projects"
+    const PATH: &'static str;
+
+    // The id the item is addressed with in `/{PATH}/{id}`
+    fn id(&self) -> i32;
+}
@@ -0,0 +1,115 @@
+/*
+
+    Author: Justin
+    Description: This file contains the model for a stock movement. Every change of the amount of a material is recorded
+                 as a movement, so the stock of a material is the sum of its movements.
+
+*/
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+use crate::models::resource::Resource;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MovementReason {
+    // Material that came into the warehouse
+    Delivery,
+    // Material that was taken for a project
+    Consumption,
+    // Material that came back from a project
+    Return,
+    // The stock was counted and did not match
+    Correction,
+    // Material that was broken or lost
+    WriteOff,
+}
+
+impl MovementReason {
+    // The reasons a user can book by hand, consumption and return are booked with the materials of a project
+    pub const MANUAL: [MovementReason; 3] = [
+        MovementReason::Delivery,
+        MovementReason::Correction,
+        MovementReason::WriteOff,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MovementReason::Delivery => "Delivery",
+            MovementReason::Consumption => "Consumption",
+            MovementReason::Return => "Return",
+            MovementReason::Correction => "Correction",
+            MovementReason::WriteOff => "Write-off",
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct StockMovement {
+    pub id: i32,
+    pub material_id: i32,
+    // Positive if material came in, negative if it went out
+    pub delta: i32,
+    pub reason: MovementReason,
+    // The project the material was taken for or came back from
+    pub project_id: Option<i32>,
+    // Username of the keycloak session that booked the movement
+    pub user: String,
+    // RFC 3339
+    pub timestamp: String,
+}
+
+impl StockMovement {
+    // The time of the booking with the offset it was booked in, None if the text is not RFC 3339
+    pub fn time(&self) -> Option<DateTime<FixedOffset>> {
+        DateTime::parse_from_rfc3339(&self.timestamp).ok()
+    }
+}
+
+impl Resource for StockMovement {
+    const PATH: &'static str = "StockMovements";
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+}
+
+// The stock of the material according to its movements
+pub fn ledger_amount(movements: &[StockMovement], material_id: i32) -> i32 {
+    movements
+        .iter()
+        .filter(|m| m.material_id == material_id)
+        .map(|m| m.delta)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn movement(id: i32, timestamp: &str) -> StockMovement {
+        StockMovement {
+            id,
+            material_id: 1,
+            delta: 1,
+            reason: MovementReason::Delivery,
+            project_id: None,
+            user: "anna".to_string(),
+            timestamp: timestamp.to_string(),
+        }
+    }
+
+    #[test]
+    fn times_are_compared_as_points_in_time() {
+        // 09:30 in Berlin is before 08:00 in UTC, although the text sorts the other way round
+        let berlin = movement(1, "2024-04-18T09:30:00+02:00");
+        let utc = movement(2, "2024-04-18T08:00:00+00:00");
+        assert!(berlin.time() < utc.time());
+        assert_eq!(movement(3, "18.04.2024 09:30").time(), None);
+
+        let mut movements = [berlin, utc, movement(3, "yesterday")];
+        movements.sort_by_key(|m| std::cmp::Reverse((m.time(), m.id)));
+        assert_eq!(movements.iter().map(|m| m.id).collect::<Vec<_>>(), vec![2, 1, 3]);
+    }
+}
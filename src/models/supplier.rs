@@ -0,0 +1,53 @@
+/*
+
+    Author: Justin
+    Description: This file contains the models for a supplier and the materials it offers. A material can be bought from
+                 several suppliers, each with its own article number and price, one of them is the preferred one.
+
+*/
+
+use serde::{Deserialize, Serialize};
+
+use crate::models::money::Money;
+use crate::models::resource::Resource;
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct Supplier {
+    pub id: i32,
+    pub name: String,
+    // Contact person, phone or mail, whatever is needed to place an order
+    pub contact: String,
+    // Days from the order to the delivery
+    pub lead_time_days: i32,
+    // The supplier does not deliver less pieces of a material than this
+    pub minimum_order_quantity: i32,
+}
+
+impl Resource for Supplier {
+    const PATH: &'static str = "Suppliers";
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct SupplierMaterial {
+    pub id: i32,
+    pub supplier_id: i32,
+    pub material_id: i32,
+    // The number of the material in the catalogue of the supplier
+    pub article_number: String,
+    // The price per piece at this supplier
+    pub price: Money,
+    // Materials are reordered from their preferred supplier
+    pub preferred: bool,
+}
+
+impl Resource for SupplierMaterial {
+    const PATH: &'static str = "SupplierMaterials";
+
+    fn id(&self) -> i32 {
+        self.id
+    }
+}
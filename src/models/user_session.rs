@@ -0,0 +1,118 @@
+/*
+
+    Author: Justin
+    Description: This file contains the model for the logged in user. The roles come from the claims of the keycloak token
+                 and decide what the user is allowed to do in the client.
+
+*/
+
+use crate::models::error::Error;
+
+// The roles of the realm or the client that the workshop client knows about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Role {
+    WorkshopAdmin,
+    Warehouse,
+    Apprentice,
+}
+
+impl Role {
+    // Unknown roles of the realm, e.g. `offline_access`, are ignored
+    pub fn from_name(name: &str) -> Option<Role> {
+        match name {
+            "workshop-admin" => Some(Role::WorkshopAdmin),
+            "warehouse" => Some(Role::Warehouse),
+            "apprentice" => Some(Role::Apprentice),
+            _ => None,
+        }
+    }
+}
+
+// Everything the UI can offer or hide depending on the roles of the user
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Permission {
+    EditProjects,
+    DeleteProjects,
+    EditMaterials,
+    DeleteMaterials,
+    EditClients,
+    DeleteClients,
+}
+
+impl Permission {
+    fn description(&self) -> &'static str {
+        match self {
+            Permission::EditProjects => "edit projects",
+            Permission::DeleteProjects => "delete projects",
+            Permission::EditMaterials => "edit materials",
+            Permission::DeleteMaterials => "delete materials",
+            Permission::EditClients => "edit clients",
+            Permission::DeleteClients => "delete clients",
+        }
+    }
+}
+
+// The logged in user, empty if nobody is logged in
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct UserSession {
+    pub username: String,
+    pub name: String,
+    pub roles: Vec<Role>,
+}
+
+impl UserSession {
+    pub fn has_role(&self, role: Role) -> bool {
+        self.roles.contains(&role)
+    }
+
+    // Admins may do everything, the warehouse manages the materials and apprentices may only look. Clients are
+    // managed by the admins who plan the projects for them.
+    pub fn allows(&self, permission: Permission) -> bool {
+        if self.has_role(Role::WorkshopAdmin) {
+            return true;
+        }
+        match permission {
+            Permission::EditProjects | Permission::DeleteProjects => false,
+            Permission::EditClients | Permission::DeleteClients => false,
+            Permission::EditMaterials | Permission::DeleteMaterials => self.has_role(Role::Warehouse),
+        }
+    }
+
+    // Same check as `allows` but as an error that can be shown to the user
+    pub fn require(&self, permission: Permission) -> Result<(), Error> {
+        match self.allows(permission) {
+            true => Ok(()),
+            false => Err(Error::Forbidden(format!(
+                "You are not allowed to {}",
+                permission.description()
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(roles: &[Role]) -> UserSession {
+        UserSession {
+            username: "anna".to_string(),
+            name: "Anna".to_string(),
+            roles: roles.to_vec(),
+        }
+    }
+
+    #[test]
+    fn only_admins_manage_clients() {
+        let admin = session(&[Role::WorkshopAdmin]);
+        let warehouse = session(&[Role::Warehouse]);
+        for permission in [Permission::EditClients, Permission::DeleteClients] {
+            assert!(admin.allows(permission));
+            assert!(!warehouse.allows(permission));
+            assert!(!session(&[Role::Apprentice]).allows(permission));
+        }
+        assert!(warehouse.allows(Permission::EditMaterials));
+        let error = warehouse.require(Permission::DeleteClients).unwrap_err();
+        assert_eq!(error.to_string(), "Error 403: You are not allowed to delete clients");
+    }
+}
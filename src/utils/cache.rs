@@ -0,0 +1,465 @@
+/*
+
+    Author: Justin
+    Description: This file contains the local copy of the collections of the API. Every collection that is loaded from
+                 the server is stored in a SQLite database, so the client can still show the last known data while the
+                 server can not be reached. Changes made in that time wait in the outbox of the same database until
+                 they can be sent.
+
+*/
+
+// Import the necessary modules
+use std::path::PathBuf;
+
+use chrono::{DateTime, Local, TimeZone};
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::models::client::Client;
+use crate::models::error::Error;
+use crate::models::goods_receipt::GoodsReceipt;
+use crate::models::material::Material;
+use crate::models::material_type::MaterialType;
+use crate::models::project::Project;
+use crate::models::project_material::ProjectMaterial;
+use crate::models::purchase_order::PurchaseOrder;
+use crate::models::resource::Resource;
+use crate::models::stock_movement::StockMovement;
+use crate::models::supplier::{Supplier, SupplierMaterial};
+
+const CACHE_DIR: &str = "workshop_management_client";
+const CACHE_FILE: &str = "cache.sqlite";
+
+// The fields that refer to an item of another collection as (collection, field, referenced collection). `lines.` looks
+// at the field of every line of the item.
+const REFERENCES: [(&str, &str, &str); 12] = [
+    (Project::PATH, "client_id", Client::PATH),
+    (Material::PATH, "type_id", MaterialType::PATH),
+    (ProjectMaterial::PATH, "project_id", Project::PATH),
+    (ProjectMaterial::PATH, "material_id", Material::PATH),
+    (StockMovement::PATH, "project_id", Project::PATH),
+    (StockMovement::PATH, "material_id", Material::PATH),
+    (SupplierMaterial::PATH, "supplier_id", Supplier::PATH),
+    (SupplierMaterial::PATH, "material_id", Material::PATH),
+    (PurchaseOrder::PATH, "supplier_id", Supplier::PATH),
+    (PurchaseOrder::PATH, "lines.material_id", Material::PATH),
+    (GoodsReceipt::PATH, "purchase_order_id", PurchaseOrder::PATH),
+    (GoodsReceipt::PATH, "lines.material_id", Material::PATH),
+];
+
+// A collection from the cache and the time it was loaded from the server
+pub struct Cached<T> {
+    pub items: Vec<T>,
+    pub loaded_at: DateTime<Local>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Create,
+    Update,
+    Delete,
+}
+
+impl Operation {
+    fn name(&self) -> &'static str {
+        match self {
+            Operation::Create => "create",
+            Operation::Update => "update",
+            Operation::Delete => "delete",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Operation> {
+        match name {
+            "create" => Some(Operation::Create),
+            "update" => Some(Operation::Update),
+            "delete" => Some(Operation::Delete),
+            _ => None,
+        }
+    }
+}
+
+// A change that was made while the server could not be reached
+#[derive(Debug, Clone)]
+pub struct OutboxEntry {
+    pub id: i64,
+    // The path of the collection, see `Resource::PATH`
+    pub resource: String,
+    pub operation: Operation,
+    pub item_id: i32,
+    // The item as JSON after the change, None for a delete
+    pub data: Option<String>,
+    // The item as JSON as the client knew it before the change, None if there is nothing to compare with
+    pub base: Option<String>,
+}
+
+#[derive(Clone)]
+pub struct LocalCache {
+    // None if the system has no directory for application data
+    path: Option<PathBuf>,
+}
+
+impl LocalCache {
+    pub fn new() -> LocalCache {
+        LocalCache {
+            path: dirs::data_local_dir().map(|dir| dir.join(CACHE_DIR).join(CACHE_FILE)),
+        }
+    }
+
+    // A cache in a file of its own instead of the one of the user
+    #[cfg(test)]
+    pub fn at(path: PathBuf) -> LocalCache {
+        LocalCache { path: Some(path) }
+    }
+
+    // The database is opened for every access, it is small and only used when data is loaded
+    fn open(&self) -> Result<Connection, Error> {
+        let path = self
+            .path
+            .as_ref()
+            .ok_or(Error::Cache("No directory for application data".to_string()))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| Error::Cache(e.to_string()))?;
+        }
+        let connection = Connection::open(path)?;
+        // Every server has its own copy, the items of a collection are stored as the JSON the API sent
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS collections (
+                api_url TEXT NOT NULL,
+                resource TEXT NOT NULL,
+                data TEXT NOT NULL,
+                loaded_at INTEGER NOT NULL,
+                PRIMARY KEY (api_url, resource)
+            );
+            CREATE TABLE IF NOT EXISTS outbox (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                api_url TEXT NOT NULL,
+                resource TEXT NOT NULL,
+                operation TEXT NOT NULL,
+                item_id INTEGER NOT NULL,
+                data TEXT,
+                base TEXT
+            );",
+        )?;
+        Ok(connection)
+    }
+
+    // Replace the copy of the collection with the items that were just loaded
+    pub fn store<T: Resource>(&self, api_url: &str, items: &[T]) -> Result<(), Error> {
+        self.write(api_url, items, Local::now())
+    }
+
+    fn write<T: Resource>(&self, api_url: &str, items: &[T], loaded_at: DateTime<Local>) -> Result<(), Error> {
+        let data = serde_json::to_string(items).map_err(|e| Error::Cache(e.to_string()))?;
+        self.open()?.execute(
+            "INSERT OR REPLACE INTO collections (api_url, resource, data, loaded_at) VALUES (?1, ?2, ?3, ?4)",
+            params![api_url, T::PATH, data, loaded_at.timestamp()],
+        )?;
+        Ok(())
+    }
+
+    // Change the copy of a collection the same way the server will once it gets the change, the copy keeps its age
+    pub fn modify<T: Resource>(&self, api_url: &str, change: impl FnOnce(&mut Vec<T>)) -> Result<(), Error> {
+        let Some(mut cached) = self.load::<T>(api_url)? else {
+            return Ok(());
+        };
+        change(&mut cached.items);
+        self.write(api_url, &cached.items, cached.loaded_at)
+    }
+
+    // The last copy of the collection, None if it was never loaded from this server
+    pub fn load<T: Resource>(&self, api_url: &str) -> Result<Option<Cached<T>>, Error> {
+        let row: Option<(String, i64)> = self
+            .open()?
+            .query_row(
+                "SELECT data, loaded_at FROM collections WHERE api_url = ?1 AND resource = ?2",
+                params![api_url, T::PATH],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let Some((data, loaded_at)) = row else {
+            return Ok(None);
+        };
+
+        Ok(Some(Cached {
+            items: serde_json::from_str(&data).map_err(|e| Error::Cache(e.to_string()))?,
+            loaded_at: Local.timestamp_opt(loaded_at, 0).single().unwrap_or_else(Local::now),
+        }))
+    }
+}
+
+impl LocalCache {
+    // Remember a change for the server, `base` is the item before the change
+    pub fn enqueue<T: Resource>(
+        &self,
+        api_url: &str,
+        operation: Operation,
+        item_id: i32,
+        data: Option<&T>,
+        base: Option<&T>,
+    ) -> Result<(), Error> {
+        let to_json = |item: Option<&T>| {
+            item.map(serde_json::to_string)
+                .transpose()
+                .map_err(|e| Error::Cache(e.to_string()))
+        };
+        self.open()?.execute(
+            "INSERT INTO outbox (api_url, resource, operation, item_id, data, base) VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![api_url, T::PATH, operation.name(), item_id, to_json(data)?, to_json(base)?],
+        )?;
+        Ok(())
+    }
+
+    // All changes for the server in the order they were made
+    pub fn outbox(&self, api_url: &str) -> Result<Vec<OutboxEntry>, Error> {
+        let connection = self.open()?;
+        let mut statement = connection.prepare(
+            "SELECT id, resource, operation, item_id, data, base FROM outbox WHERE api_url = ?1 ORDER BY id",
+        )?;
+        let rows = statement.query_map(params![api_url], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i32>(3)?,
+                row.get::<_, Option<String>>(4)?,
+                row.get::<_, Option<String>>(5)?,
+            ))
+        })?;
+
+        let mut entries = Vec::new();
+        for row in rows {
+            let (id, resource, operation, item_id, data, base) = row?;
+            let operation = Operation::from_name(&operation)
+                .ok_or(Error::Cache(format!("Unknown operation {} in the outbox", operation)))?;
+            entries.push(OutboxEntry {
+                id,
+                resource,
+                operation,
+                item_id,
+                data,
+                base,
+            });
+        }
+        Ok(entries)
+    }
+
+    pub fn remove_entry(&self, entry_id: i64) -> Result<(), Error> {
+        self.open()?.execute("DELETE FROM outbox WHERE id = ?1", params![entry_id])?;
+        Ok(())
+    }
+
+    // An item that was created offline got its real id from the server, the later changes of it and every queued
+    // item that refers to it need that id
+    pub fn replace_id(&self, api_url: &str, resource: &str, old_id: i32, new_id: i32) -> Result<(), Error> {
+        let connection = self.open()?;
+        for entry in self.outbox(api_url)? {
+            let own = entry.resource == resource && entry.item_id == old_id;
+            let mut data: Option<serde_json::Value> = match &entry.data {
+                Some(data) => Some(serde_json::from_str(data).map_err(|e| Error::Cache(e.to_string()))?),
+                None => None,
+            };
+            let mut changed = own;
+            if let Some(item) = data.as_mut() {
+                if own {
+                    item["id"] = new_id.into();
+                }
+                let references = REFERENCES.iter().filter(|(from, _, to)| *from == entry.resource && *to == resource);
+                for (_, field, _) in references {
+                    changed |= replace_reference(item, field, old_id, new_id);
+                }
+            }
+            if !changed {
+                continue;
+            }
+            let item_id = if own { new_id } else { entry.item_id };
+            connection.execute(
+                "UPDATE outbox SET item_id = ?1, data = ?2 WHERE id = ?3",
+                params![item_id, data.map(|item| item.to_string()), entry.id],
+            )?;
+        }
+        Ok(())
+    }
+
+    // Items created offline get negative ids until the server gives them their real one
+    pub fn temporary_id<T: Resource>(&self, api_url: &str) -> Result<i32, Error> {
+        let cached = self.load::<T>(api_url)?.map(|c| c.items).unwrap_or_default();
+        let queued = self.outbox(api_url)?;
+        let lowest = cached
+            .iter()
+            .map(|item| item.id())
+            .chain(queued.iter().filter(|e| e.resource == T::PATH).map(|e| e.item_id))
+            .min()
+            .unwrap_or(0);
+        Ok(lowest.min(0) - 1)
+    }
+}
+
+// Set the field of the item, or of every line for `lines.<field>`, to `new_id` where it is `old_id`, true if it was
+fn replace_reference(item: &mut serde_json::Value, field: &str, old_id: i32, new_id: i32) -> bool {
+    if let Some((list, field)) = field.split_once('.') {
+        let Some(lines) = item.get_mut(list).and_then(|lines| lines.as_array_mut()) else {
+            return false;
+        };
+        // Every line has to be looked at, so this must not stop at the first replacement
+        let mut replaced = false;
+        for line in lines.iter_mut() {
+            replaced |= replace_reference(line, field, old_id, new_id);
+        }
+        return replaced;
+    }
+    match item.get_mut(field) {
+        Some(value) if value.as_i64() == Some(old_id.into()) => {
+            *value = new_id.into();
+            true
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::NaiveDate;
+
+    use crate::models::money::Money;
+    use crate::models::purchase_order::{OrderLine, OrderStatus};
+
+    const API_URL: &str = "http://localhost:8580";
+
+    // A fresh database in the temp directory that is removed again when the test ends
+    struct TestCache {
+        cache: LocalCache,
+        path: PathBuf,
+    }
+
+    impl TestCache {
+        fn new(name: &str) -> TestCache {
+            let path = std::env::temp_dir().join(format!("wms-cache-{}-{}.sqlite", name, std::process::id()));
+            let _ = std::fs::remove_file(&path);
+            TestCache {
+                cache: LocalCache::at(path.clone()),
+                path,
+            }
+        }
+
+        fn item<T: Resource>(&self, resource: &str, item_id: i32) -> T {
+            let entry = self
+                .cache
+                .outbox(API_URL)
+                .unwrap()
+                .into_iter()
+                .find(|e| e.resource == resource && e.item_id == item_id)
+                .unwrap();
+            serde_json::from_str(entry.data.as_deref().unwrap()).unwrap()
+        }
+    }
+
+    impl Drop for TestCache {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    fn project(id: i32) -> Project {
+        let day = NaiveDate::from_ymd_opt(2024, 4, 18).unwrap();
+        Project {
+            id,
+            name: "Kitchen".to_string(),
+            client_id: 3,
+            description: String::new(),
+            startpoint: Some(day),
+            endpoint: Some(day),
+            estimated_costs: Money::ZERO,
+            estimated_hours: 0.0,
+            costs: Money::ZERO,
+            status: Default::default(),
+        }
+    }
+
+    fn project_material(id: i32, project_id: i32, material_id: i32) -> ProjectMaterial {
+        ProjectMaterial {
+            id,
+            project_id,
+            material_id,
+            amount: 2,
+            reserved: 0,
+        }
+    }
+
+    #[test]
+    fn replace_id_follows_the_references_of_queued_items() {
+        let test = TestCache::new("references");
+        let cache = &test.cache;
+        // Temporary ids are counted per collection, so the project and the assignment both start at -1
+        cache.enqueue(API_URL, Operation::Create, -1, Some(&project(-1)), None).unwrap();
+        let assignment = project_material(-1, -1, 7);
+        cache.enqueue(API_URL, Operation::Create, -1, Some(&assignment), None).unwrap();
+        let mut renamed = project(-1);
+        renamed.name = "Kitchen and hall".to_string();
+        cache.enqueue(API_URL, Operation::Update, -1, Some(&renamed), Some(&project(-1))).unwrap();
+        let other = project_material(-2, 5, 7);
+        cache.enqueue(API_URL, Operation::Create, -2, Some(&other), None).unwrap();
+
+        cache.replace_id(API_URL, Project::PATH, -1, 41).unwrap();
+
+        let outbox = cache.outbox(API_URL).unwrap();
+        let projects: Vec<Project> = outbox
+            .iter()
+            .filter(|e| e.resource == Project::PATH && e.item_id == 41)
+            .map(|e| serde_json::from_str(e.data.as_deref().unwrap()).unwrap())
+            .collect();
+        assert_eq!(projects.len(), 2);
+        assert!(projects.iter().all(|p| p.id == 41));
+        // The assignment keeps its own id but points to the real project
+        assert_eq!(test.item::<ProjectMaterial>(ProjectMaterial::PATH, -1), project_material(-1, 41, 7));
+        assert_eq!(test.item::<ProjectMaterial>(ProjectMaterial::PATH, -2), other);
+
+        cache.replace_id(API_URL, ProjectMaterial::PATH, -1, 90).unwrap();
+        assert_eq!(test.item::<ProjectMaterial>(ProjectMaterial::PATH, 90), project_material(90, 41, 7));
+    }
+
+    #[test]
+    fn replace_id_follows_the_lines_of_orders() {
+        let test = TestCache::new("lines");
+        let cache = &test.cache;
+        let line = |material_id| OrderLine {
+            material_id,
+            quantity: 5,
+            received: 0,
+            unit_price: Money::from_cents(120),
+        };
+        let order = PurchaseOrder {
+            id: -1,
+            supplier_id: -3,
+            status: OrderStatus::Draft,
+            order_date: None,
+            expected_date: NaiveDate::from_ymd_opt(2024, 5, 2).unwrap(),
+            user: "anna".to_string(),
+            lines: vec![line(-2), line(8), line(-2)],
+        };
+        cache.enqueue(API_URL, Operation::Create, -1, Some(&order), None).unwrap();
+        cache.enqueue::<Material>(API_URL, Operation::Delete, -2, None, None).unwrap();
+
+        cache.replace_id(API_URL, Supplier::PATH, -3, 12).unwrap();
+        cache.replace_id(API_URL, Material::PATH, -2, 60).unwrap();
+
+        let queued: PurchaseOrder = test.item(PurchaseOrder::PATH, -1);
+        assert_eq!(queued.supplier_id, 12);
+        let materials: Vec<i32> = queued.lines.iter().map(|l| l.material_id).collect();
+        assert_eq!(materials, vec![60, 8, 60]);
+        // A delete has no item but still needs the real id
+        let outbox = cache.outbox(API_URL).unwrap();
+        assert!(outbox.iter().any(|e| e.resource == Material::PATH && e.item_id == 60 && e.data.is_none()));
+    }
+
+    #[test]
+    fn temporary_ids_stay_below_the_queued_ones() {
+        let test = TestCache::new("temporary");
+        let cache = &test.cache;
+        assert_eq!(cache.temporary_id::<Project>(API_URL).unwrap(), -1);
+        cache.enqueue(API_URL, Operation::Create, -1, Some(&project(-1)), None).unwrap();
+        assert_eq!(cache.temporary_id::<Project>(API_URL).unwrap(), -2);
+        assert_eq!(cache.temporary_id::<ProjectMaterial>(API_URL).unwrap(), -1);
+    }
+}
@@ -0,0 +1,558 @@
+/*
+
+    Author: Justin
+    Description: This file contains the runtime configuration of the client. The endpoints of the workshop API and keycloak
+                 are collected from the built in defaults, the config file, the environment and the command line.
+
+*/
+
+// Import the necessary modules
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use serde::Deserialize;
+
+// Import our own Error crate because its easier to handle errors
+use crate::models::error::Error;
+use crate::models::goods_receipt::Valuation;
+use crate::models::money::{self, VatRate};
+
+// Name of the folder inside of the XDG config directory and the config file in it
+const CONFIG_DIR: &str = "workshop_management_client";
+const CONFIG_FILE: &str = "config.toml";
+
+// Names of the profiles that are always available
+const PROD_PROFILE: &str = "prod";
+const LOCAL_PROFILE: &str = "local";
+
+// How often the open views are refreshed in the background if nothing else is configured
+const DEFAULT_POLL_INTERVAL: u64 = 60;
+
+// Hours the workshop can work on projects in one week if nothing else is configured
+const DEFAULT_WEEKLY_CAPACITY: f32 = 40.0;
+
+// The VAT rate in percent if nothing else is configured
+const DEFAULT_VAT_RATE: f32 = 19.0;
+
+/*
+    The configuration is built from the following layers, later layers win:
+
+    1. Built in profiles (`prod` and `local`), every other installation like a staging system is a profile of the
+       config file
+    2. The config file (`$XDG_CONFIG_HOME/workshop_management_client/config.toml` or `--config <path>`)
+    3. Environment variables (`WMS_PROFILE`, `WMS_API_URL`, `WMS_KEYCLOAK_URL`, `WMS_REALM`, `WMS_CLIENT_ID`, `WMS_CLIENT_SECRET`,
+       `WMS_POLL_INTERVAL`, `WMS_VALUATION`, `WMS_WEEKLY_CAPACITY`, `WMS_CURRENCY`, `WMS_VAT_RATE`)
+    4. Command line arguments (`--profile`, `--api-url`, `--keycloak-url`, `--realm`, `--client-id`, `--poll-interval`,
+       `--valuation`, `--weekly-capacity`, `--currency`, `--vat-rate`). The client secret is never taken
+       from the command line, where every other user of the machine could read it.
+
+    Example config file:
+
+        default_profile = "staging"
+        # Seconds between the background refreshes, 0 turns them off
+        poll_interval = 30
+        # How goods receipts change the costs of a material, "last_price" or "moving_average"
+        valuation = "moving_average"
+        # Hours per week the projects may need together before the deadline is flagged, 0 turns the check off
+        weekly_capacity = 80
+        # The symbol amounts are shown with and the VAT rate in percent
+        currency = "CHF"
+        vat_rate = 8.1
+
+        [profiles.staging]
+        api_url = "http://staging.example.org:8580"
+        keycloak_url = "http://staging.example.org:8480"
+
+    Values that are missing in a profile are taken from the built in profile with the same name or from `prod`.
+    The endpoint overrides from the environment and the command line are only applied to the selected profile, so the
+    other profiles on the login screen still point to their own installation.
+*/
+
+// All settings that are needed to talk to one installation of the workshop management system
+#[derive(Debug, Clone, PartialEq)]
+pub struct Profile {
+    pub api_url: String,
+    pub keycloak_url: String,
+    pub realm: String,
+    pub client_id: String,
+    pub client_secret: Option<String>,
+}
+
+impl Profile {
+    fn prod() -> Profile {
+        Profile {
+            api_url: "http://justinrauch.myftp.org:8580".to_string(),
+            keycloak_url: "http://justinrauch.myftp.org:8480".to_string(),
+            realm: "WMS".to_string(),
+            client_id: "workshop_client".to_string(),
+            // The built in profiles use the public client, a confidential client gets its secret from the config file
+            // or WMS_CLIENT_SECRET so it never ends up in the binary
+            client_secret: None,
+        }
+    }
+
+    fn local() -> Profile {
+        Profile {
+            api_url: "http://localhost:8580".to_string(),
+            keycloak_url: "http://localhost:8480".to_string(),
+            ..Profile::prod()
+        }
+    }
+
+    // Overwrite every value that is set in the partial profile
+    fn merge(mut self, partial: &PartialProfile) -> Profile {
+        if let Some(api_url) = &partial.api_url {
+            self.api_url = api_url.clone();
+        }
+        if let Some(keycloak_url) = &partial.keycloak_url {
+            self.keycloak_url = keycloak_url.clone();
+        }
+        if let Some(realm) = &partial.realm {
+            self.realm = realm.clone();
+        }
+        if let Some(client_id) = &partial.client_id {
+            self.client_id = client_id.clone();
+        }
+        if let Some(client_secret) = &partial.client_secret {
+            // An empty secret turns the client into a public client
+            self.client_secret = match client_secret.is_empty() {
+                true => None,
+                false => Some(client_secret.clone()),
+            };
+        }
+        self
+    }
+}
+
+impl Default for Profile {
+    fn default() -> Profile {
+        Profile::prod()
+    }
+}
+
+// A profile where every value is optional, used for the config file and the overrides
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(deny_unknown_fields)]
+struct PartialProfile {
+    api_url: Option<String>,
+    keycloak_url: Option<String>,
+    realm: Option<String>,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+}
+
+// The layout of the config file
+#[derive(Deserialize, Debug, Default)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    default_profile: Option<String>,
+    poll_interval: Option<u64>,
+    valuation: Option<Valuation>,
+    weekly_capacity: Option<f32>,
+    currency: Option<String>,
+    vat_rate: Option<f32>,
+    #[serde(default)]
+    profiles: BTreeMap<String, PartialProfile>,
+}
+
+// Everything that can be set on the command line
+#[derive(Debug, Default)]
+struct Arguments {
+    config_path: Option<PathBuf>,
+    profile: Option<String>,
+    poll_interval: Option<String>,
+    valuation: Option<String>,
+    weekly_capacity: Option<String>,
+    currency: Option<String>,
+    vat_rate: Option<String>,
+    overrides: PartialProfile,
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    default_profile: String,
+    profiles: BTreeMap<String, Profile>,
+    // None if the views are only refreshed when the user navigates
+    poll_interval: Option<Duration>,
+    valuation: Valuation,
+    // None if the capacity of the workshop is not checked
+    weekly_capacity: Option<f32>,
+    currency: String,
+    vat_rate: VatRate,
+}
+
+impl Config {
+    // Load the configuration from all layers
+    pub fn load() -> Result<Config, Error> {
+        let arguments = parse_arguments(std::env::args().skip(1))?;
+
+        // Read the config file if there is one
+        let path = match &arguments.config_path {
+            Some(path) => Some(path.clone()),
+            None => dirs::config_dir().map(|dir| dir.join(CONFIG_DIR).join(CONFIG_FILE)),
+        };
+        let file = match path {
+            // A missing default config file is fine, a missing explicit one is not
+            Some(path) if path.exists() || arguments.config_path.is_some() => read_config_file(&path)?,
+            _ => ConfigFile::default(),
+        };
+
+        Config::layered(file, arguments, env_var)
+    }
+
+    // Combine the config file with the environment, which `env` looks up, and the command line
+    fn layered(file: ConfigFile, arguments: Arguments, env: impl Fn(&str) -> Option<String>) -> Result<Config, Error> {
+        // Environment variables are overwritten by the command line
+        let overrides = PartialProfile {
+            api_url: env("WMS_API_URL"),
+            keycloak_url: env("WMS_KEYCLOAK_URL"),
+            realm: env("WMS_REALM"),
+            client_id: env("WMS_CLIENT_ID"),
+            client_secret: env("WMS_CLIENT_SECRET"),
+        };
+        let overrides = merge_partial(overrides, arguments.overrides);
+        let selected = arguments
+            .profile
+            .or_else(|| env("WMS_PROFILE"))
+            .or(file.default_profile.clone());
+        let text = |argument: Option<String>, name: &str| argument.or_else(|| env(name));
+
+        let seconds = setting(
+            "poll interval",
+            "seconds",
+            file.poll_interval,
+            text(arguments.poll_interval, "WMS_POLL_INTERVAL"),
+        )?;
+        let valuation = setting(
+            "valuation",
+            "last_price or moving_average",
+            file.valuation,
+            text(arguments.valuation, "WMS_VALUATION"),
+        )?;
+        let hours = setting(
+            "weekly capacity",
+            "hours",
+            file.weekly_capacity,
+            text(arguments.weekly_capacity, "WMS_WEEKLY_CAPACITY"),
+        )?;
+        let currency = setting("currency", "a symbol", file.currency, text(arguments.currency, "WMS_CURRENCY"))?;
+        let percent = setting("VAT rate", "percent", file.vat_rate, text(arguments.vat_rate, "WMS_VAT_RATE"))?;
+        let percent = percent.unwrap_or(DEFAULT_VAT_RATE);
+        let vat_rate = VatRate::from_percent(percent)
+            .ok_or(Error::Config(format!("Invalid VAT rate '{}', expected percent", percent)))?;
+
+        let mut profiles = build_profiles(&file.profiles);
+        let default_profile = selected.unwrap_or_else(|| PROD_PROFILE.to_string());
+        let Some(profile) = profiles.get_mut(&default_profile) else {
+            return Err(Error::Config(format!(
+                "Unknown profile '{}', add it as [profiles.{}] with its api_url and keycloak_url to the config file",
+                default_profile, default_profile
+            )));
+        };
+        *profile = profile.clone().merge(&overrides);
+
+        Ok(Config {
+            default_profile,
+            profiles,
+            poll_interval: poll_interval(seconds.unwrap_or(DEFAULT_POLL_INTERVAL)),
+            valuation: valuation.unwrap_or_default(),
+            weekly_capacity: weekly_capacity(hours.unwrap_or(DEFAULT_WEEKLY_CAPACITY)),
+            currency: currency.unwrap_or_else(|| money::DEFAULT_CURRENCY.to_string()),
+            vat_rate,
+        })
+    }
+
+    // Name of the profile that should be preselected
+    pub fn default_profile(&self) -> &str {
+        &self.default_profile
+    }
+
+    // Names of all available profiles in alphabetical order
+    pub fn profile_names(&self) -> Vec<String> {
+        self.profiles.keys().cloned().collect()
+    }
+
+    pub fn profile(&self, name: &str) -> Option<&Profile> {
+        self.profiles.get(name)
+    }
+
+    pub fn poll_interval(&self) -> Option<Duration> {
+        self.poll_interval
+    }
+
+    pub fn valuation(&self) -> Valuation {
+        self.valuation
+    }
+
+    pub fn weekly_capacity(&self) -> Option<f32> {
+        self.weekly_capacity
+    }
+
+    pub fn currency(&self) -> &str {
+        &self.currency
+    }
+
+    pub fn vat_rate(&self) -> VatRate {
+        self.vat_rate
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config::layered(ConfigFile::default(), Arguments::default(), |_| None)
+            .expect("The built in profiles are always valid")
+    }
+}
+
+// Combine the built in profiles and the profiles of the config file
+fn build_profiles(file: &BTreeMap<String, PartialProfile>) -> BTreeMap<String, Profile> {
+    let mut profiles: BTreeMap<String, Profile> = BTreeMap::new();
+    profiles.insert(PROD_PROFILE.to_string(), Profile::prod());
+    profiles.insert(LOCAL_PROFILE.to_string(), Profile::local());
+
+    for (name, partial) in file.iter() {
+        let base = profiles.get(name).cloned().unwrap_or_default();
+        profiles.insert(name.clone(), base.merge(partial));
+    }
+    profiles
+}
+
+// One setting of the layers below the profiles. A text of the environment or the command line wins over the config
+// file, None means the default is used
+fn setting<T: FromSetting>(
+    name: &str,
+    expected: &str,
+    file: Option<T>,
+    text: Option<String>,
+) -> Result<Option<T>, Error> {
+    match text {
+        Some(text) => T::from_setting(&text)
+            .map(Some)
+            .ok_or(Error::Config(format!("Invalid {} '{}', expected {}", name, text, expected))),
+        None => Ok(file),
+    }
+}
+
+// How the text of an environment variable or an argument is read for a setting
+trait FromSetting: Sized {
+    fn from_setting(text: &str) -> Option<Self>;
+}
+
+impl FromSetting for u64 {
+    fn from_setting(text: &str) -> Option<u64> {
+        text.parse().ok()
+    }
+}
+
+impl FromSetting for f32 {
+    fn from_setting(text: &str) -> Option<f32> {
+        text.parse().ok().filter(|value: &f32| value.is_finite())
+    }
+}
+
+impl FromSetting for String {
+    fn from_setting(text: &str) -> Option<String> {
+        Some(text.to_string())
+    }
+}
+
+impl FromSetting for Valuation {
+    fn from_setting(text: &str) -> Option<Valuation> {
+        Valuation::from_name(text)
+    }
+}
+
+fn read_config_file(path: &Path) -> Result<ConfigFile, Error> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        Error::Config(format!("Could not read config file {}: {}", path.display(), e))
+    })?;
+    toml::from_str(&content)
+        .map_err(|e| Error::Config(format!("Invalid config file {}: {}", path.display(), e)))
+}
+
+// 0 seconds turns the background refresh off
+fn poll_interval(seconds: u64) -> Option<Duration> {
+    match seconds {
+        0 => None,
+        seconds => Some(Duration::from_secs(seconds)),
+    }
+}
+
+// 0 hours (or less) turns the capacity check off
+fn weekly_capacity(hours: f32) -> Option<f32> {
+    match hours > 0.0 {
+        true => Some(hours),
+        false => None,
+    }
+}
+
+// Empty environment variables are treated as not set
+fn env_var(name: &str) -> Option<String> {
+    std::env::var(name).ok().filter(|value| !value.is_empty())
+}
+
+// Values of the second partial profile win
+fn merge_partial(base: PartialProfile, other: PartialProfile) -> PartialProfile {
+    PartialProfile {
+        api_url: other.api_url.or(base.api_url),
+        keycloak_url: other.keycloak_url.or(base.keycloak_url),
+        realm: other.realm.or(base.realm),
+        client_id: other.client_id.or(base.client_id),
+        client_secret: other.client_secret.or(base.client_secret),
+    }
+}
+
+// Supports `--key value` and `--key=value`, unknown arguments are rejected
+fn parse_arguments(mut args: impl Iterator<Item = String>) -> Result<Arguments, Error> {
+    let mut arguments = Arguments::default();
+
+    while let Some(arg) = args.next() {
+        let (key, inline_value) = match arg.split_once('=') {
+            Some((key, value)) => (key.to_string(), Some(value.to_string())),
+            None => (arg.clone(), None),
+        };
+        let value = match inline_value.or_else(|| args.next()) {
+            Some(value) => value,
+            None => return Err(Error::Config(format!("Missing value for {}", key))),
+        };
+
+        match key.as_str() {
+            "--config" => arguments.config_path = Some(PathBuf::from(value)),
+            "--profile" => arguments.profile = Some(value),
+            "--poll-interval" => arguments.poll_interval = Some(value),
+            "--valuation" => arguments.valuation = Some(value),
+            "--weekly-capacity" => arguments.weekly_capacity = Some(value),
+            "--currency" => arguments.currency = Some(value),
+            "--vat-rate" => arguments.vat_rate = Some(value),
+            "--api-url" => arguments.overrides.api_url = Some(value),
+            "--keycloak-url" => arguments.overrides.keycloak_url = Some(value),
+            "--realm" => arguments.overrides.realm = Some(value),
+            "--client-id" => arguments.overrides.client_id = Some(value),
+            _ => return Err(Error::Config(format!("Unknown argument {}", key))),
+        }
+    }
+
+    Ok(arguments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(content: &str) -> ConfigFile {
+        toml::from_str(content).unwrap()
+    }
+
+    fn arguments(args: &[&str]) -> Arguments {
+        parse_arguments(args.iter().map(|arg| arg.to_string())).unwrap()
+    }
+
+    fn no_env(_: &str) -> Option<String> {
+        None
+    }
+
+    #[test]
+    fn built_in_profiles_and_defaults() {
+        let config = Config::default();
+        assert_eq!(config.profile_names(), vec!["local", "prod"]);
+        assert_eq!(config.default_profile(), "prod");
+        assert_eq!(config.poll_interval(), Some(Duration::from_secs(DEFAULT_POLL_INTERVAL)));
+        assert_eq!(config.weekly_capacity(), Some(DEFAULT_WEEKLY_CAPACITY));
+        assert_eq!(config.currency(), money::DEFAULT_CURRENCY);
+        assert_eq!(config.vat_rate(), VatRate::from_percent(DEFAULT_VAT_RATE).unwrap());
+    }
+
+    #[test]
+    fn staging_only_comes_from_the_config_file() {
+        let error = Config::layered(ConfigFile::default(), arguments(&["--profile", "staging"]), no_env).unwrap_err();
+        assert!(matches!(&error, Error::Config(message) if message.contains("[profiles.staging]")));
+
+        let file = file(
+            r#"
+            [profiles.staging]
+            api_url = "http://staging:8580"
+            keycloak_url = "http://staging:8480"
+            "#,
+        );
+        let config = Config::layered(file, arguments(&["--profile", "staging"]), no_env).unwrap();
+        assert_eq!(config.default_profile(), "staging");
+        assert_eq!(config.profile("staging").unwrap().api_url, "http://staging:8580");
+    }
+
+    #[test]
+    fn file_profiles_extend_the_built_in_ones() {
+        let file = file(
+            r#"
+            default_profile = "staging"
+
+            [profiles.staging]
+            api_url = "http://staging.example.org:8580"
+            keycloak_url = "http://staging.example.org:8480"
+
+            [profiles.local]
+            realm = "Local"
+
+            [profiles.other]
+            realm = "Other"
+            "#,
+        );
+        let config = Config::layered(file, Arguments::default(), no_env).unwrap();
+        assert_eq!(config.default_profile(), "staging");
+
+        let staging = config.profile("staging").unwrap();
+        assert_eq!(staging.api_url, "http://staging.example.org:8580");
+        assert_eq!(staging.realm, Profile::prod().realm);
+
+        let local = config.profile("local").unwrap();
+        assert_eq!(local.realm, "Local");
+        assert_eq!(local.api_url, Profile::local().api_url);
+
+        let other = config.profile("other").unwrap();
+        assert_eq!(other.realm, "Other");
+        assert_eq!(other.api_url, Profile::prod().api_url);
+    }
+
+    #[test]
+    fn command_line_wins_over_environment_and_file() {
+        let file = file("poll_interval = 30\nweekly_capacity = 80\ncurrency = \"CHF\"\nvat_rate = 8.1");
+        let env = |name: &str| match name {
+            "WMS_POLL_INTERVAL" => Some("10".to_string()),
+            "WMS_VAT_RATE" => Some("7".to_string()),
+            "WMS_API_URL" => Some("http://env.example.org".to_string()),
+            _ => None,
+        };
+        let arguments = arguments(&["--vat-rate=0", "--valuation", "moving_average"]);
+        let config = Config::layered(file, arguments, env).unwrap();
+
+        assert_eq!(config.poll_interval(), Some(Duration::from_secs(10)));
+        assert_eq!(config.weekly_capacity(), Some(80.0));
+        assert_eq!(config.currency(), "CHF");
+        assert_eq!(config.vat_rate(), VatRate::from_percent(0.0).unwrap());
+        assert_eq!(config.valuation(), Valuation::MovingAverage);
+        // Endpoint overrides only apply to the selected profile
+        assert_eq!(config.profile("prod").unwrap().api_url, "http://env.example.org");
+        assert_eq!(config.profile("local").unwrap(), &Profile::local());
+    }
+
+    #[test]
+    fn zero_turns_refresh_and_capacity_off() {
+        let config = Config::layered(file("poll_interval = 0\nweekly_capacity = 0"), Arguments::default(), no_env);
+        let config = config.unwrap();
+        assert_eq!(config.poll_interval(), None);
+        assert_eq!(config.weekly_capacity(), None);
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        let invalid = |args: &[&str]| Config::layered(ConfigFile::default(), arguments(args), no_env).is_err();
+        assert!(invalid(&["--poll-interval", "soon"]));
+        assert!(invalid(&["--valuation", "cheapest"]));
+        assert!(invalid(&["--weekly-capacity", "NaN"]));
+        assert!(invalid(&["--vat-rate", "150"]));
+        assert!(invalid(&["--profile", "missing"]));
+        assert!(Config::layered(file("vat_rate = -1"), Arguments::default(), no_env).is_err());
+        assert!(parse_arguments(["--unknown".to_string(), "1".to_string()].into_iter()).is_err());
+        assert!(parse_arguments(["--profile".to_string()].into_iter()).is_err());
+    }
+}
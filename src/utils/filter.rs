@@ -0,0 +1,141 @@
+/*
+
+    Author: Justin
+    Description: This file contains the filter layer between the data of the client and the lists of the UI. The full list
+                 is kept, the UI only gets a filtered view of it, so clearing the search brings every item back and a
+                 refresh of the data keeps the current search. Besides the search a list can have a scope, e.g. only the
+                 projects of one status, that is kept the same way.
+
+*/
+
+// Import the necessary modules
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use slint::{FilterModel, Model, ModelRc, VecModel};
+
+// Decides if an item matches the search, the search is already trimmed and in lowercase and never empty
+pub type Matcher<T> = fn(&T, &str) -> bool;
+
+pub type Filter<T> = Box<dyn Fn(&T) -> bool>;
+
+#[derive(Clone)]
+pub struct FilteredList<T: Clone + 'static> {
+    items: Rc<VecModel<T>>,
+    search: Rc<RefCell<String>>,
+    scope: Rc<RefCell<Option<Filter<T>>>>,
+    filtered: Rc<FilterModel<ModelRc<T>, Filter<T>>>,
+}
+
+impl<T: Clone + 'static> FilteredList<T> {
+    pub fn new(matcher: Matcher<T>) -> FilteredList<T> {
+        let items: Rc<VecModel<T>> = Rc::new(VecModel::from(Vec::new()));
+        let search: Rc<RefCell<String>> = Rc::new(RefCell::new(String::new()));
+        let scope: Rc<RefCell<Option<Filter<T>>>> = Rc::new(RefCell::new(None));
+        let filter: Filter<T> = Box::new({
+            let search = search.clone();
+            let scope = scope.clone();
+            move |item: &T| {
+                let in_scope = match scope.borrow().as_ref() {
+                    Some(scope) => scope(item),
+                    None => true,
+                };
+                let search = search.borrow();
+                in_scope && (search.is_empty() || matcher(item, &search))
+            }
+        });
+        let filtered = Rc::new(FilterModel::new(ModelRc::from(items.clone()), filter));
+
+        FilteredList {
+            items,
+            search,
+            scope,
+            filtered,
+        }
+    }
+
+    // The filtered view of the items for the UI, it follows every change of the items and the search
+    pub fn model(&self) -> ModelRc<T> {
+        ModelRc::from(self.filtered.clone())
+    }
+
+    // Replace all items, e.g. after the data was loaded again, the search stays as it is
+    pub fn set_items(&self, items: Vec<T>) {
+        self.items.set_vec(items);
+    }
+
+    // Every item, no matter the search
+    pub fn items(&self) -> Vec<T> {
+        self.items.iter().collect()
+    }
+
+    pub fn set_search(&self, search: &str) {
+        let search = search.trim().to_lowercase();
+        if *self.search.borrow() == search {
+            return;
+        }
+        *self.search.borrow_mut() = search;
+        self.filtered.reset();
+    }
+
+    // Only show the items the scope accepts, `None` shows every item again
+    pub fn set_scope(&self, scope: Option<Filter<T>>) {
+        *self.scope.borrow_mut() = scope;
+        self.filtered.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Item = (i32, &'static str);
+
+    fn by_name(item: &Item, search: &str) -> bool {
+        item.1.to_lowercase().contains(search)
+    }
+
+    fn shown(list: &FilteredList<Item>) -> Vec<i32> {
+        list.model().iter().map(|item| item.0).collect()
+    }
+
+    fn list() -> FilteredList<Item> {
+        let list = FilteredList::new(by_name as Matcher<Item>);
+        list.set_items(vec![(1, "Oak table"), (2, "Pine shelf"), (3, "Oak door")]);
+        list
+    }
+
+    #[test]
+    fn the_search_is_trimmed_and_ignores_case() {
+        let list = list();
+        assert_eq!(shown(&list), vec![1, 2, 3]);
+        list.set_search("  OAK ");
+        assert_eq!(shown(&list), vec![1, 3]);
+        list.set_search("walnut");
+        assert!(shown(&list).is_empty());
+        // Clearing the search brings every item back
+        list.set_search(" ");
+        assert_eq!(shown(&list), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn new_items_keep_the_search() {
+        let list = list();
+        let model = list.model();
+        list.set_search("oak");
+        list.set_items(vec![(4, "Oak bench"), (5, "Birch chair")]);
+        assert_eq!(model.iter().map(|item| item.0).collect::<Vec<_>>(), vec![4]);
+        assert_eq!(list.items(), vec![(4, "Oak bench"), (5, "Birch chair")]);
+    }
+
+    #[test]
+    fn the_scope_and_the_search_both_have_to_match() {
+        let list = list();
+        list.set_scope(Some(Box::new(|item: &Item| item.0 != 1)));
+        assert_eq!(shown(&list), vec![2, 3]);
+        list.set_search("oak");
+        assert_eq!(shown(&list), vec![3]);
+        list.set_scope(None);
+        assert_eq!(shown(&list), vec![1, 3]);
+    }
+}
@@ -6,141 +6,582 @@
 */
 
 // Import the necessary modules
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
 
 // Import out own Error crate because its easier to handle errors
 use crate::models::error::Error;
+use crate::models::user_session::{Permission, Role, UserSession};
+
+// The keycloak server, realm and client come from the selected profile
+use crate::utils::config::Profile;
+
+// Refresh the access token a little before it runs out so it does not expire on the way to the server
+const EXPIRY_MARGIN: u64 = 30;
+
+// How long we wait for the user to finish the login in the browser
+const BROWSER_LOGIN_TIMEOUT: Duration = Duration::from_secs(300);
+// Path of the redirect uri that the local listener answers
+const CALLBACK_PATH: &str = "/callback";
+// How long a connection of the browser may take to send its request, browsers open connections in advance that
+// send nothing and must not keep the callback waiting
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
 
-// Define the constants for the keycloak server
-const API_URL: &str = "http://justinrauch.myftp.org:8480/";
-const REALM: &str = "WMS";
-const CLIENT_ID: &str = "workshop_client";
-const CLIENT_SECRET: &str = "Ip7GUqM8mRuIHMcq3tOuuHCaejSwSk3S";
+// The parts of the token response of keycloak that we need
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    // Lifetime of the access token in seconds
+    expires_in: u64,
+    refresh_token: String,
+    // Lifetime of the refresh token in seconds, 0 means it does not expire
+    #[serde(default)]
+    refresh_expires_in: u64,
+}
+
+// The claims of the access token that describe the user
+#[derive(Deserialize, Default)]
+struct Claims {
+    #[serde(default)]
+    preferred_username: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    realm_access: Access,
+    // The roles of every client, keyed by the client id
+    #[serde(default)]
+    resource_access: HashMap<String, Access>,
+}
+
+#[derive(Deserialize, Default)]
+struct Access {
+    #[serde(default)]
+    roles: Vec<String>,
+}
 
 #[derive(Clone)]
 pub struct Keycloak {
+    profile: Profile,
+    // Shared connection pool for all requests to keycloak
+    client: reqwest::Client,
     token: String,
-    username: String,
-    password: String,
-    login_timestamp: u64,
+    refresh_token: String,
+    // Unix timestamps after which the tokens are no longer valid
+    token_expires_at: u64,
+    refresh_expires_at: u64,
+    session: UserSession,
 }
 
 impl Keycloak {
-    pub fn new() -> Keycloak {
+    pub fn new(profile: &Profile) -> Keycloak {
         Keycloak {
+            profile: profile.clone(),
+            client: reqwest::Client::new(),
             token: String::new(),
-            username: String::new(),
-            password: String::new(),
-            login_timestamp: 0,
+            refresh_token: String::new(),
+            token_expires_at: 0,
+            refresh_expires_at: 0,
+            session: UserSession::default(),
         }
     }
 
-    #[tokio::main]
     pub async fn login_user(
         &mut self,
         username: &str,
         password: &str,
     ) -> Result<String, Error> {
-        // Send a POST request to the keycloak server to get a token
-        let response = reqwest::Client::new()
-            .post(&format!(
-                "{}/realms/{}/protocol/openid-connect/token",
-                API_URL, REALM
-            ))
-            .form(&[
-                ("client_id", CLIENT_ID),
-                ("client_secret", CLIENT_SECRET),
-                ("username", username),
-                ("password", password),
-                ("grant_type", "password"),
-            ])
-            // Set a timeout of 5 seconds
-            .timeout(std::time::Duration::from_secs(5))
-            .send()
-            .await;
-        let response = match response {
-            Ok(response) => response,
-            // If the request failed return an error
-            Err(e) => {
-                return Err(Error::new(e.to_string(), 500));
-            }
-        };
-        // Check if the response is an error
-        match response.error_for_status_ref().err() {
-            Some(err) => {
-                // Get the status code and message of the error and turn it into our own error class
-                let status = err.status().unwrap().as_u16();
-                let message = err.to_string();
-                return Err(Error::new(message, status.into()));
-            },
-            None => {
-                let token = response.json().await;
-                let token: serde_json::Value = match token {
-                    Ok(token) => token,
-                    // If the token could not be parsed return an error
-                    Err(e) => {
-                        return Err(Error::new(e.to_string(), 500));
-                    }
-                };
-                // Set the login timestamp and return the token
-                self.login_timestamp = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs();
-                let token = token["access_token"].as_str().unwrap().to_string();
-                self.set_token(token.clone());
-                Ok(token)
-            }
-        }
+        // The password is only sent once, afterwards the refresh token is used
+        self.request_token(vec![
+            ("username", username),
+            ("password", password),
+            ("grant_type", "password"),
+        ])
+        .await
     }
 
-    pub fn set_token(&mut self, token: String) {
-        self.token = token;
+    // Prepare a login in the system browser with the authorization code flow and PKCE, the password never touches the
+    // client. The login does not borrow the Keycloak, so it can wait for the user without blocking the other requests.
+    pub async fn browser_login(&self) -> Result<BrowserLogin, Error> {
+        // Keycloak accepts any port for loopback redirects, so the system can choose a free one
+        let listener = TcpListener::bind(("127.0.0.1", 0)).await.map_err(io_error)?;
+        let port = listener.local_addr().map_err(io_error)?.port();
+        let redirect_uri = format!("http://127.0.0.1:{}{}", port, CALLBACK_PATH);
+
+        // Only the hash of the verifier leaves the client before the code is exchanged
+        let verifier = random_string();
+        let challenge = code_challenge(&verifier);
+        let state = random_string();
+
+        let mut url = reqwest::Url::parse(&self.endpoint("auth"))
+            .map_err(|e| Error::Config(format!("Invalid keycloak url: {}", e)))?;
+        url.query_pairs_mut()
+            .append_pair("response_type", "code")
+            .append_pair("client_id", &self.profile.client_id)
+            .append_pair("redirect_uri", &redirect_uri)
+            .append_pair("scope", "openid")
+            .append_pair("state", &state)
+            .append_pair("code_challenge", &challenge)
+            .append_pair("code_challenge_method", "S256");
+
+        Ok(BrowserLogin {
+            keycloak: Keycloak::new(&self.profile),
+            listener,
+            url,
+            redirect_uri,
+            verifier,
+            state,
+        })
     }
 
-    pub fn set_username(&mut self, username: String) {
-        self.username = username;
+    // Take over the tokens of a finished browser login, unless the profile was switched in the meantime
+    pub fn complete_login(&mut self, login: Keycloak) -> Result<String, Error> {
+        if login.profile != self.profile {
+            return Err(Error::Unauthorized("The profile was changed during the login".into()));
+        }
+        *self = Keycloak {
+            client: self.client.clone(),
+            ..login
+        };
+        Ok(self.token.clone())
     }
 
-    pub fn set_password(&mut self, password: String) {
-        self.password = password;
+    // Switch to another keycloak server, the current session is dropped
+    pub fn set_profile(&mut self, profile: &Profile) {
+        if self.profile != *profile {
+            self.clear();
+            self.profile = profile.clone();
+        }
     }
 
     pub fn clear(&mut self) {
         self.token.clear();
-        self.username.clear();
-        self.password.clear();
-        self.login_timestamp = 0;
+        self.refresh_token.clear();
+        self.token_expires_at = 0;
+        self.refresh_expires_at = 0;
+        self.session = UserSession::default();
     }
 
-    pub fn refresh_token(&mut self) -> Result<String, Error> {
-        // Check if the token is empty or if the time since the last login is less than 5 minutes
-        let time_since_login = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-            - self.login_timestamp;
+    // The user of the current token
+    pub fn session(&self) -> &UserSession {
+        &self.session
+    }
+
+    // Get a valid access token, but only if the user is allowed to do what the token is needed for
+    pub async fn authorize(&mut self, permission: Permission) -> Result<String, Error> {
+        let token = self.refresh_token().await?;
+        self.session.require(permission)?;
+        Ok(token)
+    }
+
+    // Get a valid access token, renewing it with the refresh token if it is about to expire
+    pub async fn refresh_token(&mut self) -> Result<String, Error> {
         // User never logged in
         if self.token.is_empty() {
-            return Err(Error::new("Token not found".into(), 401));
+            return Err(Error::Unauthorized("Token not found".into()));
         }
         // Old token still valid, no need to refresh
-        if time_since_login < 300 {
+        let now = now();
+        if now + EXPIRY_MARGIN < self.token_expires_at {
             return Ok(self.token.clone());
         }
-        // Attempt to login the user again
-        let token = self.login_user(&self.username.clone(), &self.password.clone());
-        // Check if the token was successfully retrieved otherwise handle the error
-        match token {
-            Err(e) => Err(e),
-            Ok(token) => {
-                // Set the new token and update the login timestamp
-                self.login_timestamp = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs();
-                Ok(token)
+        // The refresh token ran out as well, the user has to login again
+        if now >= self.refresh_expires_at {
+            self.clear();
+            return Err(Error::SessionExpired);
+        }
+
+        let refresh_token = self.refresh_token.clone();
+        let result = self
+            .request_token(vec![
+                ("refresh_token", refresh_token.as_str()),
+                ("grant_type", "refresh_token"),
+            ])
+            .await;
+        match result {
+            // Keycloak answers with 400 invalid_grant if the session was ended on the server
+            Err(Error::Unauthorized(_)) | Err(Error::Validation { .. }) => {
+                self.clear();
+                Err(Error::SessionExpired)
+            }
+            // Without a connection the old token is used while it is valid, so reads can still be answered from the
+            // local cache. An expired one would only be refused by the API, so the network error is passed on.
+            Err(Error::Network(_)) | Err(Error::Timeout) if now < self.token_expires_at => Ok(self.token.clone()),
+            result => result,
+        }
+    }
+
+    // The OpenID Connect endpoints of the realm, e.g. `auth` or `token`
+    fn endpoint(&self, name: &str) -> String {
+        format!(
+            "{}/realms/{}/protocol/openid-connect/{}",
+            self.profile.keycloak_url.trim_end_matches('/'),
+            self.profile.realm,
+            name
+        )
+    }
+
+    // Send a request to the token endpoint and keep the tokens of the response
+    async fn request_token(&mut self, mut form: Vec<(&str, &str)>) -> Result<String, Error> {
+        // The client secret is only sent for confidential clients
+        form.push(("client_id", self.profile.client_id.as_str()));
+        if let Some(client_secret) = &self.profile.client_secret {
+            form.push(("client_secret", client_secret.as_str()));
+        }
+        // Send a POST request to the keycloak server to get a token
+        let response = self
+            .client
+            .post(self.endpoint("token"))
+            .form(&form)
+            // Set a timeout of 5 seconds
+            .timeout(std::time::Duration::from_secs(5))
+            .send()
+            .await?;
+        // Check if the response is an error and turn it into our own error class with the status and body
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(Error::from_status(status.as_u16(), body));
+        }
+        let response: TokenResponse = response.json().await?;
+        // The roles can change between two tokens, so the session is taken from every new one
+        let session = self.decode_session(&response.access_token)?;
+
+        // Remember when the tokens run out
+        let now = now();
+        self.token_expires_at = now + response.expires_in;
+        self.refresh_expires_at = match response.refresh_expires_in {
+            0 => u64::MAX,
+            lifetime => now + lifetime,
+        };
+        self.token = response.access_token;
+        self.refresh_token = response.refresh_token;
+        self.session = session;
+        Ok(self.token.clone())
+    }
+
+    // Read the user and the roles of the realm and of our client from the payload of the JWT
+    fn decode_session(&self, token: &str) -> Result<UserSession, Error> {
+        let payload = token
+            .split('.')
+            .nth(1)
+            .ok_or(Error::Decode("The access token is no JWT".into()))?;
+        let payload = URL_SAFE_NO_PAD
+            .decode(payload.trim_end_matches('='))
+            .map_err(|e| Error::Decode(format!("Invalid access token: {}", e)))?;
+        let claims: Claims = serde_json::from_slice(&payload)
+            .map_err(|e| Error::Decode(format!("Invalid access token: {}", e)))?;
+
+        let client_roles = claims
+            .resource_access
+            .get(&self.profile.client_id)
+            .map(|access| access.roles.iter())
+            .into_iter()
+            .flatten();
+        let mut roles: Vec<Role> = Vec::new();
+        for role in claims.realm_access.roles.iter().chain(client_roles) {
+            if let Some(role) = Role::from_name(role) {
+                if !roles.contains(&role) {
+                    roles.push(role);
+                }
+            }
+        }
+
+        Ok(UserSession {
+            username: claims.preferred_username,
+            name: claims.name,
+            roles,
+        })
+    }
+}
+
+// A login in the browser that waits for keycloak to send the browser back to the local listener
+pub struct BrowserLogin {
+    // Receives the tokens, so the shared Keycloak only has to be locked to take them over
+    keycloak: Keycloak,
+    listener: TcpListener,
+    // The login page of keycloak
+    url: reqwest::Url,
+    redirect_uri: String,
+    verifier: String,
+    state: String,
+}
+
+impl BrowserLogin {
+    // Open the login page in the system browser
+    pub fn open_browser(&self) {
+        // Without a browser the user can still open the address by hand
+        if let Err(e) = open::that(self.url.as_str()) {
+            eprintln!("Could not open the browser ({}), please open {}", e, self.url);
+        }
+    }
+
+    // Wait until the user logged in and exchange the code for the tokens, the result goes to `Keycloak::complete_login`
+    pub async fn finish(mut self) -> Result<Keycloak, Error> {
+        let callback = wait_for_code(&self.listener, &self.state);
+        let code = match tokio::time::timeout(BROWSER_LOGIN_TIMEOUT, callback).await {
+            Ok(code) => code?,
+            Err(_) => return Err(Error::Timeout),
+        };
+
+        self.keycloak
+            .request_token(vec![
+                ("grant_type", "authorization_code"),
+                ("code", code.as_str()),
+                ("redirect_uri", self.redirect_uri.as_str()),
+                ("code_verifier", self.verifier.as_str()),
+            ])
+            .await?;
+        Ok(self.keycloak)
+    }
+}
+
+// Seconds since the unix epoch, a clock before 1970 counts as 0
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+// Answer requests on the local listener until keycloak sends the browser back with the code
+async fn wait_for_code(listener: &TcpListener, state: &str) -> Result<String, Error> {
+    loop {
+        let (mut stream, _) = listener.accept().await.map_err(io_error)?;
+        // The request line is all we need, e.g. `GET /callback?state=...&code=... HTTP/1.1`
+        let mut buffer = vec![0; 8192];
+        let length = match tokio::time::timeout(REQUEST_TIMEOUT, stream.read(&mut buffer)).await {
+            Ok(Ok(length)) if length > 0 => length,
+            // A connection that sends nothing or breaks off is dropped, the next one may be the callback
+            _ => continue,
+        };
+        let request = String::from_utf8_lossy(&buffer[..length]);
+        let target = request
+            .lines()
+            .next()
+            .and_then(|line| line.split_whitespace().nth(1))
+            .unwrap_or_default();
+
+        let url = match reqwest::Url::parse(&format!("http://127.0.0.1{}", target)) {
+            Ok(url) if url.path() == CALLBACK_PATH => url,
+            // Browsers also ask for a favicon and the like
+            _ => {
+                respond(&mut stream, "404 Not Found", "Not found").await;
+                continue;
             }
+        };
+
+        let params: HashMap<String, String> = url.query_pairs().into_owned().collect();
+        // Another page or an old tab must not end the login, so we keep waiting for the right answer
+        if params.get("state").map(String::as_str) != Some(state) {
+            respond(&mut stream, "400 Bad Request", "This answer does not belong to the running login.").await;
+            continue;
         }
+        let result = if let Some(error) = params.get("error") {
+            Err(Error::Unauthorized(params.get("error_description").unwrap_or(error).clone()))
+        } else {
+            params
+                .get("code")
+                .cloned()
+                .ok_or(Error::Decode("The answer of the browser contains no code".into()))
+        };
+
+        let page = match &result {
+            Ok(_) => "Login successful, you can close this window and return to the workshop client.".to_string(),
+            Err(e) => format!("Login failed: {}", e),
+        };
+        respond(&mut stream, "200 OK", &page).await;
+        return result;
+    }
+}
+
+// Send a plain text page to the browser, if that fails the browser just shows an error
+async fn respond(stream: &mut TcpStream, status: &str, body: &str) {
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        body
+    );
+    let _ = stream.write_all(response.as_bytes()).await;
+    let _ = stream.shutdown().await;
+}
+
+// The S256 challenge of a PKCE verifier
+fn code_challenge(verifier: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+// 32 random bytes, url safe encoded, used for the PKCE verifier and the state
+fn random_string() -> String {
+    URL_SAFE_NO_PAD.encode(rand::random::<[u8; 32]>())
+}
+
+fn io_error(error: std::io::Error) -> Error {
+    Error::Network(error.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::models::user_session::Role;
+    use crate::utils::mock_http::{self, Requests};
+
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
+
+    // An access token with the claims keycloak sends, the signature is not checked by the client
+    fn access_token(claims: &str) -> String {
+        format!("e30.{}.signature", URL_SAFE_NO_PAD.encode(claims))
+    }
+
+    // A token endpoint on the loopback interface that hands out a token of anna
+    async fn mock_token_endpoint() -> (String, Requests) {
+        mock_http::serve(|_| {
+            let token = access_token(r#"{"preferred_username":"anna","realm_access":{"roles":["warehouse"]}}"#);
+            let json = format!(
+                r#"{{"access_token":"{}","expires_in":300,"refresh_token":"refresh","refresh_expires_in":1800}}"#,
+                token
+            );
+            (200, json)
+        })
+        .await
+    }
+
+    #[test]
+    fn code_challenge_of_the_rfc_example() {
+        // RFC 7636, appendix B
+        let challenge = code_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk");
+        assert_eq!(challenge, "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM");
+    }
+
+    #[test]
+    fn verifier_and_state_are_random() {
+        let verifier = random_string();
+        assert_eq!(verifier.len(), 43);
+        assert_ne!(verifier, random_string());
+    }
+
+    #[test]
+    fn browser_login_exchanges_the_code_with_the_verifier() {
+        block_on(async {
+            let (keycloak_url, token_requests) = mock_token_endpoint().await;
+            let profile = Profile {
+                keycloak_url,
+                ..Profile::default()
+            };
+            let mut keycloak = Keycloak::new(&profile);
+
+            let login = keycloak.browser_login().await.unwrap();
+            let params: HashMap<String, String> = login.url.query_pairs().into_owned().collect();
+            assert_eq!(params["code_challenge_method"], "S256");
+            let redirect_uri = login.redirect_uri.clone();
+            let state = params["state"].clone();
+            let finished = tokio::spawn(login.finish());
+
+            // Other pages and answers of an older login are ignored and the login keeps waiting
+            let client = reqwest::Client::new();
+            let favicon = client.get(redirect_uri.replace(CALLBACK_PATH, "/favicon.ico")).send().await.unwrap();
+            assert_eq!(favicon.status(), 404);
+            let stale = client.get(format!("{}?state=old&code=stale", redirect_uri)).send().await.unwrap();
+            assert_eq!(stale.status(), 400);
+            let answer = client.get(format!("{}?state={}&code=abc", redirect_uri, state)).send().await.unwrap();
+            assert_eq!(answer.status(), 200);
+
+            let tokens = finished.await.unwrap().unwrap();
+            keycloak.complete_login(tokens).unwrap();
+            assert_eq!(keycloak.session().username, "anna");
+            assert_eq!(keycloak.session().roles, vec![Role::Warehouse]);
+            assert!(keycloak.refresh_token().await.is_ok());
+
+            let token_request = token_requests.lock().unwrap()[0].clone();
+            assert_eq!(token_request.method, "POST");
+            assert_eq!(token_request.path, "/realms/WMS/protocol/openid-connect/token");
+            let form = reqwest::Url::parse(&format!("http://form/?{}", token_request.body)).unwrap();
+            let form: HashMap<String, String> = form.query_pairs().into_owned().collect();
+            assert_eq!(form["grant_type"], "authorization_code");
+            assert_eq!(form["code"], "abc");
+            assert_eq!(form["redirect_uri"], redirect_uri);
+            assert_eq!(form["client_id"], profile.client_id);
+            assert_eq!(code_challenge(&form["code_verifier"]), params["code_challenge"]);
+            assert!(!form.contains_key("client_secret"));
+        });
+    }
+
+    #[test]
+    fn browser_login_reports_the_error_of_keycloak() {
+        block_on(async {
+            let keycloak = Keycloak::new(&Profile::default());
+            let login = keycloak.browser_login().await.unwrap();
+            let callback = format!("{}?state={}&error=access_denied", login.redirect_uri, login.state);
+            let finished = tokio::spawn(login.finish());
+
+            reqwest::get(callback).await.unwrap();
+            assert!(matches!(finished.await.unwrap(), Err(Error::Unauthorized(_))));
+        });
+    }
+
+    #[test]
+    fn a_connection_that_sends_nothing_does_not_hold_up_the_callback() {
+        block_on(async {
+            let keycloak = Keycloak::new(&Profile::default());
+            let login = keycloak.browser_login().await.unwrap();
+            let callback = format!("{}?state={}&error=access_denied", login.redirect_uri, login.state);
+            let address = reqwest::Url::parse(&login.redirect_uri).unwrap().socket_addrs(|| None).unwrap()[0];
+            let finished = tokio::spawn(login.finish());
+
+            // Opened in advance like a browser does and never used
+            let _idle = TcpStream::connect(address).await.unwrap();
+            reqwest::get(callback).await.unwrap();
+            let result = tokio::time::timeout(REQUEST_TIMEOUT * 3, finished).await;
+            assert!(matches!(result, Ok(Ok(Err(Error::Unauthorized(_))))));
+        });
+    }
+
+    #[test]
+    fn an_expired_token_is_not_handed_out_without_keycloak() {
+        block_on(async {
+            // A port nobody listens on any more
+            let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+            let profile = Profile {
+                keycloak_url: format!("http://{}", listener.local_addr().unwrap()),
+                ..Profile::default()
+            };
+            drop(listener);
+            let mut keycloak = Keycloak::new(&profile);
+            keycloak.token = "access".to_string();
+            keycloak.refresh_expires_at = now() + 600;
+
+            // Still valid for a moment, so it can be used until keycloak is back
+            keycloak.token_expires_at = now() + EXPIRY_MARGIN / 2;
+            assert_eq!(keycloak.refresh_token().await.unwrap(), "access");
+
+            keycloak.token_expires_at = now() - 1;
+            assert!(matches!(keycloak.refresh_token().await, Err(Error::Network(_))));
+            // The session is kept for when keycloak answers again
+            assert_eq!(keycloak.token, "access");
+        });
+    }
+
+    #[test]
+    fn tokens_of_another_profile_are_not_taken_over() {
+        let mut keycloak = Keycloak::new(&Profile::default());
+        let local = Profile {
+            keycloak_url: "http://localhost:8480".to_string(),
+            ..Profile::default()
+        };
+        assert!(keycloak.complete_login(Keycloak::new(&local)).is_err());
     }
 }
@@ -0,0 +1,76 @@
+/*
+
+    Author: Justin
+    Description: This file contains a HTTP server on the loopback interface for the tests of the services. It answers
+                 every request with what the test hands it and remembers the requests it got.
+
+*/
+
+// Import the necessary modules
+use std::sync::{Arc, Mutex};
+
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+// A request the server got
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub body: String,
+}
+
+pub type Requests = Arc<Mutex<Vec<Request>>>;
+
+// Start the server, `answer` returns the status and the body for every request
+pub async fn serve(answer: impl Fn(&Request) -> (u16, String) + Send + 'static) -> (String, Requests) {
+    let listener = TcpListener::bind(("127.0.0.1", 0)).await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    let requests: Requests = Arc::default();
+    let recorded = requests.clone();
+    tokio::spawn(async move {
+        // Every answer closes the connection, so there is one request per connection
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let request = read_request(&mut stream).await;
+            let (status, body) = answer(&request);
+            recorded.lock().unwrap().push(request);
+
+            let response = format!(
+                "HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            let _ = stream.write_all(response.as_bytes()).await;
+            let _ = stream.shutdown().await;
+        }
+    });
+    (url, requests)
+}
+
+// Read the header and as much of the body as it announces
+async fn read_request(stream: &mut TcpStream) -> Request {
+    let mut data = Vec::new();
+    let mut buffer = [0; 4096];
+    let (head, body) = loop {
+        let length = stream.read(&mut buffer).await.unwrap();
+        data.extend_from_slice(&buffer[..length]);
+        let text = String::from_utf8_lossy(&data).to_string();
+        if let Some((head, body)) = text.split_once("\r\n\r\n") {
+            let content_length = head
+                .lines()
+                .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(str::to_string))
+                .and_then(|value| value.trim().parse::<usize>().ok())
+                .unwrap_or_default();
+            if body.len() >= content_length || length == 0 {
+                break (head.to_string(), body.to_string());
+            }
+        }
+    };
+    let mut request_line = head.lines().next().unwrap_or_default().split_whitespace();
+    Request {
+        method: request_line.next().unwrap_or_default().to_string(),
+        path: request_line.next().unwrap_or_default().to_string(),
+        body,
+    }
+}
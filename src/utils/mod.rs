@@ -1,2 +1,13 @@
 pub mod workshop_service;
-pub mod keycloak_service;
\ No newline at end of file
+pub mod keycloak_service;
+pub mod config;
+pub mod runtime;
+pub mod repository;
+pub mod validation;
+pub mod filter;
+pub mod view_models;
+pub mod cache;
+pub mod reorder;
+pub mod schedule;
+#[cfg(test)]
+pub mod mock_http;
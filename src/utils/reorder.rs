@@ -0,0 +1,314 @@
+/*
+
+    Author: Justin
+    Description: This file contains the reorder suggestions. Every material whose stock minus the open demand of the
+                 active projects is below its threshold is proposed for an order at its preferred supplier, with an
+                 amount that brings the stock back to the threshold after the demand is taken. What is already ordered
+                 and did not arrive yet counts as stock.
+
+*/
+
+// Import the necessary modules
+use std::collections::HashMap;
+
+use crate::models::material::Material;
+use crate::models::money::Money;
+use crate::models::project::Project;
+use crate::models::project_material::ProjectMaterial;
+use crate::models::purchase_order::PurchaseOrder;
+use crate::models::supplier::{Supplier, SupplierMaterial};
+
+// Everything the suggestions are computed from
+pub struct ReorderData {
+    pub materials: Vec<Material>,
+    pub projects: Vec<Project>,
+    pub project_materials: Vec<ProjectMaterial>,
+    pub suppliers: Vec<Supplier>,
+    pub supplier_materials: Vec<SupplierMaterial>,
+    pub purchase_orders: Vec<PurchaseOrder>,
+}
+
+pub struct ReorderLine {
+    pub material: Material,
+    // The offer of the supplier, None if the material has no supplier
+    pub offer: Option<SupplierMaterial>,
+    // Reserved by active projects but not taken yet
+    pub demand: i32,
+    // Ordered but not delivered yet
+    pub on_order: i32,
+    pub quantity: i32,
+}
+
+impl ReorderLine {
+    // The price of the supplier or the current costs of the material if there is no offer
+    pub fn unit_price(&self) -> Money {
+        self.offer.as_ref().map(|o| o.price).unwrap_or(self.material.costs)
+    }
+
+    pub fn total(&self) -> Money {
+        (self.unit_price() * self.quantity).round_to_cents()
+    }
+}
+
+pub struct ReorderGroup {
+    // None for the materials that have no supplier yet
+    pub supplier: Option<Supplier>,
+    pub lines: Vec<ReorderLine>,
+}
+
+impl ReorderGroup {
+    pub fn total(&self) -> Money {
+        self.lines.iter().map(ReorderLine::total).sum()
+    }
+}
+
+// The reserved amounts of the active projects, keyed by the id of the material
+pub fn open_demand(projects: &[Project], project_materials: &[ProjectMaterial]) -> HashMap<i32, i32> {
+    let active: Vec<i32> = projects
+        .iter()
+        .filter(|p| p.status.is_active())
+        .map(|p| p.id)
+        .collect();
+    let mut demand: HashMap<i32, i32> = HashMap::new();
+    for project_material in project_materials {
+        if project_material.reserved > 0 && active.contains(&project_material.project_id) {
+            *demand.entry(project_material.material_id).or_default() += project_material.reserved;
+        }
+    }
+    demand
+}
+
+// The offer a material is reordered from, the preferred one or else the cheapest
+fn best_offer(material_id: i32, offers: &[SupplierMaterial]) -> Option<SupplierMaterial> {
+    let offers: Vec<&SupplierMaterial> = offers.iter().filter(|o| o.material_id == material_id).collect();
+    offers
+        .iter()
+        .find(|o| o.preferred)
+        .or_else(|| offers.iter().min_by_key(|o| o.price))
+        .map(|o| (*o).clone())
+}
+
+// The suggestions grouped by supplier, sorted by the name of the supplier and the materials without one at the end
+pub fn suggestions(data: ReorderData) -> Vec<ReorderGroup> {
+    let demand = open_demand(&data.projects, &data.project_materials);
+    let suppliers: HashMap<i32, Supplier> = data.suppliers.into_iter().map(|s| (s.id, s)).collect();
+
+    let mut groups: HashMap<Option<i32>, ReorderGroup> = HashMap::new();
+    for material in data.materials {
+        let demand = demand.get(&material.id).copied().unwrap_or(0);
+        let on_order: i32 = data.purchase_orders.iter().map(|o| o.open_quantity(material.id)).sum();
+        // What is needed to have the threshold left after the demand is taken
+        let missing = material.threshold_value + demand - material.amount - on_order;
+        if missing <= 0 {
+            continue;
+        }
+        let offer = best_offer(material.id, &data.supplier_materials);
+        // A link to a supplier that was deleted counts as no supplier
+        let supplier = offer.as_ref().and_then(|o| suppliers.get(&o.supplier_id)).cloned();
+        let minimum = supplier.as_ref().map(|s| s.minimum_order_quantity).unwrap_or(0);
+
+        groups
+            .entry(supplier.as_ref().map(|s| s.id))
+            .or_insert_with(|| ReorderGroup {
+                supplier,
+                lines: Vec::new(),
+            })
+            .lines
+            .push(ReorderLine {
+                material,
+                offer,
+                demand,
+                on_order,
+                quantity: missing.max(minimum),
+            });
+    }
+
+    let mut groups: Vec<ReorderGroup> = groups.into_values().collect();
+    for group in groups.iter_mut() {
+        group.lines.sort_by_key(|l| (l.material.name.to_lowercase(), l.material.id));
+    }
+    groups.sort_by_key(|g| match &g.supplier {
+        Some(supplier) => (false, supplier.name.to_lowercase(), supplier.id),
+        None => (true, String::new(), 0),
+    });
+    groups
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::NaiveDate;
+
+    use crate::models::project::ProjectStatus;
+    use crate::models::purchase_order::{OrderLine, OrderStatus};
+
+    fn material(id: i32, name: &str, amount: i32, threshold_value: i32) -> Material {
+        Material {
+            id,
+            name: name.to_string(),
+            description: String::new(),
+            type_id: 1,
+            amount,
+            costs: Money::from_cents(150),
+            threshold_value,
+        }
+    }
+
+    fn project(id: i32, status: ProjectStatus) -> Project {
+        Project {
+            id,
+            name: format!("Project {}", id),
+            client_id: 1,
+            description: String::new(),
+            startpoint: None,
+            endpoint: None,
+            estimated_costs: Money::ZERO,
+            estimated_hours: 0.0,
+            costs: Money::ZERO,
+            status,
+        }
+    }
+
+    fn reservation(project_id: i32, material_id: i32, reserved: i32) -> ProjectMaterial {
+        ProjectMaterial {
+            id: project_id * 100 + material_id,
+            project_id,
+            material_id,
+            amount: 0,
+            reserved,
+        }
+    }
+
+    fn supplier(id: i32, name: &str, minimum_order_quantity: i32) -> Supplier {
+        Supplier {
+            id,
+            name: name.to_string(),
+            contact: String::new(),
+            lead_time_days: 3,
+            minimum_order_quantity,
+        }
+    }
+
+    fn offer(supplier_id: i32, material_id: i32, cents: i64, preferred: bool) -> SupplierMaterial {
+        SupplierMaterial {
+            id: supplier_id * 100 + material_id,
+            supplier_id,
+            material_id,
+            article_number: String::new(),
+            price: Money::from_cents(cents),
+            preferred,
+        }
+    }
+
+    fn order(status: OrderStatus, material_id: i32, quantity: i32) -> PurchaseOrder {
+        PurchaseOrder {
+            id: 1,
+            supplier_id: 1,
+            status,
+            order_date: None,
+            expected_date: NaiveDate::from_ymd_opt(2024, 4, 20).unwrap(),
+            user: "anna".to_string(),
+            lines: vec![OrderLine {
+                material_id,
+                quantity,
+                received: 0,
+                unit_price: Money::from_cents(100),
+            }],
+        }
+    }
+
+    fn reorder_data(materials: Vec<Material>) -> ReorderData {
+        ReorderData {
+            materials,
+            projects: Vec::new(),
+            project_materials: Vec::new(),
+            suppliers: Vec::new(),
+            supplier_materials: Vec::new(),
+            purchase_orders: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn only_active_projects_reserve_material() {
+        let projects = [
+            project(1, ProjectStatus::InProgress),
+            project(2, ProjectStatus::Inquiry),
+            project(3, ProjectStatus::Finished),
+            project(4, ProjectStatus::OnHold),
+        ];
+        let project_materials = [
+            reservation(1, 10, 3),
+            reservation(2, 10, 5),
+            reservation(3, 10, 7),
+            reservation(4, 10, 2),
+            reservation(1, 11, 0),
+        ];
+        let demand = open_demand(&projects, &project_materials);
+        assert_eq!(demand, HashMap::from([(10, 5)]));
+    }
+
+    #[test]
+    fn the_quantity_covers_threshold_demand_and_the_minimum_order() {
+        let mut data = reorder_data(vec![material(10, "Screws", 8, 10), material(11, "Glue", 20, 10)]);
+        data.projects = vec![project(1, ProjectStatus::InProgress)];
+        data.project_materials = vec![reservation(1, 10, 5)];
+        // Only the sent order is on the way, the draft is not
+        data.purchase_orders = vec![order(OrderStatus::Sent, 10, 3), order(OrderStatus::Draft, 10, 4)];
+
+        let groups = suggestions(data);
+        assert_eq!(groups.len(), 1);
+        let line = &groups[0].lines[0];
+        assert_eq!((line.material.id, line.demand, line.on_order, line.quantity), (10, 5, 3, 4));
+
+        let mut data = reorder_data(vec![material(10, "Screws", 8, 10)]);
+        data.suppliers = vec![supplier(1, "Hardware", 6)];
+        data.supplier_materials = vec![offer(1, 10, 20, true)];
+        assert_eq!(suggestions(data)[0].lines[0].quantity, 6);
+    }
+
+    #[test]
+    fn groups_by_supplier_with_the_materials_without_one_at_the_end() {
+        let mut data = reorder_data(vec![
+            material(10, "Screws", 0, 10),
+            material(11, "Nails", 0, 10),
+            material(12, "Glue", 0, 10),
+            material(13, "Wood", 0, 10),
+            material(14, "Paint", 0, 10),
+        ]);
+        data.suppliers = vec![supplier(1, "Zinc & Co", 0), supplier(2, "alpine tools", 0)];
+        data.supplier_materials = vec![
+            // The preferred offer wins over a cheaper one
+            offer(1, 10, 50, true),
+            offer(2, 10, 10, false),
+            // Without a preferred offer the cheapest wins
+            offer(1, 11, 50, false),
+            offer(2, 11, 10, false),
+            offer(1, 12, 10, false),
+            // The supplier was deleted
+            offer(3, 13, 10, true),
+        ];
+
+        let groups = suggestions(data);
+        let layout: Vec<(Option<i32>, Vec<i32>)> = groups
+            .iter()
+            .map(|g| (g.supplier.as_ref().map(|s| s.id), g.lines.iter().map(|l| l.material.id).collect()))
+            .collect();
+        assert_eq!(layout, vec![(Some(2), vec![11]), (Some(1), vec![12, 10]), (None, vec![14, 13])]);
+    }
+
+    #[test]
+    fn totals_are_rounded_to_cents() {
+        let mut data = reorder_data(vec![material(10, "Screws", 0, 3), material(11, "Nails", 0, 1)]);
+        data.suppliers = vec![supplier(1, "Hardware", 0)];
+        let price = Money::from_f64(0.0335).unwrap();
+        data.supplier_materials = vec![SupplierMaterial { price, ..offer(1, 10, 0, true) }];
+
+        let groups = suggestions(data);
+        // 3 × 0.0335 = 0.1005
+        assert_eq!(groups[0].lines[0].total(), Money::from_cents(10));
+        // Without an offer the costs of the material are used
+        assert_eq!(groups[1].lines[0].unit_price(), Money::from_cents(150));
+        assert_eq!(groups[0].total() + groups[1].total(), Money::from_cents(160));
+    }
+}
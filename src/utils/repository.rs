@@ -0,0 +1,155 @@
+/*
+
+    Author: Justin
+    Description: This file contains the generic typed client for one collection of the API. Every model that implements
+                 Resource gets the full CRUD surface through it.
+
+*/
+
+use std::marker::PhantomData;
+
+use reqwest::header::{HeaderMap, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED};
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
+
+use crate::models::error::Error;
+use crate::models::resource::Resource;
+
+/*
+    For a resource with the path `{PATH}` the following endpoints are used:
+
+    - `GET /{PATH}` - get all items, with `If-None-Match`/`If-Modified-Since` if the server sent an ETag or Last-Modified
+    - `GET /{PATH}/{id}` - get item by id
+    - `POST /{PATH}` - create item
+    - `PUT /{PATH}/{id}` - update item
+    - `DELETE /{PATH}/{id}` - delete item
+*/
+
+// What the server said about the version of a collection, sent back to ask if it changed since
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Validator {
+    pub etag: Option<String>,
+    pub last_modified: Option<String>,
+}
+
+impl Validator {
+    fn from_headers(headers: &HeaderMap) -> Validator {
+        let header = |name| headers.get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+        Validator {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        }
+    }
+}
+
+pub enum Fetched<T> {
+    // The items and the version of the collection, an empty validator if the server does not support one
+    Changed(Vec<T>, Validator),
+    NotModified,
+}
+
+pub struct Repository<T: Resource> {
+    client: reqwest::Client,
+    url: String,
+    resource: PhantomData<T>,
+}
+
+impl<T: Resource> Repository<T> {
+    pub fn new(client: reqwest::Client, api_url: &str) -> Repository<T> {
+        Repository {
+            client,
+            url: format!("{}/{}", api_url.trim_end_matches('/'), T::PATH),
+            resource: PhantomData,
+        }
+    }
+
+    pub async fn get_all(&self, token: &str) -> Result<Vec<T>, Error> {
+        let response = self.client.get(&self.url).bearer_auth(token).send().await?;
+
+        parse_json(response).await
+    }
+
+    // Get all items unless the collection is still the version of the validator
+    pub async fn get_all_if_changed(&self, token: &str, validator: &Validator) -> Result<Fetched<T>, Error> {
+        let mut request = self.client.get(&self.url).bearer_auth(token);
+        if let Some(etag) = &validator.etag {
+            request = request.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = &validator.last_modified {
+            request = request.header(IF_MODIFIED_SINCE, last_modified);
+        }
+        let response = request.send().await?;
+        if response.status() == StatusCode::NOT_MODIFIED {
+            return Ok(Fetched::NotModified);
+        }
+
+        let validator = Validator::from_headers(response.headers());
+        Ok(Fetched::Changed(parse_json(response).await?, validator))
+    }
+
+    pub async fn get_by_id(&self, id: i32, token: &str) -> Result<T, Error> {
+        let response = self
+            .client
+            .get(format!("{}/{}", self.url, id))
+            .bearer_auth(token)
+            .send()
+            .await?;
+
+        parse_json(response).await
+    }
+
+    // The API answers with the created item, which carries the id the database chose
+    pub async fn create(&self, item: &T, token: &str) -> Result<T, Error> {
+        let response = self
+            .client
+            .post(&self.url)
+            .bearer_auth(token)
+            .json(item)
+            .send()
+            .await?;
+
+        parse_json(response).await
+    }
+
+    pub async fn update(&self, item: &T, token: &str) -> Result<(), Error> {
+        let response = self
+            .client
+            .put(format!("{}/{}", self.url, item.id()))
+            .bearer_auth(token)
+            .json(item)
+            .send()
+            .await?;
+
+        check_status(response).await?;
+        Ok(())
+    }
+
+    pub async fn delete(&self, id: i32, token: &str) -> Result<(), Error> {
+        let response = self
+            .client
+            .delete(format!("{}/{}", self.url, id))
+            .bearer_auth(token)
+            .send()
+            .await?;
+
+        check_status(response).await?;
+        Ok(())
+    }
+}
+
+// Turn an unsuccessful response into our own error, keeping the status and the body of the server
+async fn check_status(response: reqwest::Response) -> Result<reqwest::Response, Error> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    // If even the body can not be read we still want to report the status
+    let body = response.text().await.unwrap_or_default();
+    Err(Error::from_status(status.as_u16(), body))
+}
+
+// Parse the body of a successful response
+async fn parse_json<T: DeserializeOwned>(response: reqwest::Response) -> Result<T, Error> {
+    let response = check_status(response).await?;
+    Ok(response.json().await?)
+}
@@ -0,0 +1,51 @@
+/*
+
+    Author: Justin
+    Description: This file contains the one tokio runtime of the client and the helpers to move work from the UI thread
+                 onto it and the results back to the UI thread.
+
+*/
+
+// Import the necessary modules
+use std::future::Future;
+use std::sync::LazyLock;
+
+use tokio::runtime::{Builder, Runtime};
+
+// The runtime is created on first use and lives as long as the application
+static RUNTIME: LazyLock<Runtime> = LazyLock::new(|| {
+    Builder::new_multi_thread()
+        .enable_all()
+        .thread_name("wms-worker")
+        .build()
+        .expect("Failed to start the tokio runtime")
+});
+
+// Run a future on the runtime and wait for it without blocking the calling executor
+pub async fn run<F>(future: F) -> F::Output
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    match RUNTIME.spawn(future).await {
+        Ok(output) => output,
+        // The task can only fail if it panicked, so we pass the panic on
+        Err(e) => std::panic::resume_unwind(e.into_panic()),
+    }
+}
+
+// Run the work in the background and hand the result to the callback on the UI thread
+pub fn spawn<F, C>(work: F, callback: C)
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+    C: FnOnce(F::Output) + 'static,
+{
+    let result = slint::spawn_local(async move {
+        callback(run(work).await);
+    });
+    // This only fails if the event loop is not running, in which case there is no UI to update anyway
+    if let Err(e) = result {
+        eprintln!("Failed to spawn a task on the event loop: {}", e);
+    }
+}
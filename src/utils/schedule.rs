@@ -0,0 +1,218 @@
+/*
+
+    Author: Justin
+    Description: This file contains the checks of the project schedule. A deadline collides with the projects of the same
+                 client that are running on that day, and the estimated hours of every open project are spread evenly
+                 over its workdays so the load of each week can be compared with the capacity of the workshop.
+
+*/
+
+// Import the necessary modules
+use chrono::{Duration, NaiveDate};
+
+use crate::models::date;
+use crate::models::project::Project;
+
+// The planned hours of the workshop in one week
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WeekLoad {
+    // The Monday of the week
+    pub week: NaiveDate,
+    pub hours: f32,
+}
+
+// The open projects of the same client that are running on the deadline of the project
+pub fn client_collisions<'a>(project: &Project, others: &'a [Project]) -> Vec<&'a Project> {
+    let Some(deadline) = project.endpoint else {
+        return Vec::new();
+    };
+    others
+        .iter()
+        .filter(|o| o.id != project.id && o.client_id == project.client_id && !o.status.is_closed())
+        .filter(|o| o.schedule().is_some_and(|(start, end)| start <= deadline && deadline <= end))
+        .collect()
+}
+
+// The first week of the project in which the open projects need more than `capacity` hours
+pub fn overloaded_week(project: &Project, others: &[Project], capacity: f32) -> Option<WeekLoad> {
+    let (start, deadline) = project.schedule()?;
+    // A schedule that `Project::check_schedule` refuses is not checked week by week
+    Project::check_schedule(start, deadline).ok()?;
+
+    // Every project is planned once, each week then only needs a sum
+    let planned: Vec<Plan> = others
+        .iter()
+        .filter(|o| o.id != project.id && !o.status.is_closed())
+        .chain(std::iter::once(project))
+        .filter_map(Plan::of)
+        .collect();
+
+    let mut week = date::week_start(start);
+    while week <= deadline {
+        let hours: f32 = planned.iter().map(|p| p.hours_in_week(week)).sum();
+        if hours > capacity {
+            return Some(WeekLoad { week, hours });
+        }
+        week += Duration::weeks(1);
+    }
+    None
+}
+
+// The estimated hours of a project spread evenly over its workdays
+struct Plan {
+    start: NaiveDate,
+    deadline: NaiveDate,
+    // A project that only runs on a weekend is worked on the weekend
+    weekend_only: bool,
+    days: i64,
+    hours: f32,
+}
+
+impl Plan {
+    // A project without a schedule is not planned
+    fn of(project: &Project) -> Option<Plan> {
+        let (start, deadline) = project.schedule()?;
+        if deadline < start {
+            return None;
+        }
+        let workdays = workdays_between(start, deadline);
+        let weekend_only = workdays == 0;
+        let days = match weekend_only {
+            true => (deadline - start).num_days() + 1,
+            false => workdays,
+        };
+        Some(Plan { start, deadline, weekend_only, days, hours: project.estimated_hours })
+    }
+
+    // The part of the hours that falls into the week starting on `week`
+    fn hours_in_week(&self, week: NaiveDate) -> f32 {
+        let from = self.start.max(week);
+        let to = self.deadline.min(week + Duration::days(6));
+        if to < from {
+            return 0.0;
+        }
+        let in_week = match self.weekend_only {
+            true => (to - from).num_days() + 1,
+            false => workdays_between(from, to),
+        };
+        self.hours * in_week as f32 / self.days as f32
+    }
+}
+
+// The days from Monday to Friday between both days, both included
+fn workdays_between(from: NaiveDate, to: NaiveDate) -> i64 {
+    if to < from {
+        return 0;
+    }
+    let days = (to - from).num_days() + 1;
+    // Every full week has five, the days after the last full week are looked at one by one
+    let rest = (0..days % 7).filter(|day| date::is_workday(from + Duration::days(*day))).count() as i64;
+    days / 7 * 5 + rest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::models::money::Money;
+    use crate::models::project::ProjectStatus;
+
+    fn day(month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap()
+    }
+
+    fn project(id: i32, start: NaiveDate, deadline: NaiveDate, hours: f32) -> Project {
+        Project {
+            id,
+            name: format!("Project {}", id),
+            client_id: 1,
+            description: String::new(),
+            startpoint: Some(start),
+            endpoint: Some(deadline),
+            estimated_costs: Money::ZERO,
+            estimated_hours: hours,
+            costs: Money::ZERO,
+            status: ProjectStatus::InProgress,
+        }
+    }
+
+    #[test]
+    fn counts_the_workdays_between_two_days() {
+        // Monday to Sunday
+        assert_eq!(workdays_between(day(4, 15), day(4, 21)), 5);
+        // Saturday to Sunday
+        assert_eq!(workdays_between(day(4, 20), day(4, 21)), 0);
+        // Wednesday to the Tuesday after next
+        assert_eq!(workdays_between(day(4, 17), day(4, 30)), 10);
+        assert_eq!(workdays_between(day(4, 18), day(4, 18)), 1);
+        assert_eq!(workdays_between(day(4, 18), day(4, 17)), 0);
+    }
+
+    #[test]
+    fn a_project_over_two_weeks_is_split_by_its_workdays() {
+        // Wednesday to Tuesday, three workdays in the first week and two in the second
+        let plan = Plan::of(&project(1, day(4, 17), day(4, 23), 50.0)).unwrap();
+        assert_eq!(plan.hours_in_week(day(4, 15)), 30.0);
+        assert_eq!(plan.hours_in_week(day(4, 22)), 20.0);
+        assert_eq!(plan.hours_in_week(day(4, 29)), 0.0);
+        assert_eq!(plan.hours_in_week(day(4, 8)), 0.0);
+    }
+
+    #[test]
+    fn a_project_on_a_weekend_is_worked_on_the_weekend() {
+        let plan = Plan::of(&project(1, day(4, 20), day(4, 21), 12.0)).unwrap();
+        assert_eq!(plan.hours_in_week(day(4, 15)), 12.0);
+
+        let weekend = project(1, day(4, 20), day(4, 21), 12.0);
+        let load = overloaded_week(&weekend, &[], 10.0).unwrap();
+        assert_eq!(load, WeekLoad { week: day(4, 15), hours: 12.0 });
+    }
+
+    #[test]
+    fn the_capacity_itself_is_not_an_overload() {
+        let planned = project(1, day(4, 15), day(4, 19), 20.0);
+        let others = [project(2, day(4, 15), day(4, 19), 20.0)];
+        assert_eq!(overloaded_week(&planned, &others, 40.0), None);
+        assert_eq!(overloaded_week(&planned, &others, 39.5), Some(WeekLoad { week: day(4, 15), hours: 40.0 }));
+
+        // A closed project and the saved version of the planned one do not count
+        let mut finished = project(3, day(4, 15), day(4, 19), 20.0);
+        finished.status = ProjectStatus::Finished;
+        let saved = project(1, day(4, 15), day(4, 19), 100.0);
+        assert_eq!(overloaded_week(&planned, &[finished, saved], 20.0), None);
+    }
+
+    #[test]
+    fn the_first_overloaded_week_is_reported() {
+        // Two weeks of 40 hours each, the other project only runs in the second week
+        let planned = project(1, day(4, 15), day(4, 26), 80.0);
+        let others = [project(2, day(4, 22), day(4, 26), 10.0)];
+        assert_eq!(overloaded_week(&planned, &others, 45.0), Some(WeekLoad { week: day(4, 22), hours: 50.0 }));
+    }
+
+    #[test]
+    fn an_absurd_deadline_is_not_checked() {
+        let far = NaiveDate::from_ymd_opt(9999, 12, 31).unwrap();
+        assert!(Project::check_schedule(day(4, 15), far).is_err());
+        assert_eq!(overloaded_week(&project(1, day(4, 15), far, 1e9), &[], 1.0), None);
+
+        // Another project that runs forever is still planned in the weeks of this one
+        let others = [project(2, day(1, 1), far, 1e9)];
+        assert!(overloaded_week(&project(1, day(4, 15), day(4, 19), 1.0), &others, 1.0).is_some());
+    }
+
+    #[test]
+    fn collides_with_the_projects_of_the_client_running_on_the_deadline() {
+        let planned = project(1, day(4, 15), day(4, 19), 8.0);
+        let mut other_client = project(3, day(4, 1), day(4, 30), 8.0);
+        other_client.client_id = 2;
+        let others = [
+            project(2, day(4, 1), day(4, 19), 8.0),
+            other_client,
+            project(4, day(4, 20), day(4, 30), 8.0),
+            Project { startpoint: None, ..project(5, day(4, 1), day(4, 30), 8.0) },
+        ];
+        let collisions: Vec<i32> = client_collisions(&planned, &others).iter().map(|p| p.id).collect();
+        assert_eq!(collisions, vec![2]);
+    }
+}
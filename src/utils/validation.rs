@@ -0,0 +1,97 @@
+/*
+
+    Author: Justin
+    Description: This file contains the checks for the input of the forms. Every check turns the text of a field into the
+                 value the API expects or returns a message that can be shown next to the field.
+
+*/
+
+// Import the necessary modules
+use std::collections::HashMap;
+
+use chrono::NaiveDate;
+
+use crate::models::date;
+use crate::models::money::Money;
+
+// The messages of all fields of a form that are invalid, keyed by the name of the field
+#[derive(Debug, Default)]
+pub struct FieldErrors {
+    errors: HashMap<&'static str, String>,
+}
+
+impl FieldErrors {
+    // Keep the value of a valid field or remember the message of an invalid one
+    pub fn check<T>(&mut self, field: &'static str, result: Result<T, String>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(message) => {
+                self.errors.insert(field, message);
+                None
+            }
+        }
+    }
+
+    pub fn add(&mut self, field: &'static str, message: &str) {
+        self.errors.insert(field, message.to_string());
+    }
+
+    // The message of the field or an empty string if the field is valid
+    pub fn get(&self, field: &str) -> String {
+        self.errors.get(field).cloned().unwrap_or_default()
+    }
+}
+
+// A text that must not be empty, surrounding whitespace is removed
+pub fn required(value: &str) -> Result<String, String> {
+    match value.trim() {
+        "" => Err("Please fill in this field".to_string()),
+        value => Ok(value.to_string()),
+    }
+}
+
+// A day in the format of the locale or of the API, e.g. `2024-04-18`
+pub fn date(value: &str) -> Result<NaiveDate, String> {
+    match date::parse_input(value) {
+        Some(day) => Ok(day),
+        None => Err(format!(
+            "Please enter a date like {} or 2024-04-18",
+            date::display(NaiveDate::from_ymd_opt(2024, 4, 18).unwrap_or_default())
+        )),
+    }
+}
+
+// An amount of money that is not negative, e.g. `1.234,56` or `1234.56`
+pub fn money(value: &str) -> Result<Money, String> {
+    match Money::parse(value) {
+        Some(amount) if !amount.is_negative() => Ok(amount),
+        _ => Err(format!("Please enter an amount like {}", Money::from_cents(1250).input_text())),
+    }
+}
+
+// A whole number that is not negative, e.g. an amount of pieces
+pub fn positive_integer(value: &str) -> Result<i32, String> {
+    match value.trim().parse::<i32>() {
+        Ok(number) if number >= 0 => Ok(number),
+        Ok(_) => Err("The number must not be negative".to_string()),
+        Err(_) => Err("Please enter a whole number".to_string()),
+    }
+}
+
+// A whole number that may be negative but not zero, e.g. a correction of a stock
+pub fn nonzero_integer(value: &str) -> Result<i32, String> {
+    match value.trim().parse::<i32>() {
+        Ok(0) => Err("The number must not be zero".to_string()),
+        Ok(number) => Ok(number),
+        Err(_) => Err("Please enter a whole number".to_string()),
+    }
+}
+
+// A number that is not negative, a comma is accepted as decimal separator
+pub fn positive_decimal(value: &str) -> Result<f32, String> {
+    match value.trim().replace(',', ".").parse::<f32>() {
+        Ok(number) if number.is_finite() && number >= 0.0 => Ok(number),
+        Ok(_) => Err("The number must not be negative".to_string()),
+        Err(_) => Err("Please enter a number".to_string()),
+    }
+}
@@ -0,0 +1,402 @@
+/*
+
+    Author: Justin
+    Description: This file contains the mapping from the models of the API to the models of the UI. The collections are
+                 indexed by id once and every view builds its projects and materials from that index. A reference to a
+                 record that does not exist (any more) becomes a placeholder instead of stopping the client.
+
+*/
+
+// Import the necessary modules
+use std::collections::HashMap;
+
+use chrono::{Datelike, NaiveDate};
+use slint::{ModelRc, VecModel};
+
+use crate::models::client::Client;
+use crate::models::date::{self, Urgency};
+use crate::models::material::Material as r_Material;
+use crate::models::money::Money;
+use crate::models::material_type::MaterialType as r_MaterialType;
+use crate::models::project::{Project, ProjectStatus};
+use crate::models::project_material::ProjectMaterial;
+use crate::utils::workshop_service::Collections;
+use crate::{CalendarDayModel, ClientModel, Material, MaterialType, MaterialTypeUsage, ProjectModel, ProjectStatusModel};
+
+// All records of the API indexed by their id
+#[derive(Default)]
+pub struct DomainGraph {
+    projects: HashMap<i32, Project>,
+    clients: HashMap<i32, Client>,
+    materials: HashMap<i32, r_Material>,
+    material_types: HashMap<i32, r_MaterialType>,
+    // The materials of every project, keyed by the id of the project
+    project_materials: HashMap<i32, Vec<ProjectMaterial>>,
+}
+
+impl DomainGraph {
+    // Collections that were not loaded can be left empty, e.g. `Collections { materials, ..Default::default() }`
+    pub fn new(collections: Collections) -> DomainGraph {
+        let mut project_materials: HashMap<i32, Vec<ProjectMaterial>> = HashMap::new();
+        for project_material in collections.project_materials {
+            project_materials
+                .entry(project_material.project_id)
+                .or_default()
+                .push(project_material);
+        }
+
+        DomainGraph {
+            projects: collections.projects.into_iter().map(|p| (p.id, p)).collect(),
+            clients: collections.clients.into_iter().map(|c| (c.id, c)).collect(),
+            materials: collections.materials.into_iter().map(|m| (m.id, m)).collect(),
+            material_types: collections.material_types.into_iter().map(|t| (t.id, t)).collect(),
+            project_materials,
+        }
+    }
+
+    // All projects, the next deadline first and those without a deadline last
+    pub fn projects(&self) -> Vec<ProjectModel> {
+        let mut projects: Vec<&Project> = self.projects.values().collect();
+        projects.sort_by_key(|p| (p.endpoint.is_none(), p.endpoint, p.id));
+        projects.into_iter().map(|p| self.project_model(p)).collect()
+    }
+
+    // The projects that are not finished, invoiced or cancelled, the next deadline first
+    pub fn open_projects(&self) -> Vec<ProjectModel> {
+        let mut projects: Vec<&Project> = self.projects.values().filter(|p| !p.status.is_closed()).collect();
+        projects.sort_by_key(|p| (p.endpoint.is_none(), p.endpoint, p.id));
+        projects.into_iter().map(|p| self.project_model(p)).collect()
+    }
+
+    pub fn project(&self, id: i32) -> Option<ProjectModel> {
+        self.projects.get(&id).map(|p| self.project_model(p))
+    }
+
+    // The estimated and the current costs of all projects of one client
+    pub fn client_costs(&self, client_id: i32) -> (Money, Money) {
+        self.projects
+            .values()
+            .filter(|p| p.client_id == client_id)
+            .fold((Money::ZERO, Money::ZERO), |(estimated, current), p| {
+                (estimated + p.estimated_costs, current + p.costs)
+            })
+    }
+
+    // The projects of one client, the next deadline first
+    pub fn client_projects(&self, client_id: i32) -> Vec<ProjectModel> {
+        self.projects()
+            .into_iter()
+            .filter(|p| p.client.id == client_id)
+            .collect()
+    }
+
+    // All clients, sorted by last name and first name
+    pub fn clients(&self) -> Vec<ClientModel> {
+        let mut clients: Vec<&Client> = self.clients.values().collect();
+        clients.sort_by(|a, b| (&a.lastname, &a.firstname, a.id).cmp(&(&b.lastname, &b.firstname, b.id)));
+        clients.into_iter().map(|c| self.client(c.id)).collect()
+    }
+
+    pub fn has_client(&self, id: i32) -> bool {
+        self.clients.contains_key(&id)
+    }
+
+    // The client with the id or a placeholder that names the missing id
+    pub fn client(&self, id: i32) -> ClientModel {
+        match self.clients.get(&id) {
+            Some(client) => ClientModel {
+                id: client.id,
+                firstName: client.firstname.clone().into(),
+                lastName: client.lastname.clone().into(),
+                phone: client.phone.clone().into(),
+            },
+            None => ClientModel {
+                id,
+                firstName: "".into(),
+                lastName: format!("Unknown client #{}", id).into(),
+                phone: "".into(),
+            },
+        }
+    }
+
+    // The material type with the id or a placeholder that names the missing id
+    pub fn material_type(&self, id: i32) -> MaterialType {
+        match self.material_types.get(&id) {
+            Some(m_type) => MaterialType {
+                id: m_type.id,
+                name: m_type.name.clone().into(),
+                description: m_type.description.clone().into(),
+            },
+            None => MaterialType {
+                id,
+                name: format!("Unknown type #{}", id).into(),
+                description: "".into(),
+            },
+        }
+    }
+
+    // All material types with the number of materials that use them, sorted by name
+    pub fn material_type_usages(&self) -> Vec<MaterialTypeUsage> {
+        let mut material_types: Vec<&r_MaterialType> = self.material_types.values().collect();
+        material_types.sort_by_key(|t| (t.name.to_lowercase(), t.id));
+        material_types
+            .into_iter()
+            .map(|t| MaterialTypeUsage {
+                m_type: self.material_type(t.id),
+                material_count: self.materials.values().filter(|m| m.type_id == t.id).count() as i32,
+            })
+            .collect()
+    }
+
+    // All materials of the warehouse with the amount in stock, sorted by name
+    pub fn materials(&self) -> Vec<Material> {
+        let mut materials: Vec<Material> = self
+            .materials
+            .values()
+            .map(|m| self.material_model(m, m.amount))
+            .collect();
+        materials.sort_by_key(|m| (m.name.to_lowercase(), m.id));
+        materials
+    }
+
+    // The materials of the warehouse whose stock is below their threshold
+    pub fn material_alerts(&self) -> Vec<Material> {
+        self.materials()
+            .into_iter()
+            .filter(|m| m.quantity < m.threshold_value)
+            .collect()
+    }
+
+    // A project with its client and its materials, the quantity of a material is the amount used in the project
+    fn project_model(&self, project: &Project) -> ProjectModel {
+        let materials: Vec<Material> = self
+            .project_materials
+            .get(&project.id)
+            .map(|project_materials| {
+                project_materials
+                    .iter()
+                    .map(|pm| match self.materials.get(&pm.material_id) {
+                        Some(material) => self.material_model(material, pm.amount),
+                        None => Material {
+                            id: pm.material_id,
+                            name: format!("Unknown material #{}", pm.material_id).into(),
+                            description: "".into(),
+                            m_type: MaterialType::default(),
+                            quantity: pm.amount,
+                            price: "".into(),
+                            threshold_value: 0,
+                        },
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let urgency = project.urgency(date::today());
+        ProjectModel {
+            id: project.id,
+            name: project.name.clone().into(),
+            client: self.client(project.client_id),
+            mats: ModelRc::new(VecModel::from(materials)),
+            calculated_costs: project.estimated_costs.to_string().into(),
+            current_costs: project.costs.to_string().into(),
+            estimated_hours: project.estimated_hours,
+            project_deadline: project.endpoint.map(date::display).unwrap_or_default().into(),
+            project_start: project.startpoint.map(date::display).unwrap_or_default().into(),
+            overdue: urgency == Some(Urgency::Overdue),
+            due_this_week: urgency == Some(Urgency::DueThisWeek),
+            status: status_model(project.status),
+            next_statuses: ModelRc::new(VecModel::from(
+                project.status.next().iter().map(|s| status_model(*s)).collect::<Vec<_>>(),
+            )),
+        }
+    }
+
+    fn material_model(&self, material: &r_Material, quantity: i32) -> Material {
+        Material {
+            id: material.id,
+            name: material.name.clone().into(),
+            description: material.description.clone().into(),
+            m_type: self.material_type(material.type_id),
+            quantity,
+            price: material.costs.to_string().into(),
+            threshold_value: material.threshold_value,
+        }
+    }
+}
+
+pub fn status_model(status: ProjectStatus) -> ProjectStatusModel {
+    ProjectStatusModel {
+        index: status.index() as i32,
+        label: status.label().into(),
+    }
+}
+
+// The six weeks of the date picker that show the month of `month`
+pub fn calendar_days(month: NaiveDate, selected: Option<NaiveDate>, today: NaiveDate) -> Vec<CalendarDayModel> {
+    date::month_grid(month)
+        .into_iter()
+        .map(|day| CalendarDayModel {
+            day: day.day() as i32,
+            date: date::display(day).into(),
+            in_month: day.month() == month.month(),
+            today: day == today,
+            selected: Some(day) == selected,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use slint::Model;
+
+    fn day(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 4, day).unwrap()
+    }
+
+    fn project(id: i32, client_id: i32, deadline: NaiveDate, status: ProjectStatus) -> Project {
+        Project {
+            id,
+            name: format!("Project {}", id),
+            client_id,
+            description: String::new(),
+            startpoint: Some(day(1)),
+            endpoint: Some(deadline),
+            estimated_costs: Money::from_cents(10_000),
+            estimated_hours: 8.0,
+            costs: Money::from_cents(2_550),
+            status,
+        }
+    }
+
+    fn client(id: i32, firstname: &str, lastname: &str) -> Client {
+        Client {
+            id,
+            firstname: firstname.to_string(),
+            lastname: lastname.to_string(),
+            phone: String::new(),
+        }
+    }
+
+    fn material(id: i32, name: &str, type_id: i32, amount: i32, threshold_value: i32) -> r_Material {
+        r_Material {
+            id,
+            name: name.to_string(),
+            description: String::new(),
+            type_id,
+            amount,
+            costs: Money::from_cents(150),
+            threshold_value,
+        }
+    }
+
+    fn project_material(id: i32, project_id: i32, material_id: i32, amount: i32) -> ProjectMaterial {
+        ProjectMaterial {
+            id,
+            project_id,
+            material_id,
+            amount,
+            reserved: 0,
+        }
+    }
+
+    fn graph() -> DomainGraph {
+        DomainGraph::new(Collections {
+            projects: vec![
+                project(1, 10, day(20), ProjectStatus::InProgress),
+                project(2, 10, day(12), ProjectStatus::Finished),
+                // The client was deleted
+                project(3, 99, day(15), ProjectStatus::Accepted),
+            ],
+            clients: vec![client(10, "Anna", "Zeller"), client(11, "Bernd", "Abel")],
+            materials: vec![material(100, "screws", 1, 500, 100), material(101, "Boards", 7, 3, 10)],
+            material_types: vec![r_MaterialType {
+                id: 1,
+                name: "Hardware".to_string(),
+                description: String::new(),
+            }],
+            project_materials: vec![project_material(1000, 1, 100, 40), project_material(1001, 1, 555, 2)],
+        })
+    }
+
+    #[test]
+    fn projects_resolve_their_client_and_materials() {
+        let project = graph().project(1).unwrap();
+        assert_eq!(project.client.lastName, "Zeller");
+        assert_eq!(project.status.label, "In progress");
+
+        let materials: Vec<Material> = project.mats.iter().collect();
+        assert_eq!(materials.len(), 2);
+        // The quantity of a material is the amount the project uses, not the stock
+        assert_eq!(materials[0].name, "screws");
+        assert_eq!(materials[0].quantity, 40);
+        assert_eq!(materials[0].m_type.name, "Hardware");
+    }
+
+    #[test]
+    fn dangling_references_become_placeholders() {
+        let graph = graph();
+        let project = graph.project(3).unwrap();
+        assert_eq!(project.client.id, 99);
+        assert_eq!(project.client.lastName, "Unknown client #99");
+
+        let materials: Vec<Material> = graph.project(1).unwrap().mats.iter().collect();
+        assert_eq!(materials[1].id, 555);
+        assert_eq!(materials[1].name, "Unknown material #555");
+        assert_eq!(materials[1].quantity, 2);
+
+        let boards = graph.materials().into_iter().find(|m| m.id == 101).unwrap();
+        assert_eq!(boards.m_type.id, 7);
+        assert_eq!(boards.m_type.name, "Unknown type #7");
+        assert!(graph.project(42).is_none());
+    }
+
+    #[test]
+    fn lists_are_sorted() {
+        let graph = graph();
+        let ids: Vec<i32> = graph.projects().iter().map(|p| p.id).collect();
+        assert_eq!(ids, vec![2, 3, 1]);
+        let open: Vec<i32> = graph.open_projects().iter().map(|p| p.id).collect();
+        assert_eq!(open, vec![3, 1]);
+
+        let clients: Vec<String> = graph.clients().iter().map(|c| c.lastName.to_string()).collect();
+        assert_eq!(clients, vec!["Abel", "Zeller"]);
+        // Sorted by name without regard to case
+        let materials: Vec<String> = graph.materials().iter().map(|m| m.name.to_string()).collect();
+        assert_eq!(materials, vec!["Boards", "screws"]);
+    }
+
+    #[test]
+    fn clients_with_their_projects_and_costs() {
+        let graph = graph();
+        let projects: Vec<i32> = graph.client_projects(10).iter().map(|p| p.id).collect();
+        assert_eq!(projects, vec![2, 1]);
+        assert_eq!(graph.client_costs(10), (Money::from_cents(20_000), Money::from_cents(5_100)));
+        assert_eq!(graph.client_costs(11), (Money::ZERO, Money::ZERO));
+        assert!(graph.has_client(11));
+        assert!(!graph.has_client(99));
+    }
+
+    #[test]
+    fn alerts_and_type_usages() {
+        let graph = graph();
+        let alerts: Vec<i32> = graph.material_alerts().iter().map(|m| m.id).collect();
+        assert_eq!(alerts, vec![101]);
+        let usages = graph.material_type_usages();
+        assert_eq!(usages.len(), 1);
+        assert_eq!(usages[0].material_count, 1);
+    }
+
+    #[test]
+    fn calendar_marks_the_month_today_and_the_selection() {
+        let days = calendar_days(day(18), Some(day(20)), day(18));
+        assert_eq!(days.len(), 42);
+        // April 2024 starts on a Monday
+        assert_eq!(days[0].day, 1);
+        assert!(days[0].in_month);
+        assert!(days[17].today);
+        assert!(days[19].selected);
+        assert!(!days[41].in_month);
+    }
+}
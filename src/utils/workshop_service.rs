@@ -11,443 +11,1264 @@ use crate::models::project_material::ProjectMaterial;
 use crate::models::material_type::MaterialType;
 use crate::models::client::Client;
 use crate::models::error::Error;
+use crate::models::conflict::Conflict;
+use crate::models::resource::Resource;
+use crate::models::goods_receipt::{GoodsReceipt, Valuation};
+use crate::models::stock_movement::{MovementReason, StockMovement};
+use crate::models::supplier::{Supplier, SupplierMaterial};
+use crate::models::purchase_order::PurchaseOrder;
+use crate::utils::cache::{LocalCache, Operation, OutboxEntry};
+use crate::utils::config::Profile;
+use crate::utils::repository::{Fetched, Repository, Validator};
 
-const API_URL: &str = "http://justinrauch.myftp.org:8580";
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use tokio::sync::watch;
+
+// How long a single request may take and how long idle connections are kept open
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+const TCP_KEEPALIVE: Duration = Duration::from_secs(60);
 
 /*
-    All the endpoints for the API are as follows:
-
-    - `GET /Projects` - get all projects
-    - `GET /Projects/{id}` - get project by id
-    - `POST /Projects` - create project
-    - `PUT /Projects/{id}` - update project
-    - `DELETE /Projects/{id}` - delete project
-    - `GET /Materials` - get all materials
-    - `GET /Materials/{id}` - get material by id
-    - `POST /Materials` - create material
-    - `PUT /Materials/{id}` - update material
-    - `DELETE /Materials/{id}` - delete material
-    - `GET /MaterialTypes` - get all material types
-    - `GET /MaterialTypes/{id}` - get material type by id
-    - `POST /MaterialTypes` - create material type
-    - `PUT /MaterialTypes/{id}` - update material type
-    - `DELETE /MaterialTypes/{id}` - delete material type
-    - `GET /ProjectMaterials` - get all project materials
-    - `GET /ProjectMaterials/{id}` - get project material by id
-    - `POST /ProjectMaterials` - create project material
-    - `PUT /ProjectMaterials/{id}` - update project material
-    - `DELETE /ProjectMaterials/{id}` - delete project material
-    - `GET /Clients` - get all clients
-    - `GET /Clients/{id}` - get client by id
-    - `POST /Clients` - create client
-    - `PUT /Clients/{id}` - update client
-    - `DELETE /Clients/{id}` - delete client
+    Every model that implements Resource is available through `repository::<T>()`, see `utils/repository.rs` for the
+    endpoints. The collections of the API are:
+
+    - `/Projects`
+    - `/Materials`
+    - `/MaterialTypes`
+    - `/ProjectMaterials`
+    - `/Clients`
+    - `/StockMovements`
+    - `/GoodsReceipts`
+    - `/Suppliers`
+    - `/SupplierMaterials`
+    - `/PurchaseOrders`
 */
 
+// Whether the data comes from the local cache and how many changes still wait for the server
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SyncState {
+    // The time the shown data was loaded from the server, None while the server can be reached
+    pub offline_since: Option<DateTime<Local>>,
+    pub pending_changes: usize,
+}
+
+// A copy of every collection the API offers
+#[derive(Clone, Default)]
+pub struct Collections {
+    pub projects: Vec<Project>,
+    pub materials: Vec<Material>,
+    pub project_materials: Vec<ProjectMaterial>,
+    pub material_types: Vec<MaterialType>,
+    pub clients: Vec<Client>,
+}
+
+// Which collections are different from the last sync
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Changes {
+    pub projects: bool,
+    pub materials: bool,
+    pub project_materials: bool,
+    pub material_types: bool,
+    pub clients: bool,
+}
+
+impl Changes {
+    pub fn any(&self) -> bool {
+        self.projects || self.materials || self.project_materials || self.material_types || self.clients
+    }
+
+    // The materials of the warehouse show their type
+    pub fn materials_view(&self) -> bool {
+        self.materials || self.material_types
+    }
+
+    // A project shows its client and its materials
+    pub fn projects_view(&self) -> bool {
+        self.any()
+    }
+
+    // A client shows their projects
+    pub fn clients_view(&self) -> bool {
+        self.clients || self.projects
+    }
+}
+
+// How often every collection changed, a view built at the same revisions still shows the current data
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Revisions {
+    pub projects: u64,
+    pub materials: u64,
+    pub project_materials: u64,
+    pub material_types: u64,
+    pub clients: u64,
+}
+
+impl Revisions {
+    fn count(&mut self, changes: &Changes) {
+        self.projects += u64::from(changes.projects);
+        self.materials += u64::from(changes.materials);
+        self.project_materials += u64::from(changes.project_materials);
+        self.material_types += u64::from(changes.material_types);
+        self.clients += u64::from(changes.clients);
+    }
+
+    // The collections that changed since a view was built at the given revisions
+    pub fn changes_since(&self, shown: &Revisions) -> Changes {
+        Changes {
+            projects: self.projects != shown.projects,
+            materials: self.materials != shown.materials,
+            project_materials: self.project_materials != shown.project_materials,
+            material_types: self.material_types != shown.material_types,
+            clients: self.clients != shown.clients,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct WorkshopService {
+    // One client for the whole lifetime of the service so connections are reused
+    client: reqwest::Client,
+    api_url: String,
     pub projects: Vec<Project>,
     pub materials: Vec<Material>,
     pub project_materials: Vec<ProjectMaterial>,
     pub material_types: Vec<MaterialType>,
     pub clients: Vec<Client>,
+    // The version of every collection above, keyed by `Resource::PATH`
+    validators: HashMap<&'static str, Validator>,
+    revisions: Revisions,
+    cache: LocalCache,
+    state: Arc<watch::Sender<SyncState>>,
 }
 
 impl WorkshopService {
-    pub fn new() -> WorkshopService {
-        WorkshopService {
+    pub fn new(profile: &Profile) -> WorkshopService {
+        WorkshopService::with_cache(profile, LocalCache::new())
+    }
+
+    fn with_cache(profile: &Profile, cache: LocalCache) -> WorkshopService {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+            .tcp_keepalive(TCP_KEEPALIVE)
+            .build()
+            .unwrap_or_default();
+        let service = WorkshopService {
+            client,
+            api_url: profile.api_url.trim_end_matches('/').to_string(),
             projects: Vec::new(),
             materials: Vec::new(),
             project_materials: Vec::new(),
             material_types: Vec::new(),
             clients: Vec::new(),
+            validators: HashMap::new(),
+            revisions: Revisions::default(),
+            cache,
+            state: Arc::new(watch::channel(SyncState::default()).0),
+        };
+        service.count_pending_changes();
+        service
+    }
+
+    // Switch to the API of another profile
+    pub fn set_profile(&mut self, profile: &Profile) {
+        self.api_url = profile.api_url.trim_end_matches('/').to_string();
+        // The collections of the old server say nothing about the new one
+        self.validators.clear();
+        self.count_pending_changes();
+    }
+
+    // A typed client for one collection of the API, sharing the connections of the service
+    pub fn repository<T: Resource>(&self) -> Repository<T> {
+        Repository::new(self.client.clone(), &self.api_url)
+    }
+
+    // Follows whether the data comes from the local cache and how many changes wait for the server
+    pub fn sync_state(&self) -> watch::Receiver<SyncState> {
+        self.state.subscribe()
+    }
+
+    // Get all items of a collection, if the server can not be reached they come from the local cache
+    pub async fn get_all<T: Resource>(&self, token: &str) -> Result<Vec<T>, Error> {
+        match self.repository::<T>().get_all(token).await {
+            Ok(items) => {
+                // The data is still correct without the copy, so a broken cache only gets logged
+                if let Err(e) = self.cache.store(&self.api_url, &items) {
+                    eprintln!("Failed to update the local cache: {}", e);
+                }
+                self.set_online();
+                Ok(items)
+            }
+            Err(e @ (Error::Network(_) | Error::Timeout)) => self.load_cached(e),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn load_cached<T: Resource>(&self, error: Error) -> Result<Vec<T>, Error> {
+        match self.cache.load::<T>(&self.api_url) {
+            Ok(Some(cached)) => {
+                self.set_offline(cached.loaded_at);
+                Ok(cached.items)
+            }
+            // Without a copy the error of the connection is more helpful
+            _ => Err(error),
+        }
+    }
+
+    // Get all items of a collection unless it is still the version that was loaded last, None if it did not change
+    async fn fetch<T: Resource>(&self, token: &str) -> Result<Option<(Vec<T>, Validator)>, Error> {
+        let validator = self.validators.get(T::PATH).cloned().unwrap_or_default();
+        match self.repository::<T>().get_all_if_changed(token, &validator).await {
+            Ok(Fetched::NotModified) => {
+                self.set_online();
+                Ok(None)
+            }
+            Ok(Fetched::Changed(items, validator)) => {
+                if let Err(e) = self.cache.store(&self.api_url, &items) {
+                    eprintln!("Failed to update the local cache: {}", e);
+                }
+                self.set_online();
+                Ok(Some((items, validator)))
+            }
+            // The copy of the cache has no version, the next sync gets everything again
+            Err(e @ (Error::Network(_) | Error::Timeout)) => {
+                self.load_cached(e).map(|items| Some((items, Validator::default())))
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    // Get one item of a collection, if the server can not be reached it comes from the local cache
+    pub async fn get_by_id<T: Resource>(&self, id: i32, token: &str) -> Result<T, Error> {
+        match self.repository::<T>().get_by_id(id, token).await {
+            Ok(item) => {
+                self.set_online();
+                Ok(item)
+            }
+            Err(e @ (Error::Network(_) | Error::Timeout)) => match self.cache.load::<T>(&self.api_url) {
+                Ok(Some(cached)) => {
+                    let item = cached
+                        .items
+                        .into_iter()
+                        .find(|item| item.id() == id)
+                        .ok_or(Error::NotFound(format!("{} {} is not in the local cache", T::PATH, id)))?;
+                    self.set_offline(cached.loaded_at);
+                    Ok(item)
+                }
+                _ => Err(e),
+            },
+            Err(e) => Err(e),
+        }
+    }
+
+    // Create an item, without a connection it gets a temporary id and waits in the outbox
+    pub async fn create<T: Resource>(&self, item: &T, token: &str) -> Result<T, Error> {
+        match self.repository::<T>().create(item, token).await {
+            Ok(created) => {
+                self.set_online();
+                Ok(created)
+            }
+            // If the change can not even be kept, the error of the connection is more helpful
+            Err(e @ (Error::Network(_) | Error::Timeout)) => self.queue_create(item).map_err(|_| e),
+            Err(e) => Err(e),
+        }
+    }
+
+    // Update an item, without a connection the change waits in the outbox
+    pub async fn update<T: Resource>(&self, item: &T, token: &str) -> Result<(), Error> {
+        match self.repository::<T>().update(item, token).await {
+            Ok(()) => {
+                self.set_online();
+                Ok(())
+            }
+            Err(e @ (Error::Network(_) | Error::Timeout)) => {
+                self.queue_change(Operation::Update, item.id(), Some(item)).map_err(|_| e)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    // Delete an item, without a connection the deletion waits in the outbox
+    pub async fn delete<T: Resource>(&self, id: i32, token: &str) -> Result<(), Error> {
+        match self.repository::<T>().delete(id, token).await {
+            Ok(()) => {
+                self.set_online();
+                Ok(())
+            }
+            Err(e @ (Error::Network(_) | Error::Timeout)) => {
+                self.queue_change::<T>(Operation::Delete, id, None).map_err(|_| e)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    fn queue_create<T: Resource>(&self, item: &T) -> Result<T, Error> {
+        let id = self.cache.temporary_id::<T>(&self.api_url)?;
+        let item = with_id(item, id)?;
+        self.cache.enqueue(&self.api_url, Operation::Create, id, Some(&item), None)?;
+        // The views show the new item right away
+        self.cache.modify::<T>(&self.api_url, |items| items.push(item.clone()))?;
+        self.went_offline::<T>();
+        Ok(item)
+    }
+
+    // `item` is the changed item, None for a delete
+    fn queue_change<T: Resource>(&self, operation: Operation, id: i32, item: Option<&T>) -> Result<(), Error> {
+        let cached = self.cache.load::<T>(&self.api_url)?.map(|c| c.items).unwrap_or_default();
+        // The copy the user changed, an item that was created offline has nothing on the server to compare with
+        let base = cached.iter().find(|c| c.id() == id && id > 0);
+        self.cache.enqueue(&self.api_url, operation, id, item, base)?;
+        self.cache.modify::<T>(&self.api_url, |items| {
+            items.retain(|c| c.id() != id);
+            items.extend(item.cloned());
+        })?;
+        self.went_offline::<T>();
+        Ok(())
+    }
+
+    // Send the changes of the outbox in the order they were made, stops at the first conflict with the server
+    pub async fn replay_outbox(&self, token: &str) -> Result<Option<Conflict>, Error> {
+        // The outbox is read again for every entry because a create changes the ids of the later entries
+        while let Some(entry) = self.cache.outbox(&self.api_url)?.into_iter().next() {
+            let conflict = match entry.resource.as_str() {
+                Project::PATH => self.replay::<Project>(&entry, token).await?,
+                Material::PATH => self.replay::<Material>(&entry, token).await?,
+                ProjectMaterial::PATH => self.replay::<ProjectMaterial>(&entry, token).await?,
+                MaterialType::PATH => self.replay::<MaterialType>(&entry, token).await?,
+                Client::PATH => self.replay::<Client>(&entry, token).await?,
+                StockMovement::PATH => self.replay::<StockMovement>(&entry, token).await?,
+                GoodsReceipt::PATH => self.replay::<GoodsReceipt>(&entry, token).await?,
+                Supplier::PATH => self.replay::<Supplier>(&entry, token).await?,
+                SupplierMaterial::PATH => self.replay::<SupplierMaterial>(&entry, token).await?,
+                PurchaseOrder::PATH => self.replay::<PurchaseOrder>(&entry, token).await?,
+                resource => return Err(Error::Cache(format!("Unknown collection {} in the outbox", resource))),
+            };
+            if conflict.is_some() {
+                return Ok(conflict);
+            }
+            self.cache.remove_entry(entry.id)?;
+            self.count_pending_changes();
+        }
+        Ok(None)
+    }
+
+    async fn replay<T: Resource>(&self, entry: &OutboxEntry, token: &str) -> Result<Option<Conflict>, Error> {
+        let repository = self.repository::<T>();
+        let mine: Option<T> = parse(entry.data.as_deref())?;
+        if entry.operation == Operation::Create {
+            let mine = mine.ok_or(Error::Cache("A create in the outbox has no item".to_string()))?;
+            let created = repository.create(&mine, token).await?;
+            self.cache.replace_id(&self.api_url, &entry.resource, entry.item_id, created.id())?;
+            return Ok(None);
+        }
+
+        // The copy of the server decides if the change still fits
+        let server = match repository.get_by_id(entry.item_id, token).await {
+            Ok(item) => Some(item),
+            Err(Error::NotFound(_)) => None,
+            Err(e) => return Err(e),
+        };
+        let base: Option<serde_json::Value> = parse(entry.base.as_deref())?;
+        let mine_json = to_json(mine.as_ref())?;
+        let server_json = to_json(server.as_ref())?;
+        // Without a base the item was created offline and nobody else can have changed it
+        let changed = base.is_some() && server_json.is_some() && base != server_json;
+        let conflict = Conflict {
+            entry_id: entry.id,
+            resource: entry.resource.clone(),
+            item_id: entry.item_id,
+            operation: entry.operation,
+            mine: mine_json.clone(),
+            server: server_json.clone(),
+        };
+
+        match (entry.operation, mine) {
+            // Someone else already made the same change
+            (Operation::Update, Some(_)) if mine_json == server_json => Ok(None),
+            (Operation::Update, Some(_)) if server_json.is_none() || changed => Ok(Some(conflict)),
+            (Operation::Update, Some(mine)) => repository.update(&mine, token).await.map(|_| None),
+            // Already gone on the server
+            (Operation::Delete, _) if server_json.is_none() => Ok(None),
+            (Operation::Delete, _) if changed => Ok(Some(conflict)),
+            (Operation::Delete, _) => repository.delete(entry.item_id, token).await.map(|_| None),
+            _ => Err(Error::Cache(format!("Invalid entry {} in the outbox", entry.id))),
+        }
+    }
+
+    // Settle a conflict, either the change of the client wins or the copy of the server stays
+    pub async fn resolve_conflict(&self, conflict: &Conflict, keep_mine: bool, token: &str) -> Result<(), Error> {
+        if keep_mine {
+            match conflict.resource.as_str() {
+                Project::PATH => self.force::<Project>(conflict, token).await?,
+                Material::PATH => self.force::<Material>(conflict, token).await?,
+                ProjectMaterial::PATH => self.force::<ProjectMaterial>(conflict, token).await?,
+                MaterialType::PATH => self.force::<MaterialType>(conflict, token).await?,
+                Client::PATH => self.force::<Client>(conflict, token).await?,
+                StockMovement::PATH => self.force::<StockMovement>(conflict, token).await?,
+                GoodsReceipt::PATH => self.force::<GoodsReceipt>(conflict, token).await?,
+                Supplier::PATH => self.force::<Supplier>(conflict, token).await?,
+                SupplierMaterial::PATH => self.force::<SupplierMaterial>(conflict, token).await?,
+                PurchaseOrder::PATH => self.force::<PurchaseOrder>(conflict, token).await?,
+                resource => return Err(Error::Cache(format!("Unknown collection {} in the outbox", resource))),
+            }
+        }
+        self.cache.remove_entry(conflict.entry_id)?;
+        self.count_pending_changes();
+        Ok(())
+    }
+
+    // Send the change of the client no matter what happened on the server
+    async fn force<T: Resource>(&self, conflict: &Conflict, token: &str) -> Result<(), Error> {
+        let repository = self.repository::<T>();
+        let mine: Option<T> = conflict
+            .mine
+            .clone()
+            .map(serde_json::from_value)
+            .transpose()
+            .map_err(|e| Error::Cache(e.to_string()))?;
+        match (conflict.operation, mine, &conflict.server) {
+            (Operation::Update, Some(mine), Some(_)) => repository.update(&mine, token).await,
+            // The item was deleted on the server, so it is created again and gets a new id
+            (Operation::Update, Some(mine), None) => {
+                let created = repository.create(&mine, token).await?;
+                self.cache.replace_id(&self.api_url, &conflict.resource, conflict.item_id, created.id())
+            }
+            (Operation::Delete, _, Some(_)) => repository.delete(conflict.item_id, token).await,
+            _ => Ok(()),
+        }
+    }
+
+    // Change the stock of a material and record why, a stock below zero is refused
+    pub async fn book_movement(
+        &self,
+        material_id: i32,
+        delta: i32,
+        reason: MovementReason,
+        project_id: Option<i32>,
+        user: &str,
+        token: &str,
+    ) -> Result<Material, Error> {
+        // The current stock, someone else may have taken material in the meantime
+        let mut material = self.get_by_id::<Material>(material_id, token).await?;
+        if material.amount + delta < 0 {
+            return Err(Error::InsufficientStock {
+                material: material.name,
+                available: material.amount,
+            });
+        }
+        material.amount += delta;
+        self.update(&material, token).await?;
+        if let Err(e) = self.record_movement(material_id, delta, reason, project_id, user, token).await {
+            // A stock without its movement would not add up with the ledger, so the old amount is put back
+            material.amount -= delta;
+            return Err(match self.update(&material, token).await {
+                Ok(()) => e,
+                Err(restore) => not_restored(e, restore),
+            });
+        }
+        Ok(material)
+    }
+
+    // Save a material of the form. A different amount than before is a correction of the counted stock, the material
+    // is only changed if the correction is recorded as well.
+    pub async fn save_material(&self, material: &Material, user: &str, token: &str) -> Result<(), Error> {
+        if material.id == 0 {
+            let created = self.create(material, token).await?;
+            // The first stock of a new material starts its ledger
+            if material.amount == 0 {
+                return Ok(());
+            }
+            let reason = MovementReason::Correction;
+            if let Err(e) = self.record_movement(created.id, material.amount, reason, None, user, token).await {
+                return Err(match self.delete::<Material>(created.id, token).await {
+                    Ok(()) => e,
+                    Err(restore) => not_restored(e, restore),
+                });
+            }
+            return Ok(());
+        }
+
+        let before = self.get_by_id::<Material>(material.id, token).await?;
+        self.update(material, token).await?;
+        let delta = material.amount - before.amount;
+        if delta == 0 {
+            return Ok(());
+        }
+        if let Err(e) = self.record_movement(material.id, delta, MovementReason::Correction, None, user, token).await {
+            return Err(match self.update(&before, token).await {
+                Ok(()) => e,
+                Err(restore) => not_restored(e, restore),
+            });
+        }
+        Ok(())
+    }
+
+    // Take a material off a project. What the project took goes back to the warehouse first, so a failed return
+    // leaves the material on the project instead of losing the stock. Reserved amounts were never taken and only
+    // disappear with the row.
+    pub async fn remove_project_material(
+        &self,
+        project_material: &ProjectMaterial,
+        user: &str,
+        token: &str,
+    ) -> Result<(), Error> {
+        let material_id = project_material.material_id;
+        let project_id = Some(project_material.project_id);
+        let amount = project_material.amount;
+        if amount != 0 {
+            self.book_movement(material_id, amount, MovementReason::Return, project_id, user, token).await?;
+        }
+        if let Err(e) = self.delete::<ProjectMaterial>(project_material.id, token).await {
+            // The row is still there, so the material counts as taken again
+            if amount != 0 {
+                let reason = MovementReason::Consumption;
+                if let Err(restore) = self.book_movement(material_id, -amount, reason, project_id, user, token).await {
+                    return Err(not_restored(e, restore));
+                }
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    // Delete a project together with its materials, what the project took goes back to the warehouse
+    pub async fn delete_project(&self, project_id: i32, user: &str, token: &str) -> Result<(), Error> {
+        let project_materials = self.get_all::<ProjectMaterial>(token).await?;
+        for project_material in project_materials.iter().filter(|m| m.project_id == project_id) {
+            self.remove_project_material(project_material, user, token).await?;
+        }
+        self.delete::<Project>(project_id, token).await
+    }
+
+    // Keep the receipt and book every line of the delivery into the warehouse, the costs follow the valuation. The
+    // receipt is recorded first, if a later line is refused everything booked so far is taken back.
+    pub async fn receive_goods(
+        &self,
+        receipt: &GoodsReceipt,
+        valuation: Valuation,
+        token: &str,
+    ) -> Result<GoodsReceipt, Error> {
+        let mut booking = Booking::default();
+        match self.book_receipt(receipt, valuation, &mut booking, token).await {
+            Ok(receipt) => Ok(receipt),
+            Err(e) => {
+                self.undo(booking, token).await;
+                Err(e)
+            }
+        }
+    }
+
+    async fn book_receipt(
+        &self,
+        receipt: &GoodsReceipt,
+        valuation: Valuation,
+        booking: &mut Booking,
+        token: &str,
+    ) -> Result<GoodsReceipt, Error> {
+        let recorded = self.create(receipt, token).await?;
+        booking.receipt_id = Some(recorded.id);
+        for line in &receipt.lines {
+            let before = self.get_by_id::<Material>(line.material_id, token).await?;
+            let mut material = before.clone();
+            material.costs = valuation.unit_costs(material.amount, material.costs, line.quantity, line.unit_price);
+            material.amount += line.quantity;
+            self.update(&material, token).await?;
+            booking.lines.push((before, line.quantity, None));
+            let movement = self
+                .record_movement(line.material_id, line.quantity, MovementReason::Delivery, None, &receipt.user, token)
+                .await?;
+            if let Some(booked) = booking.lines.last_mut() {
+                booked.2 = Some(movement.id);
+            }
+        }
+        Ok(recorded)
+    }
+
+    // Take back a booking that could not be finished, the last line first. A failure here is only logged, the error
+    // that stopped the booking is the one the user has to see.
+    async fn undo(&self, booking: Booking, token: &str) {
+        for (before, quantity, movement_id) in booking.lines.into_iter().rev() {
+            // Someone else may have changed the stock in the meantime, so only the delivered amount is taken out again
+            let result = async {
+                let mut material = self.get_by_id::<Material>(before.id, token).await?;
+                material.amount -= quantity;
+                material.costs = before.costs;
+                self.update(&material, token).await?;
+                if let Some(movement_id) = movement_id {
+                    self.delete::<StockMovement>(movement_id, token).await?;
+                }
+                Ok::<_, Error>(())
+            };
+            if let Err(e) = result.await {
+                eprintln!("Failed to take back the delivery of material {}: {}", before.id, e);
+            }
+        }
+        if let Some(receipt_id) = booking.receipt_id {
+            if let Err(e) = self.delete::<GoodsReceipt>(receipt_id, token).await {
+                eprintln!("Failed to delete the goods receipt {}: {}", receipt_id, e);
+            }
+        }
+    }
+
+    // Book a delivery against an order, the order is checked before the stock changes and the delivery is taken back
+    // if the order can not be updated
+    pub async fn receive_order(
+        &self,
+        receipt: &GoodsReceipt,
+        valuation: Valuation,
+        token: &str,
+    ) -> Result<PurchaseOrder, Error> {
+        let order_id = receipt
+            .purchase_order_id
+            .ok_or(Error::NotFound("The receipt belongs to no order".to_string()))?;
+        let mut order = self.get_by_id::<PurchaseOrder>(order_id, token).await?;
+        order.receive(&receipt.lines)?;
+        // The delivery only counts if the order shows it as well
+        let mut booking = Booking::default();
+        let result = async {
+            self.book_receipt(receipt, valuation, &mut booking, token).await?;
+            self.update(&order, token).await
+        };
+        if let Err(e) = result.await {
+            self.undo(booking, token).await;
+            return Err(e);
         }
+        Ok(order)
+    }
+
+    // Only add a movement to the ledger, for a stock that was already changed or to reconcile the ledger with it
+    pub async fn record_movement(
+        &self,
+        material_id: i32,
+        delta: i32,
+        reason: MovementReason,
+        project_id: Option<i32>,
+        user: &str,
+        token: &str,
+    ) -> Result<StockMovement, Error> {
+        let movement = StockMovement {
+            id: 0, // We can ignore it because the database will set it
+            material_id,
+            delta,
+            reason,
+            project_id,
+            user: user.to_string(),
+            timestamp: Local::now().to_rfc3339(),
+        };
+        self.create(&movement, token).await
+    }
+
+    fn count_pending_changes(&self) {
+        let pending_changes = self.cache.outbox(&self.api_url).map(|o| o.len()).unwrap_or(0);
+        self.state.send_if_modified(|state| {
+            let changed = state.pending_changes != pending_changes;
+            state.pending_changes = pending_changes;
+            changed
+        });
+    }
+
+    fn set_online(&self) {
+        self.state.send_if_modified(|state| state.offline_since.take().is_some());
+    }
+
+    // Several collections can come from the cache, the oldest one decides how old the shown data is
+    fn set_offline(&self, loaded_at: DateTime<Local>) {
+        self.state.send_if_modified(|state| match state.offline_since {
+            Some(since) if since <= loaded_at => false,
+            _ => {
+                state.offline_since = Some(loaded_at);
+                true
+            }
+        });
+    }
+
+    // A change went to the outbox, the shown data is as old as the copy of its collection
+    fn went_offline<T: Resource>(&self) {
+        let loaded_at = match self.cache.load::<T>(&self.api_url) {
+            Ok(Some(cached)) => cached.loaded_at,
+            _ => Local::now(),
+        };
+        self.set_offline(loaded_at);
+        self.count_pending_changes();
+    }
+
+    // Get all collections and keep them in the service
+    pub async fn load_all(&mut self, token: &str) -> Result<Collections, Error> {
+        self.sync(token).await.map(|(collections, _)| collections)
+    }
+
+    // Get all collections at the same time, the server only sends the ones that changed since the last sync
+    pub async fn sync(&mut self, token: &str) -> Result<(Collections, Changes), Error> {
+        let (projects, materials, project_materials, material_types, clients) = tokio::try_join!(
+            self.fetch::<Project>(token),
+            self.fetch::<Material>(token),
+            self.fetch::<ProjectMaterial>(token),
+            self.fetch::<MaterialType>(token),
+            self.fetch::<Client>(token),
+        )?;
+
+        let changes = Changes {
+            projects: apply(&mut self.validators, &mut self.projects, projects),
+            materials: apply(&mut self.validators, &mut self.materials, materials),
+            project_materials: apply(&mut self.validators, &mut self.project_materials, project_materials),
+            material_types: apply(&mut self.validators, &mut self.material_types, material_types),
+            clients: apply(&mut self.validators, &mut self.clients, clients),
+        };
+        self.revisions.count(&changes);
+        Ok((self.collections(), changes))
+    }
+
+    pub fn revisions(&self) -> Revisions {
+        self.revisions
+    }
+
+    fn collections(&self) -> Collections {
+        Collections {
+            projects: self.projects.clone(),
+            materials: self.materials.clone(),
+            project_materials: self.project_materials.clone(),
+            material_types: self.material_types.clone(),
+            clients: self.clients.clone(),
+        }
+    }
+}
+
+// What a delivery changed so far, so it can be taken back
+#[derive(Default)]
+struct Booking {
+    receipt_id: Option<i32>,
+    // The material before the delivery, the delivered quantity and the movement that recorded it
+    lines: Vec<(Material, i32, Option<i32>)>,
+}
+
+// Keep a fetched collection, returns true if its items are different from the ones that were kept before
+fn apply<T: Resource>(
+    validators: &mut HashMap<&'static str, Validator>,
+    items: &mut Vec<T>,
+    fetched: Option<(Vec<T>, Validator)>,
+) -> bool {
+    let Some((fetched, validator)) = fetched else {
+        return false;
+    };
+    validators.insert(T::PATH, validator);
+    // A server without ETag or Last-Modified always sends everything, so the items are compared as well
+    if *items == fetched {
+        return false;
+    }
+    *items = fetched;
+    true
+}
+
+// The item with another id, the models only share the id through their JSON
+fn with_id<T: Resource>(item: &T, id: i32) -> Result<T, Error> {
+    let mut json = serde_json::to_value(item).map_err(|e| Error::Cache(e.to_string()))?;
+    json["id"] = id.into();
+    serde_json::from_value(json).map_err(|e| Error::Cache(e.to_string()))
+}
+
+fn parse<T: serde::de::DeserializeOwned>(json: Option<&str>) -> Result<Option<T>, Error> {
+    json.map(serde_json::from_str)
+        .transpose()
+        .map_err(|e| Error::Cache(e.to_string()))
+}
+
+fn to_json<T: Resource>(item: Option<&T>) -> Result<Option<serde_json::Value>, Error> {
+    item.map(serde_json::to_value)
+        .transpose()
+        .map_err(|e| Error::Cache(e.to_string()))
+}
+
+// A change that failed and could not be taken back either, the user has to fix the stock by hand
+pub fn not_restored(error: Error, restore: Error) -> Error {
+    Error::Conflict(format!("{}; the stock could not be restored: {}", error, restore))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::atomic::{AtomicI32, Ordering};
+    use std::sync::Mutex;
+
+    use chrono::NaiveDate;
+    use serde_json::{json, Value};
+
+    use crate::models::goods_receipt::ReceiptLine;
+    use crate::models::money::Money;
+    use crate::models::purchase_order::{OrderLine, OrderStatus};
+    use crate::utils::mock_http;
+
+    const TOKEN: &str = "token";
+
+    // A request the mock API got
+    #[derive(Debug, Clone)]
+    struct Request {
+        method: String,
+        path: String,
+        body: Value,
     }
 
-    // Get all items endpoints
-    #[tokio::main]
-    pub async fn get_projects(&mut self, token: &str) -> Result<Vec<Project>, Error> {
-        let response = reqwest::Client::new()
-            .get(&format!("{}/Projects", API_URL))
-            .bearer_auth(token)
-            .send()
-            .await
-            .expect("Failed to get projects");
-
-        if response.status().is_success() {
-            self.projects = response.json().await.expect("Failed to parse projects");
-            Ok(self.projects.clone())
-        } else {
-            Err(Error::new(response.status().to_string(), 500))
-        }
-    }
+    type Requests = Arc<Mutex<Vec<Request>>>;
 
-    #[tokio::main]
-    pub async fn get_materials(&mut self, token: &str) -> Result<Vec<Material>, Error> {
-        let response = reqwest::Client::new()
-            .get(&format!("{}/Materials", API_URL))
-            .bearer_auth(token)
-            .send()
-            .await
-            .expect("Failed to get materials");
-
-        if response.status().is_success() {
-            self.materials = response.json().await.expect("Failed to parse materials");
-            Ok(self.materials.clone())
-        } else {
-            Err(Error::new(response.status().to_string(), 500))
-        }
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
     }
 
-    #[tokio::main]
-    pub async fn get_project_materials(&mut self, token: &str) -> Result<Vec<ProjectMaterial>, Error> {
-        let response = reqwest::Client::new()
-            .get(&format!("{}/ProjectMaterials", API_URL))
-            .bearer_auth(token)
-            .send()
-            .await
-            .expect("Failed to get project materials");
+    // An API on the loopback interface, `answer` returns the status and the body for every request
+    async fn mock_api(answer: impl Fn(&Request) -> (u16, Value) + Send + 'static) -> (String, Requests) {
+        let requests: Requests = Arc::default();
+        let recorded = requests.clone();
+        let (url, _) = mock_http::serve(move |request| {
+            let request = Request {
+                method: request.method.clone(),
+                path: request.path.clone(),
+                body: serde_json::from_str(&request.body).unwrap_or(Value::Null),
+            };
+            let (status, body) = answer(&request);
+            recorded.lock().unwrap().push(request);
+            (status, body.to_string())
+        })
+        .await;
+        (url, requests)
+    }
 
-        if response.status().is_success() {
-            self.project_materials = response.json().await.expect("Failed to parse project materials");
-            Ok(self.project_materials.clone())
-        } else {
-            Err(Error::new(response.status().to_string(), 500))
+    // The API gives every created item the next id starting at `first_id` and accepts everything else
+    fn creates_with_ids(first_id: i32) -> impl Fn(&Request) -> (u16, Value) + Send + 'static {
+        let next_id = AtomicI32::new(first_id);
+        move |request| match request.method.as_str() {
+            "POST" => {
+                let mut item = request.body.clone();
+                item["id"] = next_id.fetch_add(1, Ordering::SeqCst).into();
+                (200, item)
+            }
+            _ => (200, Value::Null),
         }
     }
 
-    #[tokio::main]
-    pub async fn get_material_types(&mut self, token: &str) -> Result<Vec<MaterialType>, Error> {
-        let response = reqwest::Client::new()
-            .get(&format!("{}/MaterialTypes", API_URL))
-            .bearer_auth(token)
-            .send()
-            .await
-            .expect("Failed to get material types");
-
-        if response.status().is_success() {
-            self.material_types = response.json().await.expect("Failed to parse material types");
-            Ok(self.material_types.clone())
-        } else {
-            Err(Error::new(response.status().to_string(), 500))
+    // The items the mock API knows, keyed by their path like `/Materials/1`
+    type Stock = Arc<Mutex<HashMap<String, Value>>>;
+
+    // An API that keeps the items in `stock` and refuses every request to `refused` with a conflict
+    fn warehouse(stock: Stock, refused: &'static str) -> impl Fn(&Request) -> (u16, Value) + Send + 'static {
+        let creates = creates_with_ids(50);
+        move |request| {
+            if format!("{} {}", request.method, request.path) == refused {
+                return (409, json!("refused"));
+            }
+            let known = stock.lock().unwrap().contains_key(&request.path);
+            match (request.method.as_str(), known) {
+                ("GET", true) => (200, stock.lock().unwrap()[&request.path].clone()),
+                ("GET", false) => (404, json!("not found")),
+                ("PUT", true) => {
+                    stock.lock().unwrap().insert(request.path.clone(), request.body.clone());
+                    (200, Value::Null)
+                }
+                ("DELETE", true) => {
+                    stock.lock().unwrap().remove(&request.path);
+                    (200, Value::Null)
+                }
+                _ => creates(request),
+            }
         }
     }
 
-    #[tokio::main]
-    pub async fn get_clients(&mut self, token: &str) -> Result<Vec<Client>, Error> {
-        let response = reqwest::Client::new()
-            .get(&format!("{}/Clients", API_URL))
-            .bearer_auth(token)
-            .send()
-            .await
-            .expect("Failed to get clients");
-
-        if response.status().is_success() {
-            self.clients = response.json().await.expect("Failed to parse clients");
-            Ok(self.clients.clone())
-        } else {
-            Err(Error::new(response.status().to_string(), 500))
+    fn stock<T: Resource>(items: &[T]) -> Stock {
+        let items = items
+            .iter()
+            .map(|item| (format!("/{}/{}", T::PATH, item.id()), serde_json::to_value(item).unwrap()))
+            .collect();
+        Arc::new(Mutex::new(items))
+    }
+
+    fn stocked(stock: &Stock, material_id: i32) -> Material {
+        serde_json::from_value(stock.lock().unwrap()[&format!("/Materials/{}", material_id)].clone()).unwrap()
+    }
+
+    fn receipt(lines: &[(i32, i32)]) -> GoodsReceipt {
+        GoodsReceipt {
+            id: 0,
+            supplier: "Hafele".to_string(),
+            delivery_note: "LS-1".to_string(),
+            date: NaiveDate::from_ymd_opt(2024, 4, 18).unwrap(),
+            user: "anna".to_string(),
+            lines: lines
+                .iter()
+                .map(|&(material_id, quantity)| ReceiptLine {
+                    material_id,
+                    quantity,
+                    unit_price: Money::from_cents(300),
+                })
+                .collect(),
+            purchase_order_id: None,
         }
     }
 
-    // Get item by id endpoints
-    #[tokio::main]
-    pub async fn get_project_by_id(&self, id: i32, token: &str) -> Result<Project, Error> {
-        let response = reqwest::Client::new()
-            .get(&format!("{}/Projects/{}", API_URL, id))
-            .bearer_auth(token)
-            .send()
-            .await
-            .expect("Failed to get project by id");
-
-        if response.status().is_success() {
-            Ok(response.json().await.expect("Failed to parse project"))
-        } else {
-            Err(Error::new(response.status().to_string(), 500))
-        }
-    }
-
-    #[tokio::main]
-    pub async fn get_material_by_id(&self, id: i32, token: &str) -> Result<Material, Error> {
-        let response = reqwest::Client::new()
-            .get(&format!("{}/Materials/{}", API_URL, id))
-            .bearer_auth(token)
-            .send()
-            .await
-            .expect("Failed to get material by id");
-
-        if response.status().is_success() {
-            Ok(response.json().await.expect("Failed to parse material"))
-        } else {
-            Err(Error::new(response.status().to_string(), 500))
-        }
-    }
-
-    #[tokio::main]
-    pub async fn get_project_material_by_id(&self, id: i32, token: &str) -> Result<ProjectMaterial, Error> {
-        let response = reqwest::Client::new()
-            .get(&format!("{}/ProjectMaterials/{}", API_URL, id))
-            .bearer_auth(token)
-            .send()
-            .await
-            .expect("Failed to get project material by id");
-
-        if response.status().is_success() {
-            Ok(response.json().await.expect("Failed to parse project material"))
-        } else {
-            Err(Error::new(response.status().to_string(), 500))
-        }
-    }
-
-    #[tokio::main]
-    pub async fn get_material_type_by_id(&self, id: i32, token: &str) -> Result<MaterialType, Error> {
-        let response = reqwest::Client::new()
-            .get(&format!("{}/MaterialTypes/{}", API_URL, id))
-            .bearer_auth(token)
-            .send()
-            .await
-            .expect("Failed to get material type by id");
-
-        if response.status().is_success() {
-            Ok(response.json().await.expect("Failed to parse material type"))
-        } else {
-            Err(Error::new(response.status().to_string(), 500))
-        }
-    }
-
-    #[tokio::main]
-    pub async fn get_client_by_id(&self, id: i32, token: &str) -> Result<Client, Error> {
-        let response = reqwest::Client::new()
-            .get(&format!("{}/Clients/{}", API_URL, id))
-            .bearer_auth(token)
-            .send()
-            .await
-            .expect("Failed to get client by id");
-
-        if response.status().is_success() {
-            Ok(response.json().await.expect("Failed to parse client"))
-        } else {
-            Err(Error::new(response.status().to_string(), 500))
-        }
-    }
-
-    // Create item endpoints
-    #[tokio::main]
-    pub async fn create_project(&mut self, project: Project, token: &str) -> Result<(), Error> {
-        let response = reqwest::Client::new()
-            .post(&format!("{}/Projects", API_URL))
-            .bearer_auth(token)
-            .json(&project)
-            .send()
-            .await
-            .expect("Failed to create project");
-
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            Err(Error::new(response.status().to_string(), 500))
-        }
-    }
-
-    #[tokio::main]
-    pub async fn create_material(&mut self, material: Material, token: &str) -> Result<(), Error> {
-        let response = reqwest::Client::new()
-            .post(&format!("{}/Materials", API_URL))
-            .bearer_auth(token)
-            .json(&material)
-            .send()
-            .await
-            .expect("Failed to create material");
-
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            Err(Error::new(response.status().to_string(), 500))
-        }
-    }
-
-    #[tokio::main]
-    pub async fn create_project_material(&mut self, project_material: ProjectMaterial, token: &str) -> Result<(), Error> {
-        let response = reqwest::Client::new()
-            .post(&format!("{}/ProjectMaterials", API_URL))
-            .bearer_auth(token)
-            .json(&project_material)
-            .send()
-            .await
-            .expect("Failed to create project material");
-
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            Err(Error::new(response.status().to_string(), 500))
-        }
-    }
-
-    #[tokio::main]
-    pub async fn create_client(&mut self, client: Client, token: &str) -> Result<(), Error> {
-        let response = reqwest::Client::new()
-            .post(&format!("{}/Clients", API_URL))
-            .bearer_auth(token)
-            .json(&client)
-            .send()
-            .await
-            .expect("Failed to create client");
-
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            Err(Error::new(response.status().to_string(), 500))
-        }
-    }
-
-    #[tokio::main]
-    pub async fn create_material_type(&mut self, material_type: MaterialType, token: &str) -> Result<(), Error> {
-        let response = reqwest::Client::new()
-            .post(&format!("{}/MaterialTypes", API_URL))
-            .bearer_auth(token)
-            .json(&material_type)
-            .send()
-            .await
-            .expect("Failed to create material type");
-
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            Err(Error::new(response.status().to_string(), 500))
-        }
-    }
-
-    // Update item endpoints
-    #[tokio::main]
-    pub async fn update_project(&mut self, project: Project, token: &str) -> Result<(), Error> {
-        let response = reqwest::Client::new()
-            .put(&format!("{}/Projects/{}", API_URL, project.id))
-            .bearer_auth(token)
-            .json(&project)
-            .send()
-            .await
-            .expect("Failed to update project");
-
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            Err(Error::new(response.status().to_string(), 500))
-        }
-    }
-
-    #[tokio::main]
-    pub async fn update_material(&mut self, material: Material, token: &str) -> Result<(), Error> {
-        let response = reqwest::Client::new()
-            .put(&format!("{}/Materials/{}", API_URL, material.id))
-            .bearer_auth(token)
-            .json(&material)
-            .send()
-            .await
-            .expect("Failed to update material");
-
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            Err(Error::new(response.status().to_string(), 500))
-        }
-    }
-
-    #[tokio::main]
-    pub async fn update_project_material(&mut self, project_material: ProjectMaterial, token: &str) -> Result<(), Error> {
-        let response = reqwest::Client::new()
-            .put(&format!("{}/ProjectMaterials/{}", API_URL, project_material.id))
-            .bearer_auth(token)
-            .json(&project_material)
-            .send()
-            .await
-            .expect("Failed to update project material");
-
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            Err(Error::new(response.status().to_string(), 500))
-        }
-    }
-
-    #[tokio::main]
-    pub async fn update_client(&mut self, client: Client, token: &str) -> Result<(), Error> {
-        let response = reqwest::Client::new()
-            .put(&format!("{}/Clients/{}", API_URL, client.id))
-            .bearer_auth(token)
-            .json(&client)
-            .send()
-            .await
-            .expect("Failed to update client");
-
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            Err(Error::new(response.status().to_string(), 500))
-        }
-    }
-
-    // Delete item endpoints
-    #[tokio::main]
-    pub async fn delete_project(&mut self, id: i32, token: &str) -> Result<(), Error> {
-        let response = reqwest::Client::new()
-            .delete(&format!("{}/Projects/{}", API_URL, id))
-            .bearer_auth(token)
-            .send()
-            .await
-            .expect("Failed to delete project");
-
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            Err(Error::new(response.status().to_string(), 500))
-        }
-    }
-
-    #[tokio::main]
-    pub async fn delete_material(&mut self, id: i32, token: &str) -> Result<(), Error> {
-        let response = reqwest::Client::new()
-            .delete(&format!("{}/Materials/{}", API_URL, id))
-            .bearer_auth(token)
-            .send()
-            .await
-            .expect("Failed to delete material");
-
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            Err(Error::new(response.status().to_string(), 500))
-        }
-    }
-
-    #[tokio::main]
-    pub async fn delete_project_material(&mut self, id: i32, token: &str) -> Result<(), Error> {
-        let response = reqwest::Client::new()
-            .delete(&format!("{}/ProjectMaterials/{}", API_URL, id))
-            .bearer_auth(token)
-            .send()
-            .await
-            .expect("Failed to delete project material");
+    fn calls(requests: &Requests) -> Vec<String> {
+        requests.lock().unwrap().iter().map(|r| format!("{} {}", r.method, r.path)).collect()
+    }
+
+    // A service with its own cache file that is removed again when the test ends
+    struct TestService {
+        service: WorkshopService,
+        path: std::path::PathBuf,
+    }
 
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            Err(Error::new(response.status().to_string(), 500))
+    impl TestService {
+        fn new(name: &str, api_url: &str) -> TestService {
+            let path = std::env::temp_dir().join(format!("wms-service-{}-{}.sqlite", name, std::process::id()));
+            let _ = std::fs::remove_file(&path);
+            let profile = Profile {
+                api_url: api_url.to_string(),
+                ..Profile::default()
+            };
+            TestService {
+                service: WorkshopService::with_cache(&profile, LocalCache::at(path.clone())),
+                path,
+            }
         }
-    }
-
-    #[tokio::main]
-    pub async fn delete_client(&mut self, id: i32, token: &str) -> Result<(), Error> {
-        let response = reqwest::Client::new()
-            .delete(&format!("{}/Clients/{}", API_URL, id))
-            .bearer_auth(token)
-            .send()
-            .await
-            .expect("Failed to delete client");
+    }
 
-        if response.status().is_success() {
-            Ok(())
-        } else {
-            Err(Error::new(response.status().to_string(), 500))
+    impl Drop for TestService {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
         }
     }
+
+    fn project(id: i32) -> Project {
+        let day = NaiveDate::from_ymd_opt(2024, 4, 18).unwrap();
+        Project {
+            id,
+            name: "Kitchen".to_string(),
+            client_id: 3,
+            description: String::new(),
+            startpoint: Some(day),
+            endpoint: Some(day),
+            estimated_costs: Money::ZERO,
+            estimated_hours: 0.0,
+            costs: Money::ZERO,
+            status: Default::default(),
+        }
+    }
+
+    fn material(id: i32) -> Material {
+        Material {
+            id,
+            name: "Hinge".to_string(),
+            description: String::new(),
+            type_id: 1,
+            amount: 10,
+            costs: Money::from_cents(250),
+            threshold_value: 0,
+        }
+    }
+
+    #[test]
+    fn replay_sends_the_real_ids_of_offline_items() {
+        block_on(async {
+            let (api_url, requests) = mock_api(creates_with_ids(41)).await;
+            let test = TestService::new("replay", &api_url);
+            let service = &test.service;
+            let cache = &service.cache;
+
+            // A project and a material were created offline and the material was assigned to the project
+            cache.enqueue(&api_url, Operation::Create, -1, Some(&project(-1)), None).unwrap();
+            cache.enqueue(&api_url, Operation::Create, -1, Some(&material(-1)), None).unwrap();
+            let assignment = ProjectMaterial {
+                id: -1,
+                project_id: -1,
+                material_id: -1,
+                amount: 2,
+                reserved: 0,
+            };
+            cache.enqueue(&api_url, Operation::Create, -1, Some(&assignment), None).unwrap();
+            let movement = StockMovement {
+                id: -1,
+                material_id: -1,
+                delta: -2,
+                reason: MovementReason::Consumption,
+                project_id: Some(-1),
+                user: "anna".to_string(),
+                timestamp: String::new(),
+            };
+            cache.enqueue(&api_url, Operation::Create, -1, Some(&movement), None).unwrap();
+
+            assert!(service.replay_outbox(TOKEN).await.unwrap().is_none());
+            assert!(cache.outbox(&api_url).unwrap().is_empty());
+
+            let requests = requests.lock().unwrap().clone();
+            let paths: Vec<&str> = requests.iter().map(|r| r.path.as_str()).collect();
+            assert_eq!(paths, vec!["/Projects", "/Materials", "/ProjectMaterials", "/StockMovements"]);
+            // The project got 41 and the material 42 from the server
+            assert_eq!(requests[2].body["project_id"], json!(41));
+            assert_eq!(requests[2].body["material_id"], json!(42));
+            assert_eq!(requests[3].body["project_id"], json!(41));
+            assert_eq!(requests[3].body["material_id"], json!(42));
+        });
+    }
+
+    #[test]
+    fn sync_counts_a_revision_only_for_collections_that_changed() {
+        block_on(async {
+            let (api_url, _) = mock_api(|r: &Request| match r.path.as_str() {
+                "/Projects" => (200, json!([project(1)])),
+                _ => (200, json!([])),
+            })
+            .await;
+            let mut test = TestService::new("revisions", &api_url);
+
+            test.service.sync(TOKEN).await.unwrap();
+            let first = test.service.revisions();
+            assert_eq!(first.projects, 1);
+            assert_eq!(first.materials, 0);
+
+            // Nothing changed on the server, so a view built at the first revisions is still current
+            test.service.sync(TOKEN).await.unwrap();
+            assert!(!test.service.revisions().changes_since(&first).any());
+            assert!(Revisions::default().changes_since(&first).projects_view());
+            assert!(!Revisions::default().changes_since(&first).materials_view());
+        });
+    }
+
+    #[test]
+    fn receive_goods_records_the_receipt_and_books_every_line() {
+        block_on(async {
+            let stock = stock(&[material(1), material(2)]);
+            let (api_url, requests) = mock_api(warehouse(stock.clone(), "")).await;
+            let test = TestService::new("receive", &api_url);
+
+            let recorded = test.service.receive_goods(&receipt(&[(1, 5), (2, 10)]), Valuation::LastPrice, TOKEN).await;
+            assert_eq!(recorded.unwrap().id, 50);
+            assert_eq!(stocked(&stock, 1).amount, 15);
+            assert_eq!(stocked(&stock, 2).amount, 20);
+            assert_eq!(stocked(&stock, 2).costs, Money::from_cents(300));
+            assert_eq!(calls(&requests)[0], "POST /GoodsReceipts");
+        });
+    }
+
+    #[test]
+    fn receive_goods_takes_back_a_refused_delivery() {
+        block_on(async {
+            let stock = stock(&[material(1), material(2)]);
+            let (api_url, requests) = mock_api(warehouse(stock.clone(), "PUT /Materials/2")).await;
+            let test = TestService::new("refused", &api_url);
+
+            let result = test.service.receive_goods(&receipt(&[(1, 5), (2, 10)]), Valuation::LastPrice, TOKEN).await;
+            assert!(matches!(result, Err(Error::Conflict(_))));
+            // The first line went back to the stock and costs it had
+            assert_eq!(stocked(&stock, 1), material(1));
+            let calls = calls(&requests);
+            assert!(calls.contains(&"DELETE /StockMovements/51".to_string()));
+            assert_eq!(calls.last().unwrap(), "DELETE /GoodsReceipts/50");
+        });
+    }
+
+    #[test]
+    fn a_movement_the_server_refuses_puts_the_stock_back() {
+        block_on(async {
+            let stock = stock(&[material(1)]);
+            let (api_url, requests) = mock_api(warehouse(stock.clone(), "POST /StockMovements")).await;
+            let test = TestService::new("movement", &api_url);
+
+            let result = test.service.book_movement(1, -4, MovementReason::WriteOff, None, "anna", TOKEN).await;
+            assert!(matches!(result, Err(Error::Conflict(_))));
+            assert_eq!(stocked(&stock, 1), material(1));
+            assert_eq!(calls(&requests).last().unwrap(), "PUT /Materials/1");
+        });
+    }
+
+    #[test]
+    fn a_material_is_only_saved_with_its_correction() {
+        block_on(async {
+            let stock = stock(&[material(1)]);
+            let (api_url, requests) = mock_api(warehouse(stock.clone(), "POST /StockMovements")).await;
+            let test = TestService::new("save-material", &api_url);
+
+            // The counted stock of an existing material goes back to what it was
+            let counted = Material { amount: 7, ..material(1) };
+            assert!(test.service.save_material(&counted, "anna", TOKEN).await.is_err());
+            assert_eq!(stocked(&stock, 1), material(1));
+
+            // A new material with stock is deleted again
+            let result = test.service.save_material(&material(0), "anna", TOKEN).await;
+            assert!(matches!(result, Err(Error::Conflict(_))));
+            assert_eq!(calls(&requests).last().unwrap(), "DELETE /Materials/50");
+
+            // Nothing has to be recorded if the amount stays the same
+            let renamed = Material { name: "Hinge, brass".to_string(), ..material(1) };
+            test.service.save_material(&renamed, "anna", TOKEN).await.unwrap();
+            assert_eq!(stocked(&stock, 1), renamed);
+        });
+    }
+
+    #[test]
+    fn a_failed_removal_reports_a_return_that_could_not_be_taken_back() {
+        block_on(async {
+            let stock = stock(&[material(1)]);
+            let warehouse = warehouse(stock.clone(), "DELETE /ProjectMaterials/5");
+            // The return is recorded, the booking that takes it back is refused
+            let movements = AtomicI32::new(0);
+            let (api_url, _) = mock_api(move |request| {
+                let movement = format!("{} {}", request.method, request.path) == "POST /StockMovements";
+                match movement && movements.fetch_add(1, Ordering::SeqCst) > 0 {
+                    true => (409, json!("refused")),
+                    false => warehouse(request),
+                }
+            })
+            .await;
+            let test = TestService::new("remove", &api_url);
+            let taken = ProjectMaterial {
+                id: 5,
+                project_id: 3,
+                material_id: 1,
+                amount: 2,
+                reserved: 0,
+            };
+
+            let error = test.service.remove_project_material(&taken, "anna", TOKEN).await.unwrap_err();
+            assert!(error.to_string().contains("the stock could not be restored"), "{}", error);
+            assert_eq!(stocked(&stock, 1).amount, material(1).amount + 2);
+        });
+    }
+
+    #[test]
+    fn receive_order_takes_back_the_delivery_if_the_order_is_refused() {
+        block_on(async {
+            let stock = stock(&[material(1)]);
+            let (api_url, requests) = mock_api(warehouse(stock.clone(), "PUT /PurchaseOrders/7")).await;
+            let test = TestService::new("order", &api_url);
+            let order = PurchaseOrder {
+                id: 7,
+                supplier_id: 1,
+                status: OrderStatus::Sent,
+                order_date: None,
+                expected_date: NaiveDate::from_ymd_opt(2024, 4, 20).unwrap(),
+                user: "anna".to_string(),
+                lines: vec![OrderLine {
+                    material_id: 1,
+                    quantity: 5,
+                    received: 0,
+                    unit_price: Money::from_cents(300),
+                }],
+            };
+            stock.lock().unwrap().insert("/PurchaseOrders/7".to_string(), serde_json::to_value(&order).unwrap());
+            let mut delivery = receipt(&[(1, 5)]);
+            delivery.purchase_order_id = Some(7);
+
+            let result = test.service.receive_order(&delivery, Valuation::LastPrice, TOKEN).await;
+            assert!(matches!(result, Err(Error::Conflict(_))));
+            assert_eq!(stocked(&stock, 1), material(1));
+            assert_eq!(calls(&requests).last().unwrap(), "DELETE /GoodsReceipts/50");
+        });
+    }
+
+    // Project 41 was renamed offline
+    fn rename_offline(test: &TestService) {
+        let mine = Project { name: "Kitchen offline".to_string(), ..project(41) };
+        let api_url = &test.service.api_url;
+        test.service.cache.enqueue(api_url, Operation::Update, 41, Some(&mine), Some(&project(41))).unwrap();
+    }
+
+    #[test]
+    fn an_update_on_an_unchanged_item_is_sent() {
+        block_on(async {
+            let server = stock(&[project(41)]);
+            let (api_url, requests) = mock_api(warehouse(server.clone(), "")).await;
+            let test = TestService::new("update-unchanged", &api_url);
+            rename_offline(&test);
+
+            assert!(test.service.replay_outbox(TOKEN).await.unwrap().is_none());
+            assert_eq!(calls(&requests), vec!["GET /Projects/41", "PUT /Projects/41"]);
+            assert_eq!(server.lock().unwrap()["/Projects/41"]["name"], json!("Kitchen offline"));
+            assert!(test.service.cache.outbox(&api_url).unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn an_update_of_an_item_changed_on_the_server_is_a_conflict() {
+        block_on(async {
+            let theirs = Project { estimated_hours: 12.0, ..project(41) };
+            let (api_url, requests) = mock_api(warehouse(stock(&[theirs]), "")).await;
+            let test = TestService::new("update-changed", &api_url);
+            rename_offline(&test);
+
+            let conflict = test.service.replay_outbox(TOKEN).await.unwrap().unwrap();
+            assert_eq!(calls(&requests), vec!["GET /Projects/41"]);
+            assert_eq!(conflict.description(), "Project 41 was changed offline and on the server");
+            let fields: Vec<String> = conflict.differences().into_iter().map(|d| d.field).collect();
+            assert_eq!(fields, vec!["estimated_hours", "name"]);
+            // The change waits until the user decides
+            assert_eq!(test.service.cache.outbox(&api_url).unwrap().len(), 1);
+
+            test.service.resolve_conflict(&conflict, true, TOKEN).await.unwrap();
+            assert_eq!(calls(&requests).last().unwrap(), "PUT /Projects/41");
+            assert!(test.service.cache.outbox(&api_url).unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn an_update_the_server_already_has_is_dropped() {
+        block_on(async {
+            let theirs = Project { name: "Kitchen offline".to_string(), ..project(41) };
+            let (api_url, requests) = mock_api(warehouse(stock(&[theirs]), "")).await;
+            let test = TestService::new("update-same", &api_url);
+            rename_offline(&test);
+
+            assert!(test.service.replay_outbox(TOKEN).await.unwrap().is_none());
+            assert_eq!(calls(&requests), vec!["GET /Projects/41"]);
+            assert!(test.service.cache.outbox(&api_url).unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn an_update_of_an_item_deleted_on_the_server_is_created_again() {
+        block_on(async {
+            let (api_url, requests) = mock_api(warehouse(Arc::default(), "")).await;
+            let test = TestService::new("update-deleted", &api_url);
+            rename_offline(&test);
+
+            let conflict = test.service.replay_outbox(TOKEN).await.unwrap().unwrap();
+            assert_eq!(conflict.description(), "Project 41 was changed offline but deleted on the server meanwhile");
+            assert!(conflict.server.is_none());
+
+            test.service.resolve_conflict(&conflict, true, TOKEN).await.unwrap();
+            assert_eq!(calls(&requests), vec!["GET /Projects/41", "POST /Projects"]);
+            assert!(test.service.cache.outbox(&api_url).unwrap().is_empty());
+        });
+    }
+
+    #[test]
+    fn a_delete_checks_the_server_first() {
+        block_on(async {
+            let server = stock(&[Project { name: "Changed".to_string(), ..project(41) }, project(42)]);
+            let (api_url, requests) = mock_api(warehouse(server.clone(), "")).await;
+            let test = TestService::new("delete", &api_url);
+            let cache = &test.service.cache;
+            // 41 was changed on the server, 42 was not and 43 is gone already
+            for id in [41, 42, 43] {
+                cache.enqueue(&api_url, Operation::Delete, id, None, Some(&project(id))).unwrap();
+            }
+
+            let conflict = test.service.replay_outbox(TOKEN).await.unwrap().unwrap();
+            assert_eq!(conflict.description(), "Project 41 was deleted offline but changed on the server meanwhile");
+            // The server copy stays
+            test.service.resolve_conflict(&conflict, false, TOKEN).await.unwrap();
+            assert!(test.service.replay_outbox(TOKEN).await.unwrap().is_none());
+
+            assert_eq!(
+                calls(&requests),
+                vec!["GET /Projects/41", "GET /Projects/42", "DELETE /Projects/42", "GET /Projects/43"]
+            );
+            assert!(server.lock().unwrap().contains_key("/Projects/41"));
+            assert!(cache.outbox(&api_url).unwrap().is_empty());
+        });
+    }
 }